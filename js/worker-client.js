@@ -0,0 +1,91 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+// Page side of worker mode, for packages built with the `worker` feature.
+//
+// The worker script initializes the module and serves a context:
+//
+//     import init, { serve_worker } from "datafusion-wasm";
+//     await init();
+//     serve_worker();
+//
+// and the page talks to it through `DataFusionWorker`:
+//
+//     const df = new DataFusionWorker(new Worker("worker.js", { type: "module" }));
+//     await df.call("register_csv", "trips", "https://example.com/trips.csv");
+//     const table = await df.call("execute_sql", "SELECT count(*) FROM trips");
+
+export class DataFusionWorker {
+  constructor(worker) {
+    this.worker = worker;
+    this.nextId = 0;
+    this.pending = new Map();
+    worker.addEventListener("message", (event) => this.#receive(event.data));
+  }
+
+  // Call `method` of the worker's context with `args`. A `Uint8Array` argument that
+  // spans its whole buffer is transferred, so don't use it afterwards; views into a
+  // larger or shared buffer are copied. Resolves to the result; byte results arrive
+  // as a transferred `Uint8Array`.
+  call(method, ...args) {
+    const id = ++this.nextId;
+    const transfer = new Set();
+    args = args.map((arg) => {
+      if (!(arg instanceof Uint8Array)) {
+        return arg;
+      }
+      const bytes = ownsBuffer(arg) ? arg : arg.slice();
+      transfer.add(bytes.buffer);
+      return bytes;
+    });
+    return new Promise((resolve, reject) => {
+      this.pending.set(id, { resolve, reject });
+      this.worker.postMessage({ id, method, args }, [...transfer]);
+    });
+  }
+
+  terminate() {
+    this.worker.terminate();
+    for (const { reject } of this.pending.values()) {
+      reject(new Error("worker terminated"));
+    }
+    this.pending.clear();
+  }
+
+  #receive({ id, result, error }) {
+    const pending = this.pending.get(id);
+    if (!pending) {
+      return;
+    }
+    this.pending.delete(id);
+    if (error) {
+      const err = new Error(error.message);
+      err.name = error.name;
+      pending.reject(err);
+    } else {
+      pending.resolve(result);
+    }
+  }
+}
+
+// Whether transferring the buffer of `bytes` detaches nothing else: the view spans
+// all of it and it isn't a `SharedArrayBuffer`, which can't be transferred.
+function ownsBuffer(bytes) {
+  const shared =
+    typeof SharedArrayBuffer !== "undefined" && bytes.buffer instanceof SharedArrayBuffer;
+  return !shared && bytes.byteOffset === 0 && bytes.byteLength === bytes.buffer.byteLength;
+}
@@ -0,0 +1,123 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Results exported through the Arrow C Data Interface.
+//!
+//! The `ArrowSchema` / `ArrowArray` structs and their buffers live in this
+//! module's linear memory, at the addresses returned by [`ArrowCData`]. A
+//! consumer that can read that memory (exported as `memory()`) imports them
+//! without copying. The release callbacks are functions of this module, so the
+//! data stays owned here until `free()` is called.
+
+use arrow::array::{Array, StructArray};
+use arrow::datatypes::{DataType, SchemaRef};
+use arrow::ffi::{FFI_ArrowArray, FFI_ArrowSchema};
+use datafusion::arrow::array::RecordBatch;
+use wasm_bindgen::prelude::*;
+
+use crate::error::Result;
+
+/// A result as one C Data Interface struct array per batch, sharing one schema.
+#[wasm_bindgen]
+pub struct ArrowCData {
+    // boxed so the addresses handed out stay valid
+    schema: Box<FFI_ArrowSchema>,
+    #[allow(clippy::vec_box)]
+    arrays: Vec<Box<FFI_ArrowArray>>,
+}
+
+impl ArrowCData {
+    pub fn try_new(schema: &SchemaRef, record_batches: &[RecordBatch]) -> Result<Self> {
+        let data_type = DataType::Struct(schema.fields().clone());
+        let arrays = record_batches
+            .iter()
+            .map(|batch| {
+                let array = StructArray::from(batch.clone());
+                Box::new(FFI_ArrowArray::new(&array.to_data()))
+            })
+            .collect();
+        Ok(Self {
+            schema: Box::new(FFI_ArrowSchema::try_from(&data_type)?),
+            arrays,
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl ArrowCData {
+    /// Address of the `ArrowSchema` of the result, a struct with one child per column.
+    #[wasm_bindgen(getter)]
+    pub fn schema_address(&self) -> usize {
+        self.schema.as_ref() as *const FFI_ArrowSchema as usize
+    }
+
+    /// Addresses of the `ArrowArray` of each batch.
+    #[wasm_bindgen(getter)]
+    pub fn array_addresses(&self) -> Vec<usize> {
+        self.arrays
+            .iter()
+            .map(|array| array.as_ref() as *const FFI_ArrowArray as usize)
+            .collect()
+    }
+
+    /// The memory the addresses point into.
+    #[wasm_bindgen(getter)]
+    pub fn memory(&self) -> JsValue {
+        wasm_bindgen::memory()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+    use std::sync::Arc;
+
+    /// The batches of `data`, read back through the interface.
+    fn import(data: &mut ArrowCData) -> Vec<RecordBatch> {
+        data.arrays
+            .iter_mut()
+            .map(|array| {
+                let array = std::mem::replace(array.as_mut(), FFI_ArrowArray::empty());
+                let data = unsafe { arrow::ffi::from_ffi(array, &data.schema) }.unwrap();
+                RecordBatch::from(StructArray::from(data))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec![Some("a"), None])),
+            ],
+        )
+        .unwrap();
+
+        let mut data = ArrowCData::try_new(&schema, &[batch.clone(), batch.clone()]).unwrap();
+        assert_eq!(data.array_addresses().len(), 2);
+        let imported = import(&mut data);
+        assert_eq!(imported, vec![batch.clone(), batch]);
+    }
+}
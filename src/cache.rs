@@ -0,0 +1,325 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! In-memory LRU cache of byte ranges fetched from remote objects.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use chrono::{DateTime, Duration, Utc};
+use object_store::ObjectMeta;
+
+/// Default cache capacity, in bytes.
+pub const DEFAULT_CACHE_CAPACITY: usize = 64 * 1024 * 1024;
+/// Default time after which cached objects are revalidated against the remote.
+pub const DEFAULT_REVALIDATE_AFTER_SECS: i64 = 30;
+
+#[derive(Debug)]
+struct CachedRange {
+    range: Range<usize>,
+    data: Bytes,
+    last_used: u64,
+}
+
+#[derive(Debug)]
+struct CachedObject {
+    meta: ObjectMeta,
+    ranges: Vec<CachedRange>,
+    validated_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+struct CacheState {
+    capacity: usize,
+    used: usize,
+    tick: u64,
+    revalidate_after: Duration,
+    objects: HashMap<String, CachedObject>,
+    /// Key of the object of every cached range, by the range's `last_used`, which is
+    /// unique. The first entry is the least recently used range.
+    lru: BTreeMap<u64, String>,
+    hits: u64,
+    misses: u64,
+}
+
+/// Byte range cache shared by every store built from one registry.
+///
+/// Objects are keyed by their full URL. A lookup hits when any cached range
+/// of the object fully covers the requested one. Least recently used ranges
+/// are evicted once the total cached size exceeds the capacity.
+#[derive(Debug)]
+pub struct RangeCache {
+    state: Mutex<CacheState>,
+}
+
+impl Default for RangeCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl RangeCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(CacheState {
+                capacity,
+                used: 0,
+                tick: 0,
+                revalidate_after: Duration::seconds(DEFAULT_REVALIDATE_AFTER_SECS),
+                objects: HashMap::new(),
+                lru: BTreeMap::new(),
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    /// Whether anything can be cached, the capacity isn't 0.
+    pub fn is_enabled(&self) -> bool {
+        self.state.lock().unwrap().capacity > 0
+    }
+
+    /// Change the capacity. A capacity of 0 disables caching.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.capacity = capacity;
+        state.evict();
+    }
+
+    /// Change how long cached objects are trusted before being revalidated.
+    pub fn set_revalidate_after(&self, revalidate_after: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.revalidate_after = revalidate_after;
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.objects.clear();
+        state.lru.clear();
+        state.used = 0;
+    }
+
+    /// Metadata recorded for `key`, and whether it is due for revalidation.
+    pub fn meta(&self, key: &str) -> Option<(ObjectMeta, bool)> {
+        let state = self.state.lock().unwrap();
+        let object = state.objects.get(key)?;
+        let stale = Utc::now() - object.validated_at >= state.revalidate_after;
+        Some((object.meta.clone(), stale))
+    }
+
+    /// Record that the remote confirmed the cached copy of `key` is current.
+    pub fn mark_validated(&self, key: &str) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(object) = state.objects.get_mut(key) {
+            object.validated_at = Utc::now();
+        }
+    }
+
+    /// Drop everything cached for `key`.
+    pub fn invalidate(&self, key: &str) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(mut object) = state.objects.remove(key) {
+            state.forget(&mut object.ranges);
+        }
+    }
+
+    pub fn get(&self, key: &str, range: &Range<usize>) -> Option<Bytes> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+
+        let CacheState { objects, lru, .. } = &mut *state;
+        let found = objects.get_mut(key).and_then(|object| {
+            let cached = object.ranges.iter_mut().find(|cached| {
+                cached.range.start <= range.start && range.end <= cached.range.end
+            })?;
+            if let Some(key) = lru.remove(&cached.last_used) {
+                lru.insert(tick, key);
+            }
+            cached.last_used = tick;
+
+            let offset = cached.range.start;
+            Some(cached.data.slice(range.start - offset..range.end - offset))
+        });
+        match found {
+            Some(_) => state.hits += 1,
+            None => state.misses += 1,
+        }
+        found
+    }
+
+    /// Lookups that hit and missed since the cache was created.
+    pub fn hit_counts(&self) -> (u64, u64) {
+        let state = self.state.lock().unwrap();
+        (state.hits, state.misses)
+    }
+
+    pub fn insert(&self, key: &str, meta: ObjectMeta, range: Range<usize>, data: Bytes) {
+        let mut state = self.state.lock().unwrap();
+        if data.len() > state.capacity {
+            return;
+        }
+        state.tick += 1;
+        let tick = state.tick;
+
+        let object = state
+            .objects
+            .entry(key.to_string())
+            .or_insert_with(|| CachedObject {
+                meta: meta.clone(),
+                ranges: vec![],
+                validated_at: Utc::now(),
+            });
+        // the object changed remotely, everything cached for it is stale
+        let mut stale = vec![];
+        if object.meta.e_tag != meta.e_tag || object.meta.size != meta.size {
+            stale = std::mem::take(&mut object.ranges);
+            object.meta = meta;
+            object.validated_at = Utc::now();
+        }
+
+        state.used += data.len();
+        state.lru.insert(tick, key.to_string());
+        let object = state.objects.get_mut(key).unwrap();
+        object.ranges.push(CachedRange {
+            range,
+            data,
+            last_used: tick,
+        });
+        state.forget(&mut stale);
+        state.evict();
+    }
+}
+
+impl CacheState {
+    fn evict(&mut self) {
+        while self.used > self.capacity {
+            let Some((last_used, key)) = self.lru.pop_first() else {
+                break;
+            };
+
+            let object = self.objects.get_mut(&key).unwrap();
+            let index = object
+                .ranges
+                .iter()
+                .position(|cached| cached.last_used == last_used)
+                .unwrap();
+            let evicted = object.ranges.swap_remove(index);
+            self.used -= evicted.data.len();
+            if object.ranges.is_empty() {
+                self.objects.remove(&key);
+            }
+        }
+    }
+
+    /// Account for `ranges` being dropped from the cache.
+    fn forget(&mut self, ranges: &mut Vec<CachedRange>) {
+        for cached in ranges.drain(..) {
+            self.lru.remove(&cached.last_used);
+            self.used -= cached.data.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(size: usize) -> ObjectMeta {
+        ObjectMeta {
+            location: "file.parquet".into(),
+            last_modified: Default::default(),
+            size,
+            e_tag: Some("v1".to_string()),
+            version: None,
+        }
+    }
+
+    #[test]
+    fn test_hit_within_cached_range() {
+        let cache = RangeCache::new(1024);
+        cache.insert("a", meta(100), 10..20, Bytes::from_static(b"0123456789"));
+
+        assert_eq!(cache.get("a", &(12..15)).unwrap().as_ref(), b"234");
+        assert!(cache.get("a", &(15..25)).is_none());
+        assert!(cache.get("b", &(12..15)).is_none());
+        assert_eq!(cache.hit_counts(), (1, 2));
+    }
+
+    #[test]
+    fn test_evict_least_recently_used() {
+        let cache = RangeCache::new(20);
+        cache.insert("a", meta(100), 0..10, Bytes::from(vec![0; 10]));
+        cache.insert("b", meta(100), 0..10, Bytes::from(vec![0; 10]));
+        // touch "a" so "b" becomes the eviction candidate
+        cache.get("a", &(0..10)).unwrap();
+        cache.insert("c", meta(100), 0..10, Bytes::from(vec![0; 10]));
+
+        assert!(cache.get("a", &(0..10)).is_some());
+        assert!(cache.get("b", &(0..10)).is_none());
+        assert!(cache.get("c", &(0..10)).is_some());
+    }
+
+    #[test]
+    fn test_evict_ranges_of_one_object_in_order() {
+        let cache = RangeCache::new(30);
+        cache.insert("a", meta(100), 0..10, Bytes::from(vec![0; 10]));
+        cache.insert("a", meta(100), 10..20, Bytes::from(vec![0; 10]));
+        cache.insert("a", meta(100), 20..30, Bytes::from(vec![0; 10]));
+        cache.get("a", &(0..10)).unwrap();
+        cache.insert("b", meta(100), 0..10, Bytes::from(vec![0; 10]));
+
+        assert!(cache.get("a", &(10..20)).is_none());
+        assert!(cache.get("a", &(0..10)).is_some());
+        assert!(cache.get("a", &(20..30)).is_some());
+
+        // a changed object releases the space of its stale ranges
+        let mut changed = meta(100);
+        changed.e_tag = Some("v2".to_string());
+        cache.insert("a", changed, 40..50, Bytes::from(vec![0; 10]));
+        assert!(cache.get("b", &(0..10)).is_some());
+        assert!(cache.get("a", &(40..50)).is_some());
+    }
+
+    #[test]
+    fn test_changed_object_drops_stale_ranges() {
+        let cache = RangeCache::new(1024);
+        cache.insert("a", meta(100), 0..10, Bytes::from(vec![0; 10]));
+
+        let mut changed = meta(100);
+        changed.e_tag = Some("v2".to_string());
+        cache.insert("a", changed, 20..30, Bytes::from(vec![0; 10]));
+
+        assert!(cache.get("a", &(0..10)).is_none());
+        assert!(cache.get("a", &(20..30)).is_some());
+    }
+
+    #[test]
+    fn test_revalidation_due() {
+        let cache = RangeCache::new(1024);
+        cache.insert("a", meta(100), 0..10, Bytes::from(vec![0; 10]));
+        assert!(!cache.meta("a").unwrap().1);
+
+        cache.set_revalidate_after(Duration::zero());
+        assert!(cache.meta("a").unwrap().1);
+
+        cache.invalidate("a");
+        assert!(cache.meta("a").is_none());
+    }
+}
@@ -0,0 +1,88 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Session-wide policy for casts that fail.
+
+use std::sync::{Arc, Mutex};
+
+use datafusion::arrow::compute::CastOptions;
+use datafusion::common::config::ConfigOptions;
+use datafusion::common::tree_node::{Transformed, TreeNode};
+use datafusion::logical_expr::expr::{Cast, TryCast};
+use datafusion::logical_expr::{Expr, LogicalPlan};
+use datafusion::optimizer::AnalyzerRule;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CastPolicy {
+    /// A value that can't be cast fails the query or ingest (fail fast).
+    #[default]
+    Error,
+    /// A value that can't be cast becomes null.
+    Null,
+}
+
+impl CastPolicy {
+    pub fn cast_options(&self) -> CastOptions<'static> {
+        CastOptions {
+            safe: *self == CastPolicy::Null,
+            ..Default::default()
+        }
+    }
+}
+
+/// Rewrites every `CAST`, including those added by type coercion, into
+/// `TRY_CAST` while the policy is [`CastPolicy::Null`].
+#[derive(Debug, Default)]
+pub struct CastPolicyRule {
+    policy: Arc<Mutex<CastPolicy>>,
+}
+
+impl CastPolicyRule {
+    pub fn new(policy: Arc<Mutex<CastPolicy>>) -> Self {
+        Self { policy }
+    }
+}
+
+impl AnalyzerRule for CastPolicyRule {
+    fn analyze(
+        &self,
+        plan: LogicalPlan,
+        _config: &ConfigOptions,
+    ) -> datafusion::error::Result<LogicalPlan> {
+        if *self.policy.lock().unwrap() == CastPolicy::Error {
+            return Ok(plan);
+        }
+
+        plan.transform_up_with_subqueries(|plan| {
+            plan.map_expressions(|expr| {
+                expr.transform_up(|expr| match expr {
+                    Expr::Cast(Cast { expr, data_type }) => Ok(Transformed::yes(Expr::TryCast(
+                        TryCast::new(expr, data_type),
+                    ))),
+                    _ => Ok(Transformed::no(expr)),
+                })
+            })
+        })
+        .map(|transformed| transformed.data)
+    }
+
+    fn name(&self) -> &str {
+        "cast_policy"
+    }
+}
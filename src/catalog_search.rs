@@ -0,0 +1,177 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Fuzzy search over the tables and columns registered in a session.
+
+use datafusion::execution::context::SessionContext;
+use serde::Serialize;
+use tsify_next::Tsify;
+
+use crate::error::Result;
+
+const INFORMATION_SCHEMA: &str = "information_schema";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Tsify)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    Table,
+    Column,
+    /// A key or value of a column's metadata.
+    Metadata,
+}
+
+#[derive(Debug, Serialize, Tsify)]
+#[tsify(into_wasm_abi, missing_as_null)]
+pub struct CatalogMatch {
+    pub kind: MatchKind,
+    pub catalog: String,
+    pub schema: String,
+    pub table: String,
+    pub column: Option<String>,
+    /// The text that matched: a table or column name, or `key=value` metadata.
+    pub text: String,
+    /// Higher is better, from 1 (scattered letters) to 100 (exact match).
+    pub score: u32,
+}
+
+/// Up to `limit` tables, columns and column metadata matching `query`, best first.
+pub async fn search(ctx: &SessionContext, query: &str, limit: usize) -> Result<Vec<CatalogMatch>> {
+    let mut matches = Vec::new();
+    for catalog_name in ctx.catalog_names() {
+        let Some(catalog) = ctx.catalog(&catalog_name) else {
+            continue;
+        };
+        for schema_name in catalog.schema_names() {
+            if schema_name == INFORMATION_SCHEMA {
+                continue;
+            }
+            let Some(schema) = catalog.schema(&schema_name) else {
+                continue;
+            };
+            for table_name in schema.table_names() {
+                let mut push = |kind, column: Option<&str>, text: String| {
+                    if let Some(score) = score(query, &text) {
+                        matches.push(CatalogMatch {
+                            kind,
+                            catalog: catalog_name.clone(),
+                            schema: schema_name.clone(),
+                            table: table_name.clone(),
+                            column: column.map(str::to_string),
+                            text,
+                            score,
+                        });
+                    }
+                };
+                push(MatchKind::Table, None, table_name.clone());
+
+                let Some(table) = schema.table(&table_name).await? else {
+                    continue;
+                };
+                for field in table.schema().fields() {
+                    push(MatchKind::Column, Some(field.name()), field.name().clone());
+                    for (key, value) in field.metadata() {
+                        push(
+                            MatchKind::Metadata,
+                            Some(field.name()),
+                            format!("{key}={value}"),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| (a.kind as u8).cmp(&(b.kind as u8)))
+            .then_with(|| a.text.len().cmp(&b.text.len()))
+            .then_with(|| a.table.cmp(&b.table))
+    });
+    matches.truncate(limit);
+    Ok(matches)
+}
+
+/// How well `text` matches `query`, ignoring case: exact, prefix, substring, or
+/// all query characters in order with as few gaps as possible. `None` if the
+/// characters don't appear in order.
+fn score(query: &str, text: &str) -> Option<u32> {
+    let query = query.trim().to_lowercase();
+    let text = text.to_lowercase();
+    if query.is_empty() {
+        return None;
+    }
+    if text == query {
+        return Some(100);
+    }
+    if text.starts_with(&query) {
+        return Some(80);
+    }
+    if text.contains(&query) {
+        return Some(60);
+    }
+
+    // subsequence match, penalized by the letters skipped between matches
+    let mut query_chars = query.chars().peekable();
+    let mut gaps = 0;
+    let mut started = false;
+    for c in text.chars() {
+        match query_chars.peek() {
+            Some(&next) if next == c => {
+                started = true;
+                query_chars.next();
+            }
+            Some(_) if started => gaps += 1,
+            Some(_) => {}
+            None => break,
+        }
+    }
+    if query_chars.peek().is_some() {
+        return None;
+    }
+    Some(40u32.saturating_sub(gaps * 5).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        assert_eq!(score("orders", "Orders"), Some(100));
+        assert_eq!(score("ord", "orders"), Some(80));
+        assert_eq!(score("der", "orders"), Some(60));
+        assert!(score("odrs", "orders").unwrap() < 40);
+        assert!(score("ordr", "orders") > score("ordr", "o_r_d_r"));
+        assert_eq!(score("xyz", "orders"), None);
+        assert_eq!(score(" ", "orders"), None);
+    }
+
+    #[tokio::test]
+    async fn test_search() {
+        let ctx = SessionContext::new();
+        ctx.sql("CREATE TABLE customer_orders (order_id INT, customer_name VARCHAR)")
+            .await
+            .unwrap();
+
+        let matches = search(&ctx, "cust", 10).await.unwrap();
+        assert_eq!(matches[0].kind, MatchKind::Table);
+        assert_eq!(matches[0].table, "customer_orders");
+        assert_eq!(matches[1].column.as_deref(), Some("customer_name"));
+        assert_eq!(search(&ctx, "order", 1).await.unwrap().len(), 1);
+    }
+}
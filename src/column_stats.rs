@@ -0,0 +1,247 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Per-column statistics of a result, gathered batch by batch while it is
+//! collected: min, max, null count and a HyperLogLog distinct estimate.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use datafusion::arrow::array::{Array, RecordBatch};
+use datafusion::arrow::datatypes::{DataType, SchemaRef};
+use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
+use datafusion::common::ScalarValue;
+use datafusion::functions_aggregate::min_max::{MaxAccumulator, MinAccumulator};
+use datafusion::logical_expr::Accumulator;
+use serde::Serialize;
+use tsify_next::Tsify;
+
+use crate::error::Result;
+
+/// Registers of the distinct estimate, 2^12 for a standard error of about 1.6%.
+const SKETCH_BITS: u32 = 12;
+
+#[derive(Debug, Clone, Serialize, Tsify)]
+#[tsify(into_wasm_abi, missing_as_null)]
+pub struct ColumnStats {
+    pub name: String,
+    pub data_type: String,
+    /// Numbers for numeric columns, display strings otherwise, `null` when every
+    /// value is null or the type has no order.
+    #[tsify(type = "unknown")]
+    pub min: serde_json::Value,
+    #[tsify(type = "unknown")]
+    pub max: serde_json::Value,
+    pub null_count: usize,
+    pub distinct_estimate: u64,
+}
+
+struct ColumnCollector {
+    /// `None` for types without min / max, e.g. structs.
+    min: Option<MinAccumulator>,
+    max: Option<MaxAccumulator>,
+    null_count: usize,
+    sketch: HyperLogLog,
+}
+
+/// Gathers [`ColumnStats`] of the batches of one result.
+pub struct ColumnStatsCollector {
+    schema: SchemaRef,
+    columns: Vec<ColumnCollector>,
+}
+
+impl ColumnStatsCollector {
+    pub fn new(schema: SchemaRef) -> Self {
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|field| ColumnCollector {
+                min: MinAccumulator::try_new(field.data_type()).ok(),
+                max: MaxAccumulator::try_new(field.data_type()).ok(),
+                null_count: 0,
+                sketch: HyperLogLog::default(),
+            })
+            .collect();
+        Self { schema, columns }
+    }
+
+    pub fn update(&mut self, batch: &RecordBatch) -> Result<()> {
+        let format_options = FormatOptions::default();
+        for (collector, array) in self.columns.iter_mut().zip(batch.columns()) {
+            collector.null_count += array.null_count();
+            if collector
+                .min
+                .as_mut()
+                .is_some_and(|min| min.update_batch(std::slice::from_ref(array)).is_err())
+            {
+                collector.min = None;
+            }
+            if collector
+                .max
+                .as_mut()
+                .is_some_and(|max| max.update_batch(std::slice::from_ref(array)).is_err())
+            {
+                collector.max = None;
+            }
+
+            let formatter = ArrayFormatter::try_new(array.as_ref(), &format_options)?;
+            for index in (0..array.len()).filter(|index| array.is_valid(*index)) {
+                let mut hasher = DefaultHasher::new();
+                formatter.value(index).to_string().hash(&mut hasher);
+                collector.sketch.add(hasher.finish());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<Vec<ColumnStats>> {
+        self.schema
+            .fields()
+            .iter()
+            .zip(self.columns)
+            .map(|(field, mut collector)| {
+                let data_type = field.data_type();
+                let min = collector
+                    .min
+                    .as_mut()
+                    .map(|min| min.evaluate())
+                    .transpose()?;
+                let max = collector
+                    .max
+                    .as_mut()
+                    .map(|max| max.evaluate())
+                    .transpose()?;
+                Ok(ColumnStats {
+                    name: field.name().clone(),
+                    data_type: data_type.to_string(),
+                    min: to_json(min, data_type)?,
+                    max: to_json(max, data_type)?,
+                    null_count: collector.null_count,
+                    distinct_estimate: collector.sketch.estimate(),
+                })
+            })
+            .collect()
+    }
+}
+
+fn to_json(value: Option<ScalarValue>, data_type: &DataType) -> Result<serde_json::Value> {
+    let Some(value) = value.filter(|value| !value.is_null()) else {
+        return Ok(serde_json::Value::Null);
+    };
+    if data_type.is_numeric() {
+        if let ScalarValue::Float64(Some(number)) = value.cast_to(&DataType::Float64)? {
+            if let Some(number) = serde_json::Number::from_f64(number) {
+                return Ok(number.into());
+            }
+        }
+    }
+    Ok(value.to_string().into())
+}
+
+struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self {
+            registers: vec![0; 1 << SKETCH_BITS],
+        }
+    }
+}
+
+impl HyperLogLog {
+    fn add(&mut self, hash: u64) {
+        let index = (hash >> (64 - SKETCH_BITS)) as usize;
+        // the sentinel bit caps the rank when the remaining bits are all zero
+        let rest = (hash << SKETCH_BITS) | (1 << (SKETCH_BITS - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        self.registers[index] = self.registers[index].max(rank);
+    }
+
+    fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|rank| 2f64.powi(-(*rank as i32)))
+            .sum();
+        let estimate = 0.7213 / (1.0 + 1.079 / m) * m * m / sum;
+        let zeros = self.registers.iter().filter(|rank| **rank == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            // linear counting is more accurate for small cardinalities
+            return (m * (m / zeros as f64).ln()).round() as u64;
+        }
+        estimate.round() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Int64Array, StringArray};
+    use datafusion::arrow::datatypes::{Field, Schema};
+
+    use super::*;
+
+    #[test]
+    fn test_stats_across_batches() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("n", DataType::Int64, true),
+            Field::new("s", DataType::Utf8, true),
+        ]));
+        let batch = |n: Vec<Option<i64>>, s: Vec<Option<&str>>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(n)),
+                    Arc::new(StringArray::from(s)),
+                ],
+            )
+            .unwrap()
+        };
+
+        let mut collector = ColumnStatsCollector::new(schema.clone());
+        collector
+            .update(&batch(vec![Some(3), None], vec![Some("b"), Some("a")]))
+            .unwrap();
+        collector
+            .update(&batch(vec![Some(-1), Some(3)], vec![None, Some("a")]))
+            .unwrap();
+        let stats = collector.finish().unwrap();
+
+        assert_eq!(stats[0].min, serde_json::json!(-1.0));
+        assert_eq!(stats[0].max, serde_json::json!(3.0));
+        assert_eq!(stats[0].null_count, 1);
+        assert_eq!(stats[0].distinct_estimate, 2);
+        assert_eq!(stats[1].min, serde_json::json!("a"));
+        assert_eq!(stats[1].distinct_estimate, 2);
+    }
+
+    #[test]
+    fn test_distinct_estimate_is_close() {
+        let mut sketch = HyperLogLog::default();
+        for value in 0..100_000u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            sketch.add(hasher.finish());
+        }
+        let estimate = sketch.estimate() as f64;
+        assert!((estimate - 100_000.0).abs() < 5_000.0, "{estimate}");
+    }
+}
@@ -0,0 +1,109 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Detection of compressed CSV / NDJSON files from their name.
+
+use std::str::FromStr;
+
+use datafusion::common::parsers::CompressionTypeVariant;
+use datafusion::sql::parser::Statement;
+use datafusion::sql::sqlparser::ast::Value;
+
+use crate::error::{Result, WasmError};
+
+const COMPRESSION_OPTION: &str = "format.compression";
+
+/// The compression implied by the file extension of `location`, e.g. `.csv.gz`.
+pub fn detect(location: &str) -> Option<CompressionTypeVariant> {
+    let path = location.split(['?', '#']).next().unwrap_or(location);
+    let (_, extension) = path.rsplit_once('.')?;
+    match extension.to_ascii_lowercase().as_str() {
+        "gz" | "gzip" => Some(CompressionTypeVariant::GZIP),
+        "bz2" => Some(CompressionTypeVariant::BZIP2),
+        "xz" => Some(CompressionTypeVariant::XZ),
+        "zst" | "zstd" => Some(CompressionTypeVariant::ZSTD),
+        _ => None,
+    }
+}
+
+pub fn parse(name: &str) -> Result<CompressionTypeVariant> {
+    CompressionTypeVariant::from_str(name)
+        .map_err(|_| WasmError::Other(format!("unknown compression: {name}")))
+}
+
+/// Add `format.compression` to `CREATE EXTERNAL TABLE` statements over CSV / JSON
+/// files whose location names a compressed file, unless it is set explicitly.
+pub fn detect_in_statement(statement: &mut Statement) {
+    let Statement::CreateExternalTable(create) = statement else {
+        return;
+    };
+    if !matches!(
+        create.file_type.to_ascii_uppercase().as_str(),
+        "CSV" | "JSON" | "NDJSON"
+    ) {
+        return;
+    }
+    if create
+        .options
+        .iter()
+        .any(|(key, _)| key.eq_ignore_ascii_case(COMPRESSION_OPTION))
+    {
+        return;
+    }
+    if let Some(compression) = detect(&create.location) {
+        create.options.push((
+            COMPRESSION_OPTION.to_string(),
+            Value::SingleQuotedString(compression.to_string()),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::sql::parser::DFParser;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            detect("https://example.com/data.csv.gz?token=1"),
+            Some(CompressionTypeVariant::GZIP)
+        );
+        assert_eq!(
+            detect("s3://bucket/events.json.zst"),
+            Some(CompressionTypeVariant::ZSTD)
+        );
+        assert_eq!(detect("https://example.com/data.csv"), None);
+    }
+
+    #[test]
+    fn test_detect_in_statement() {
+        let sql = "CREATE EXTERNAL TABLE t STORED AS CSV LOCATION 'https://example.com/t.csv.gz'";
+        let mut statement = DFParser::parse_sql(sql).unwrap().pop_front().unwrap();
+        detect_in_statement(&mut statement);
+        let Statement::CreateExternalTable(create) = statement else {
+            unreachable!()
+        };
+        assert_eq!(
+            create.options,
+            vec![(
+                COMPRESSION_OPTION.to_string(),
+                Value::SingleQuotedString("GZIP".to_string())
+            )]
+        );
+    }
+}
@@ -15,10 +15,36 @@
 // specific language governing permissions and limitations
 // under the License.
 
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
-    pub fn log(s: &str);
+    pub fn error(s: &str);
+    #[wasm_bindgen(js_namespace = console)]
+    pub fn warn(s: &str);
+    #[wasm_bindgen(js_namespace = console)]
+    pub fn info(s: &str);
+    #[wasm_bindgen(js_namespace = console)]
+    pub fn debug(s: &str);
+}
+
+// native builds, such as the tests, have no console to call
+#[cfg(not(target_arch = "wasm32"))]
+pub fn error(s: &str) {
+    eprintln!("{s}");
+}
+#[cfg(not(target_arch = "wasm32"))]
+pub fn warn(s: &str) {
+    eprintln!("{s}");
+}
+#[cfg(not(target_arch = "wasm32"))]
+pub fn info(s: &str) {
+    eprintln!("{s}");
+}
+#[cfg(not(target_arch = "wasm32"))]
+pub fn debug(s: &str) {
+    eprintln!("{s}");
 }
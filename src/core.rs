@@ -15,27 +15,127 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use std::sync::Arc;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
-use datafusion::arrow::util::display::FormatOptions;
-use datafusion::arrow::util::pretty::pretty_format_batches_with_options;
+use chrono::{DateTime, Utc};
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes::DataType;
+use datafusion::dataframe::DataFrame;
+use datafusion::datasource::{MemTable, TableProvider, TableType};
 use datafusion::execution::context::{SessionConfig, SessionContext};
 use datafusion::execution::disk_manager::DiskManagerConfig;
 use datafusion::execution::runtime_env::RuntimeEnvBuilder;
-use datafusion::physical_plan::collect;
-use datafusion::sql::parser::DFParser;
+use datafusion::physical_plan::{execute_stream, ExecutionPlan};
+use datafusion::physical_planner::{DefaultPhysicalPlanner, PhysicalPlanner};
+use datafusion::sql::parser::{DFParser, Statement};
 use wasm_bindgen::prelude::*;
 
-use crate::console;
-use crate::error::Result;
+use crate::c_data::ArrowCData;
+use crate::cast_policy::{CastPolicy, CastPolicyRule};
+use crate::catalog_search::{self, CatalogMatch};
+use crate::column_stats::{ColumnStats, ColumnStatsCollector};
+use crate::compression;
+use crate::dataframe::{Rendering, WasmDataFrame};
+use crate::diagnostics::ParseReport;
+use crate::diff;
+use crate::duckdb;
+use crate::error::{Result, WasmError};
+use crate::events::{BatchEvent, EndEvent, EventKind, QueryEvents, StartEvent};
+use crate::execute_options::ExecuteOptions;
+use crate::extension;
+use crate::functions;
+#[cfg(feature = "parquet")]
+use crate::geoparquet::GeoParquetTable;
+use crate::info::{Capabilities, EngineInfo};
+use crate::ingest::{self, SchemaEvolution};
+use crate::ipc_input;
+use crate::js_rows;
+use crate::listing::{self, NdJsonFormatFactory, ReaderOptions, TableFormat, TableSchema};
+use crate::live_table::{self, LiveTable};
+use crate::logger::{self, LogLevel};
+use crate::metrics::{
+    self, MetricsSnapshot, MetricsTable, PruningStats, QueryRecord, QueryStats, SessionMetrics,
+};
+use crate::namespace::Namespaces;
 use crate::object_store::{OpendalRegistry, S3Config};
-use crate::ResultFormat;
+use crate::pages::Pages;
+#[cfg(feature = "parquet")]
+use crate::parquet_info::{self, ParquetInfo};
+use crate::parquet_reader::ParquetReaderOptions;
+#[cfg(feature = "parquet")]
+use crate::parquet_writer::{self, ParquetWriterOptions};
+use crate::perf_marks;
+use crate::plan_cache::{self, PlanCache, PlanCacheStats};
+use crate::pragma::Pragmas;
+use crate::preview::Preview;
+use crate::probe::ProbeReport;
+use crate::progress::{JsCallback, ProgressEvent, QueryProgress, QueryStage};
+use crate::queue::{QueryQueue, QueuedQuery};
+use crate::quota::StorageEstimate;
+use crate::readable_stream::{self, ChunkEncoder};
+use crate::repro::ReproBundle;
+use crate::resource_limits::{ResourceGuard, ResourceLimits};
+use crate::result_cache::{self, CachedResult, ResultCache};
+use crate::result_format::{DisplayOptions, RenderOptions, ResultInfo};
+use crate::row_ids::{self, ROW_ID_COLUMN};
+use crate::scheduling::{QueryPriority, Scheduler};
+use crate::segments::{IpcSegment, Segments};
+use crate::settings;
+use crate::sql_format;
+use crate::sql_options::SqlOptions;
+use crate::statement_filter::{StatementFilter, StatementInfo};
+#[cfg(any(feature = "csv", feature = "json"))]
+use crate::stream_ingest::{self, StreamDecoder};
+use crate::subscriptions::Subscriptions;
+use crate::table_stats::{self, StatisticsReport, StatisticsTable};
+use crate::trace::{self, SpanKind};
+use crate::unsafe_opendal_store::ReadConfig;
+use crate::warnings::{self, Warning, Warnings};
+use crate::{
+    result_renderer, result_renderer_names, IpcCompression, JsonNumbers, ResultFormat,
+    ResultRenderer,
+};
+
+/// Bytes read from the end of a Parquet file to get its footer and metadata.
+const PARQUET_METADATA_SIZE_HINT: usize = 64 * 1024;
 
 #[wasm_bindgen]
 pub struct DataFusionContext {
     session_context: Arc<SessionContext>,
     store_registry: OpendalRegistry,
     result_format: ResultFormat,
+    /// Custom renderer selected by name, takes precedence over `result_format`.
+    result_renderer: Option<Arc<dyn ResultRenderer>>,
+    render_options: RenderOptions,
+    schema_evolution: SchemaEvolution,
+    /// Shared with the [`CastPolicyRule`] installed in the session.
+    cast_policy: Arc<Mutex<CastPolicy>>,
+    segments: Segments,
+    pages: Pages,
+    namespaces: Namespaces,
+    scheduler: Scheduler,
+    queue: QueryQueue,
+    statement_filter: StatementFilter,
+    subscriptions: Subscriptions,
+    query_progress: QueryProgress,
+    query_events: QueryEvents,
+    metrics: Arc<SessionMetrics>,
+    /// Whether tables created by `append_csv` / `append_json` get a `_row_id` column.
+    row_ids: bool,
+    /// Rows kept of each statement's result, see `set_max_rows`.
+    max_rows: Option<usize>,
+    preview: Preview,
+    resource_limits: ResourceLimits,
+    last_result: Mutex<ResultInfo>,
+    /// Warnings of the last query or append.
+    warnings: Warnings,
+    /// Whether results get column statistics, see `set_column_stats`.
+    column_stats: bool,
+    last_column_stats: Mutex<Vec<ColumnStats>>,
+    last_stats: Mutex<QueryStats>,
+    result_cache: ResultCache,
+    plan_cache: PlanCache,
 }
 
 #[wasm_bindgen]
@@ -44,35 +144,411 @@ impl DataFusionContext {
         "hello from datafusion-wasm".to_string()
     }
 
-    pub fn new() -> Self {
+    /// Version and build information of this binary, e.g. for bug reports.
+    pub fn engine_info() -> EngineInfo {
+        EngineInfo::current()
+    }
+
+    /// Call `callback({message, stack})` when the engine panics, e.g. to report the crash.
+    /// A context that panicked is unusable: drop it and create a new one. `undefined`
+    /// removes the callback.
+    pub fn on_panic(callback: Option<js_sys::Function>) {
+        crate::panic_hook::set_callback(callback);
         crate::set_panic_hook();
+    }
 
-        // build opendal registry
-        let store_registry = OpendalRegistry::new();
+    /// Log engine messages up to `level`, `Info` by default.
+    pub fn set_log_level(level: LogLevel) {
+        logger::set_level(level);
+    }
 
-        let rt = Arc::new(
-            RuntimeEnvBuilder::new()
-                .with_disk_manager(DiskManagerConfig::Disabled)
-                .with_object_store_registry(Arc::new(store_registry.clone()))
-                .build()
-                .unwrap(),
-        );
-        let session_config = SessionConfig::new()
-            .with_target_partitions(1)
-            .with_information_schema(true);
-        let session_context = Arc::new(SessionContext::new_with_config_rt(session_config, rt));
+    /// Send engine logs to `callback(level, message)`, with `level` one of `"error"`,
+    /// `"warn"`, `"info"`, `"debug"` and `"trace"`, instead of the console. `undefined`
+    /// goes back to the console.
+    pub fn set_log_sink(callback: Option<js_sys::Function>) {
+        logger::set_sink(callback);
+    }
 
-        console::log("datafusion context is initialized");
+    /// Threading support of the page: `cross_origin_isolated`, `shared_array_buffer`
+    /// and `threads`, whether queries use more than one thread, plus the `runtime`
+    /// (`browser`, `node`, `deno`, `cloudflare-workers`) and the `apis` it offers.
+    pub fn capabilities() -> Capabilities {
+        Capabilities::detect()
+    }
 
-        Self {
-            session_context,
-            store_registry,
-            result_format: ResultFormat::Table,
+    pub fn new() -> Self {
+        Self::with_store_registry(OpendalRegistry::new())
+    }
+
+    /// A new context with its own tables, settings and metrics that shares this one's
+    /// object stores: the S3 configuration, credential callback, range cache and I/O
+    /// progress. Use one per tab or notebook cell without duplicating credentials and
+    /// cached data. Store settings changed in one session, including by `reset`,
+    /// apply to all of them.
+    pub fn new_session(&self) -> DataFusionContext {
+        Self::with_store_registry(self.store_registry.clone())
+    }
+
+    /// Drop every table, view, function, namespace and open cursor, forget the S3
+    /// configuration and credential callback, and start over with a fresh session.
+    /// Display settings are kept.
+    pub fn reset(&mut self) {
+        self.store_registry.reset_config();
+        self.session_context =
+            Self::build_session(&self.store_registry, &self.metrics, &self.cast_policy);
+        self.segments = Segments::default();
+        self.pages = Pages::default();
+        self.namespaces = Namespaces::default();
+        self.subscriptions.clear();
+        self.invalidate_all();
+        logger::info("datafusion context is reset");
+    }
+
+    /// Like `reset`, and also free the caches of remote data and the query history
+    /// right away instead of when the object is garbage collected.
+    pub fn close(&mut self) {
+        self.reset();
+        self.store_registry.clear_cache();
+        self.metrics.clear_history();
+    }
+
+    /// Run `sql` and render the result of each statement. A leading hint comment
+    /// opening with `/*+ tz('Europe/Berlin')` sets the session time zone for this call
+    /// only.
+    /// `SET datafusion.<option> = <value>` changes the option for the whole session and
+    /// returns its `name, value` row, `SHOW ALL` / `SHOW <option>` list options. Along
+    /// with a hint comment or `config` overrides, `SET` only lasts for the call, and it
+    /// is rejected in a `namespace`.
+    ///
+    /// `options` is an optional object (or its JSON text) with a `label` and `tags`
+    /// (string to string), recorded in `query_history` and `session_metrics`, a
+    /// `namespace` to run the query in, a `config` object of session options overridden
+    /// for this call only, e.g. `{"datafusion.execution.batch_size": 1024}`, and a
+    /// `priority`, `"interactive"` by default. Queued `"background"` queries wait for
+    /// every queued interactive one, and running ones are parked between batches while
+    /// an interactive query runs, so prefetching and metadata lookups don't delay
+    /// user-initiated work.
+    pub async fn execute_sql(
+        &self,
+        sql: String,
+        #[wasm_bindgen(unchecked_optional_param_type = "ExecuteOptions | string")] options: JsValue,
+    ) -> Result<String> {
+        let options: ExecuteOptions = serde_json::from_str(&options_json(&options)?)?;
+        self.execute_inner(sql, &options).await
+    }
+
+    /// Like `execute_sql`, but returns the raw bytes produced for the last statement.
+    /// Use this with binary renderers. The array owns its buffer, which can be transferred.
+    pub async fn execute_sql_bytes(&self, sql: String) -> Result<js_sys::Uint8Array> {
+        let mut results = self
+            .collect_statements(&sql, &ExecuteOptions::default(), self.active_preview())
+            .await?;
+        let record_batches = results.pop().unwrap_or_default();
+        Ok(js_sys::Uint8Array::from(
+            self.render(&record_batches)?.as_slice(),
+        ))
+    }
+
+    /// Run `sql` and return the rows of its last statement as JS objects. Int64 and UInt64
+    /// values are `BigInt`s, so they round-trip exactly; decimals are strings.
+    pub async fn execute_sql_rows(&self, sql: String) -> Result<js_sys::Array> {
+        let mut results = self
+            .collect_statements(&sql, &ExecuteOptions::default(), None)
+            .await?;
+        let record_batches = results.pop().unwrap_or_default();
+        js_rows::to_rows(
+            &record_batches,
+            &self.render_options.display.format_options(),
+        )
+    }
+
+    /// Run `sql` and return the result of its last statement as a sequence of Arrow IPC
+    /// streams of about `max_segment_bytes` each, so it never has to be held in one buffer.
+    /// Each segment decodes on its own; pass its `continuation` to
+    /// `next_ipc_segment` until it is `undefined`.
+    pub async fn execute_sql_ipc_segments(
+        &self,
+        sql: String,
+        max_segment_bytes: usize,
+    ) -> Result<IpcSegment> {
+        let physical_plan = self.plan_last_statement(&sql).await?;
+        let stream = execute_stream(physical_plan, self.session_context.task_ctx())?;
+        self.segments
+            .start(
+                stream,
+                max_segment_bytes,
+                self.render_options.ipc_compression,
+            )
+            .await
+    }
+
+    /// Run `sql` and read the result of its last statement `page_size` rows at a time.
+    /// Returns a cursor for `next_page`; the query only runs as far as pages are read.
+    pub async fn execute_sql_paged(&self, sql: String, page_size: usize) -> Result<String> {
+        let physical_plan = self.plan_last_statement(&sql).await?;
+        let stream = execute_stream(physical_plan, self.session_context.task_ctx())?;
+        self.pages.open(stream, page_size).await
+    }
+
+    /// The next page of `cursor` in the current result format, `undefined` once every
+    /// row was returned.
+    pub async fn next_page(&self, cursor: String) -> Result<Option<String>> {
+        match self.pages.next(&cursor).await? {
+            Some(record_batches) => Ok(Some(String::from_utf8(self.render(&record_batches)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Run `sql` and stream the result of its last statement as a `ReadableStream` of
+    /// `Uint8Array` chunks, as they are produced. `format` is `"ipc"`, an Arrow IPC stream
+    /// with the current IPC compression, or `"csv"`, lines with a header.
+    pub async fn execute_sql_readable_stream(
+        &self,
+        sql: String,
+        format: String,
+    ) -> Result<JsValue> {
+        let physical_plan = self.plan_last_statement(&sql).await?;
+        let encoder = ChunkEncoder::new(
+            &format,
+            &physical_plan.schema(),
+            self.render_options.ipc_compression,
+        )?;
+        let stream = execute_stream(physical_plan, self.session_context.task_ctx())?;
+        readable_stream::readable_stream(stream, encoder)
+    }
+
+    /// Stop a paged query early. Returns whether `cursor` was open.
+    pub async fn close_cursor(&self, cursor: String) -> bool {
+        self.pages.close(&cursor).await
+    }
+
+    /// Run `sql` and encode the result of its last statement as a Parquet file.
+    /// `options` is an optional JSON object with `compression` (e.g. `"zstd(3)"`),
+    /// `max_row_group_size`, `statistics` (`none`, `chunk` or `page`) and `dictionary`.
+    /// The array owns its buffer, which can be transferred.
+    #[cfg(feature = "parquet")]
+    pub async fn export_parquet(
+        &self,
+        sql: String,
+        options: Option<String>,
+    ) -> Result<js_sys::Uint8Array> {
+        let options = ParquetWriterOptions::from_json(options.as_deref())?;
+        let physical_plan = self.plan_last_statement(&sql).await?;
+        let schema = physical_plan.schema();
+        let record_batches = self
+            .scheduler
+            .collect(
+                physical_plan,
+                self.session_context.task_ctx(),
+                QueryPriority::Interactive,
+            )
+            .await?;
+        let data = parquet_writer::write_parquet(schema, &record_batches, &options)?;
+        Ok(js_sys::Uint8Array::from(data.as_slice()))
+    }
+
+    /// Run `sql` and export the result of its last statement through the Arrow C Data
+    /// Interface, for wasm code reading this module's memory. Call `free()` on the result
+    /// once the consumer is done with it.
+    pub async fn export_c_data(&self, sql: String) -> Result<ArrowCData> {
+        let physical_plan = self.plan_last_statement(&sql).await?;
+        let schema = physical_plan.schema();
+        let record_batches = self
+            .scheduler
+            .collect(
+                physical_plan,
+                self.session_context.task_ctx(),
+                QueryPriority::Interactive,
+            )
+            .await?;
+        ArrowCData::try_new(&schema, &record_batches)
+    }
+
+    /// Scan table `name` for its row count and the min, max and null count of every
+    /// column, which later queries give the optimizer, e.g. for join ordering. Same as
+    /// `ANALYZE TABLE name`. Returns them as `{rows, columns: [{name, null_count, min,
+    /// max}]}`. Re-registering the table drops them.
+    pub async fn analyze_table(&self, name: String) -> Result<StatisticsReport> {
+        self.analyze(&self.session_context, &name).await
+    }
+
+    /// Index the text in `column` of table `name` for full-text search with
+    /// `fts_match(column, query)`, e.g. `fts_match(body, 'wasm* "query engine" -java')`.
+    /// `match` is an alias, quoted as `"match"(...)` where the dialect reserves it.
+    /// Returns the number of new distinct documents indexed.
+    #[cfg(feature = "fts")]
+    pub async fn fts_index(&self, name: String, column: String) -> Result<usize> {
+        crate::fts::index_column(&self.session_context, &name, &column).await
+    }
+
+    /// Register every optional SQL function pack compiled into this build. Sessions get
+    /// them already; this restores any that were removed. Returns their feature names,
+    /// of `crypto`, `functions-json`, `geo` and `fts`.
+    pub fn register_all_optional_functions(&self) -> Vec<String> {
+        functions::register_optional(&self.session_context)
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Run `sql_a` and `sql_b`, which must return the same columns, and compare their
+    /// rows by `key_columns`. Returns JSON with `added` (only in `sql_b`), `removed` (only
+    /// in `sql_a`) and `changed`, `{before, after}` pairs of rows whose other values differ.
+    pub async fn diff_queries(
+        &self,
+        sql_a: String,
+        sql_b: String,
+        key_columns: Vec<String>,
+    ) -> Result<String> {
+        let mut results = Vec::with_capacity(2);
+        for sql in [&sql_a, &sql_b] {
+            let physical_plan = self.plan_last_statement(sql).await?;
+            let schema = physical_plan.schema();
+            let record_batches = self
+                .scheduler
+                .collect(
+                    physical_plan,
+                    self.session_context.task_ctx(),
+                    QueryPriority::Interactive,
+                )
+                .await?;
+            results.push((schema, record_batches));
+        }
+        let diff = diff::diff(
+            &results[0].0,
+            &results[0].1,
+            &results[1].0,
+            &results[1].1,
+            &key_columns,
+        )?;
+
+        let rows = |batch: &RecordBatch| -> Result<Vec<serde_json::Value>> {
+            if batch.num_rows() == 0 {
+                return Ok(vec![]);
+            }
+            let json = ResultFormat::Json
+                .render_with(std::slice::from_ref(batch), &self.render_options)?;
+            Ok(serde_json::from_slice(&json)?)
+        };
+        let changed = rows(&diff.changed_before)?
+            .into_iter()
+            .zip(rows(&diff.changed_after)?)
+            .map(|(before, after)| serde_json::json!({ "before": before, "after": after }))
+            .collect::<Vec<_>>();
+        Ok(serde_json::json!({
+            "added": rows(&diff.added)?,
+            "removed": rows(&diff.removed)?,
+            "changed": changed,
+        })
+        .to_string())
+    }
+
+    /// Run `sql` and write the result of its last statement as an Excel workbook with one
+    /// worksheet, e.g. for a download. Numbers and booleans keep their type.
+    #[cfg(feature = "xlsx")]
+    pub async fn execute_sql_xlsx(&self, sql: String) -> Result<js_sys::Uint8Array> {
+        let mut results = self
+            .collect_statements(&sql, &ExecuteOptions::default(), None)
+            .await?;
+        let record_batches = results.pop().unwrap_or_default();
+        let data = crate::result_format::write_xlsx(
+            &record_batches,
+            &self.render_options.display.format_options(),
+        )?;
+        Ok(js_sys::Uint8Array::from(data.as_slice()))
+    }
+
+    /// Run `sql` and encode the result of its last statement for DuckDB-WASM's
+    /// `insertArrowFromIPCStream`: an uncompressed Arrow IPC stream without view types.
+    pub async fn export_to_duckdb(&self, sql: String) -> Result<js_sys::Uint8Array> {
+        let physical_plan = self.plan_last_statement(&sql).await?;
+        let schema = physical_plan.schema();
+        let record_batches = self
+            .scheduler
+            .collect(
+                physical_plan,
+                self.session_context.task_ctx(),
+                QueryPriority::Interactive,
+            )
+            .await?;
+        Ok(js_sys::Uint8Array::from(
+            duckdb::to_ipc(&schema, &record_batches)?.as_slice(),
+        ))
+    }
+
+    /// Register a table exported by DuckDB-WASM, `tableToIPC(result, "stream")`, as
+    /// in-memory table `name`, replacing any table of that name.
+    pub async fn register_from_duckdb(&self, name: String, ipc_bytes: Vec<u8>) -> Result<()> {
+        let (schema, batches) = ipc_input::read_with_schema(&ipc_bytes)?;
+        let table = MemTable::try_new(schema, vec![batches])?;
+        self.invalidate_table(&name);
+        self.session_context.deregister_table(name.as_str())?;
+        self.session_context
+            .register_table(name.as_str(), Arc::new(table))?;
+        self.notify_subscriptions(&name).await;
+        Ok(())
+    }
+
+    /// Set the default Parquet writer options of `COPY ... STORED AS PARQUET`, as a JSON
+    /// object with the same keys as `export_parquet`. Statement `OPTIONS` take precedence.
+    #[cfg(feature = "parquet")]
+    pub fn set_parquet_writer_options(&self, options: String) -> Result<()> {
+        let options = ParquetWriterOptions::from_json(Some(&options))?;
+        options.apply(
+            &mut self
+                .session_context
+                .state_ref()
+                .write()
+                .table_options_mut()
+                .parquet
+                .global,
+        )
+    }
+
+    /// Tune Parquet scans, from an object (or its JSON text) with `pushdown_filters` (late
+    /// materialization), `reorder_filters`, `page_index`, `bloom_filter`, `pruning` and
+    /// `metadata_size_hint` (64 KiB by default). All are on by default; turning pushdown
+    /// off can help queries whose filters match most rows.
+    pub fn set_parquet_reader_options(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "ParquetReaderOptions | string")] options: JsValue,
+    ) -> Result<()> {
+        let options = ParquetReaderOptions::from_json(&options_json(&options)?)?;
+        {
+            let state = self.session_context.state_ref();
+            let mut state = state.write();
+            options.apply(&mut state.config_mut().options_mut().execution.parquet);
+            options.apply(&mut state.table_options_mut().parquet.global);
+        }
+        self.plan_cache.clear();
+        Ok(())
+    }
+
+    /// Tune the SQL parser, from an object (or its JSON text) with
+    /// `ident_normalization` (on by default; off, unquoted identifiers keep their case,
+    /// so case-sensitive Parquet columns don't need quotes), `options_value_normalization`
+    /// and `parse_float_as_decimal`.
+    pub fn set_sql_options(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "SqlOptions | string")] options: JsValue,
+    ) -> Result<()> {
+        let options = SqlOptions::from_json(&options_json(&options)?)?;
+        {
+            let state = self.session_context.state_ref();
+            let mut state = state.write();
+            options.apply(&mut state.config_mut().options_mut().sql_parser);
         }
+        self.invalidate_all();
+        Ok(())
+    }
+
+    pub async fn next_ipc_segment(&self, continuation: String) -> Result<IpcSegment> {
+        self.segments.next(&continuation).await
     }
 
-    pub async fn execute_sql(&self, sql: String) -> Result<String> {
-        self.execute_inner(sql).await
+    /// Release a segmented result that won't be read to the end.
+    pub async fn cancel_ipc_segments(&self, continuation: String) -> bool {
+        self.segments.cancel(&continuation).await
     }
 
     pub fn set_s3_config(
@@ -89,41 +565,1464 @@ impl DataFusionContext {
             region,
             access_key_id,
             secret_access_key,
+            ..Default::default()
         };
         self.store_registry.set_s3_config(s3_config);
     }
 
+    /// Set a callback `(url) => credentials` called when an S3 request is rejected with
+    /// 401 or 403, e.g. after a token expired. It returns or resolves to
+    /// `{access_key_id, secret_access_key, session_token?}`; the request is retried once
+    /// with the new credentials, which are kept for later requests.
+    pub fn set_credential_callback(&self, callback: Option<js_sys::Function>) {
+        self.store_registry.set_credential_callback(callback);
+    }
+
     pub fn set_result_format(&mut self, result_format: ResultFormat) {
         self.result_format = result_format;
+        self.result_renderer = None;
+    }
+
+    /// Select a custom renderer registered from Rust via `register_result_renderer`.
+    pub fn set_result_renderer(&mut self, name: String) -> Result<()> {
+        let renderer = result_renderer(&name)
+            .ok_or_else(|| WasmError::Other(format!("unknown result renderer: {name}")))?;
+        self.result_renderer = Some(renderer);
+        Ok(())
+    }
+
+    pub fn list_result_renderers() -> Vec<String> {
+        result_renderer_names()
+    }
+
+    /// Register Arrow extension type `name`, stored as any of `storage_types` (Arrow
+    /// type names such as `FixedSizeBinary(16)`), so it can be set on schema override
+    /// columns. `arrow.uuid`, `arrow.json`, `geoarrow.wkb` and `geoarrow.wkt` are built in.
+    pub fn register_extension_type(name: String, storage_types: Vec<String>) -> Result<()> {
+        let storage_types = storage_types
+            .iter()
+            .map(|data_type| {
+                DataType::from_str(data_type).map_err(|err| WasmError::Other(err.to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        extension::register_extension_type(name, storage_types);
+        Ok(())
+    }
+
+    pub fn list_extension_types() -> Vec<String> {
+        extension::extension_type_names()
+    }
+
+    /// Keep at most `max_rows` rows of each statement's result, in every format, so huge
+    /// results don't exhaust the tab's memory. The remaining rows are still computed
+    /// and counted, see `last_result_info`. `undefined` removes the limit.
+    pub fn set_max_rows(&mut self, max_rows: Option<usize>) {
+        self.max_rows = max_rows;
+    }
+
+    /// Abort queries using too much, from an object (or its JSON text) with `max_rows`
+    /// and `max_bytes`, the rows produced by a call and their in-memory size, and
+    /// `max_bytes_scanned`, the bytes fetched from object stores. The call fails with a
+    /// `ResourceLimitError`. Absent limits are unlimited.
+    pub fn set_resource_limits(&mut self, limits: JsValue) -> Result<()> {
+        self.resource_limits = serde_json::from_str(&options_json(&limits)?)?;
+        Ok(())
+    }
+
+    /// Preview results in the `Table` format: only the first `rows` rows and `columns`
+    /// columns are read and shown, so peeking at a remote Parquet file fetches a
+    /// fraction of it. Other formats are unaffected. `total_rows` in `last_result_info`
+    /// only tells whether the preview is truncated. `undefined` removes a limit.
+    pub fn set_preview(&mut self, rows: Option<usize>, columns: Option<usize>) {
+        self.preview = Preview { rows, columns };
+    }
+
+    /// `{rows, total_rows, truncated, warnings}` of the last statement run by
+    /// `execute_sql`, `execute_sql_bytes` or `execute_sql_rows`. `warnings`
+    /// lists the `{kind, message}` notices of the whole call, `kind` being
+    /// `"implicit_cast"`, `"schema_inference"` or `"malformed_records"`.
+    pub fn last_result_info(&self) -> ResultInfo {
+        self.last_result.lock().unwrap().clone()
+    }
+
+    /// The `{kind, message}` warnings of the last query or `append_csv` /
+    /// `append_json` call. `append_csv` skips lines with the wrong number of fields and
+    /// reports them here.
+    pub fn last_warnings(&self) -> Vec<Warning> {
+        self.warnings.snapshot()
+    }
+
+    /// Gather the min, max, null count and an estimate of distinct values of every column
+    /// while results are collected, for `last_column_stats`. Off by default.
+    pub fn set_column_stats(&mut self, enabled: bool) {
+        self.column_stats = enabled;
+    }
+
+    /// Column statistics of the last statement run by `execute_sql`, `execute_sql_bytes`
+    /// or `execute_sql_rows`, as an array of `{name, data_type, min, max, null_count,
+    /// distinct_estimate}`. Covers every row, even those dropped by `set_max_rows`; empty
+    /// unless enabled with `set_column_stats`.
+    pub fn last_column_stats(&self) -> Vec<ColumnStats> {
+        self.last_column_stats.lock().unwrap().clone()
+    }
+
+    /// Statistics of the last query run by `execute_sql`, `execute_sql_bytes` or
+    /// `execute_sql_rows`: `query_id`, `elapsed_ms`, `rows`, `batches`,
+    /// `peak_memory`, `bytes_scanned`, `bytes_downloaded` and `pruning`, the row groups
+    /// and rows Parquet scans skipped by statistics, bloom filters, page index and filter
+    /// pushdown, e.g. `row_groups_pruned_statistics`.
+    pub fn last_query_stats(&self) -> QueryStats {
+        self.last_stats.lock().unwrap().clone()
+    }
+
+    /// Cache up to `max_bytes` of query results, keyed by the normalized SQL and the
+    /// version of the tables read, so repeated queries return without running. Appending
+    /// to or re-registering a table, and any statement other than a query, invalidate
+    /// the affected results. 0 disables the cache.
+    pub fn enable_result_cache(&self, max_bytes: usize) {
+        self.result_cache.set_capacity(max_bytes);
+    }
+
+    pub fn clear_result_cache(&self) {
+        self.result_cache.invalidate_all();
+    }
+
+    /// Set a callback reporting the milestones of each `execute_sql` call as
+    /// `callback({query_id, stage, statement, statements, batches, rows})`, `stage`
+    /// being `"parsed"`, `"planned"`, `"scanning"`, `"batch"` (after each batch, with
+    /// the batches and rows of the statement so far) or `"finished"` (with the totals
+    /// of the call, also after a failure). `undefined` removes it.
+    pub fn on_progress(&self, callback: Option<js_sys::Function>) {
+        self.query_progress.set_callback(callback);
+    }
+
+    /// Record a trace of every query: spans for parsing, planning, optimizing and
+    /// executing each statement, and for the object store requests it made.
+    pub fn set_tracing(&self, enabled: bool) {
+        self.store_registry.tracer().set_enabled(enabled);
+    }
+
+    /// The trace of query `query_id`, or of the last traced query, as OTLP JSON
+    /// (an `ExportTraceServiceRequest`) ready to POST to a collector's `/v1/traces`.
+    /// Only the last 16 traces are kept.
+    pub fn query_trace(&self, query_id: Option<f64>) -> Option<String> {
+        self.store_registry
+            .tracer()
+            .export(query_id.map(|id| id as u64))
+    }
+
+    /// Add `performance.measure` entries named `datafusion:<phase>` for each query,
+    /// its parse, plan, optimize and execute phases and its object store requests,
+    /// plus `datafusion:operator:<name>` for the compute time of each operator, so
+    /// the browser's performance profiler shows where a query spends its time.
+    pub fn set_performance_marks(enabled: bool) {
+        perf_marks::set_enabled(enabled);
+    }
+
+    /// Add a listener of query lifecycle `event`: `"start"` gets
+    /// `{query_id, sql, label, started_at}`, `"batch"` gets
+    /// `{query_id, statement, batches, rows, elapsed_ms}` after each batch and `"end"`
+    /// gets `{query_id, sql, label, elapsed_ms, rows, batches, error}`, also after a
+    /// failure. Listeners are called in the order they were added.
+    pub fn on(&self, event: String, callback: js_sys::Function) -> Result<()> {
+        self.query_events.add(EventKind::parse(&event)?, callback);
+        Ok(())
+    }
+
+    /// Remove a listener added with `on`.
+    pub fn off(&self, event: String, callback: js_sys::Function) -> Result<()> {
+        self.query_events
+            .remove(EventKind::parse(&event)?, &callback);
+        Ok(())
+    }
+
+    /// Set a callback vetting every statement before any statement of a call runs. It
+    /// gets `{kind, tables, sql}`, `kind` being the statement type such as `"Query"`,
+    /// `"Insert"` or `"CreateExternalTable"`, and allows it by returning or resolving
+    /// to `true`. Anything else fails the call with a `StatementRejectedError`, a
+    /// string being the reason. `undefined` removes the filter.
+    pub fn set_statement_filter(&self, callback: Option<js_sys::Function>) {
+        self.statement_filter.set_callback(callback);
+    }
+
+    /// Run `sql` now and again whenever a table it reads gets rows from `append_rows`,
+    /// `append_csv`, `append_json` or `append_ipc`, calling `callback(result)` with the
+    /// rendered result, or `callback(undefined, error)`. Returns the subscription id.
+    pub async fn subscribe(&self, sql: String, callback: js_sys::Function) -> Result<u32> {
+        let state = self.session_context.state();
+        let mut tables = Vec::new();
+        for statement in DFParser::parse_sql(&sql)? {
+            tables.extend(state.resolve_table_references(&statement)?);
+        }
+        let id = self
+            .subscriptions
+            .add(sql.clone(), &tables, callback.clone());
+        self.deliver(sql, &JsCallback(callback)).await;
+        Ok(id)
+    }
+
+    /// Stop a subscription. Returns whether `id` was subscribed.
+    pub fn unsubscribe(&self, id: u32) -> bool {
+        self.subscriptions.remove(id)
+    }
+
+    /// Run at most `queries` queries of each priority at once, 1 by default. Further
+    /// calls wait in the order they were made, interactive ones first.
+    pub fn set_max_concurrent_queries(&self, queries: usize) {
+        self.queue.set_concurrency(queries);
+    }
+
+    /// Queries waiting or running, oldest first, as `{id, sql, label, priority, state}`
+    /// with `state` `"queued"` or `"running"`.
+    pub fn running_queries(&self) -> Vec<QueuedQuery> {
+        self.queue.queries()
+    }
+
+    /// Keep the optimized plans of up to `entries` queries, so re-running the same
+    /// statements skips planning. Defaults to 64; 0 disables the cache.
+    pub fn set_plan_cache_size(&self, entries: usize) {
+        self.plan_cache.set_capacity(entries);
+    }
+
+    /// `{entries, capacity, hits, misses}` of the plan cache.
+    pub fn plan_cache_stats(&self) -> PlanCacheStats {
+        self.plan_cache.stats()
+    }
+
+    /// Drop every cached plan, e.g. after changing tables behind the context's back.
+    pub fn clear_plan_cache(&self) {
+        self.plan_cache.clear();
+    }
+
+    /// Set how results are displayed, from an object (or its JSON text) with `time_zone`
+    /// (the session time zone, e.g. `"+02:00"`), `null`, `date_format`, `datetime_format`,
+    /// `timestamp_format`, `timestamp_tz_format`, `time_format` (chrono `strftime` syntax)
+    /// and `duration_format` (`"iso8601"` or `"pretty"`). Unset keys use the defaults.
+    pub fn set_format_options(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "DisplayOptions | string")] options: JsValue,
+    ) -> Result<()> {
+        let display: DisplayOptions = serde_json::from_str(&options_json(&options)?)?;
+
+        if let Some(time_zone) = &display.time_zone {
+            self.session_context
+                .state_ref()
+                .write()
+                .config_mut()
+                .options_mut()
+                .execution
+                .time_zone = Some(time_zone.clone());
+        }
+        self.render_options.display = display;
+        Ok(())
+    }
+
+    /// Set whether the `Json` format writes Int64, UInt64 and decimal values as numbers
+    /// (the default, large values lose precision in `JSON.parse`) or as strings.
+    pub fn set_json_numbers(&mut self, json_numbers: JsonNumbers) {
+        self.render_options.json_numbers = json_numbers;
+    }
+
+    /// Set how the `Json` format writes binary and nested values, from an object (or its
+    /// JSON text) with `binary` (`"hex"`, `"base64"` or `"array"` of bytes), `nested`
+    /// (`"json"` or `"string"`) and `map` (`"object"` or `"entries"`).
+    pub fn set_json_options(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "JsonOptions | string")] options: JsValue,
+    ) -> Result<()> {
+        self.render_options.json = serde_json::from_str(&options_json(&options)?)?;
+        Ok(())
+    }
+
+    /// Set the options of `Tsv` results, from an object (or its JSON text) with `header`,
+    /// whether to start with the column names (the default).
+    pub fn set_tsv_options(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "TsvOptions | string")] options: JsValue,
+    ) -> Result<()> {
+        self.render_options.tsv = serde_json::from_str(&options_json(&options)?)?;
+        Ok(())
+    }
+
+    /// Set the CSS classes of `Html` results, from an object (or its JSON text) with
+    /// `table_class`, `header_class`, `row_class` and `null_class`.
+    pub fn set_html_options(
+        &mut self,
+        #[wasm_bindgen(unchecked_param_type = "HtmlOptions | string")] options: JsValue,
+    ) -> Result<()> {
+        self.render_options.html = serde_json::from_str(&options_json(&options)?)?;
+        Ok(())
+    }
+
+    /// Compress the buffers of `ArrowIpc` results. Fails if the codec isn't compiled in.
+    pub fn set_ipc_compression(&mut self, compression: IpcCompression) -> Result<()> {
+        if !IpcCompression::supported().contains(&compression) {
+            return Err(WasmError::Other(format!(
+                "{} IPC compression is not enabled in this build",
+                compression.name()
+            )));
+        }
+        self.render_options.ipc_compression = compression;
+        Ok(())
+    }
+
+    /// Pick the best IPC codec among `accepted` (e.g. `["zstd", "lz4"]`, the codecs the
+    /// caller's arrow-js can decode) that this build supports, falling back to none.
+    /// Returns the name of the selected codec.
+    pub fn negotiate_ipc_compression(&mut self, accepted: Vec<String>) -> String {
+        let compression = IpcCompression::negotiate(&accepted);
+        self.render_options.ipc_compression = compression;
+        compression.name().to_string()
+    }
+
+    /// Names of the IPC codecs compiled into this build.
+    pub fn supported_ipc_compressions() -> Vec<String> {
+        IpcCompression::supported()
+            .iter()
+            .map(|compression| compression.name().to_string())
+            .collect()
+    }
+
+    /// Usage and quota of this origin's storage in bytes.
+    pub async fn storage_estimate() -> Result<StorageEstimate> {
+        StorageEstimate::current().await
+    }
+
+    /// Check that `bytes` more can be written to origin storage before persisting data,
+    /// failing with a `QuotaExceededError` otherwise.
+    pub async fn ensure_storage_available(bytes: f64) -> Result<()> {
+        StorageEstimate::current()
+            .await?
+            .ensure_available(bytes as u64)
+    }
+
+    /// Set the size in bytes of the in-memory cache for remote byte ranges.
+    /// Pass 0 to disable caching.
+    pub fn set_cache_size(&self, bytes: usize) {
+        self.store_registry.set_cache_capacity(bytes);
+    }
+
+    /// Set how many seconds cached objects are served before being revalidated against
+    /// the remote with a conditional `If-None-Match` request.
+    pub fn set_cache_revalidation(&self, seconds: u32) {
+        self.store_registry
+            .set_cache_revalidation(chrono::Duration::seconds(seconds as i64));
+    }
+
+    /// Configure remote reads: ranges less than `coalesce_window` bytes apart are merged
+    /// into one request, and `read_ahead` extra bytes are prefetched into the cache after
+    /// each read.
+    pub fn set_read_config(&self, coalesce_window: usize, read_ahead: usize) {
+        self.store_registry.set_read_config(ReadConfig {
+            coalesce_window,
+            read_ahead,
+        });
+    }
+
+    /// Set the largest object, in bytes, downloaded in full when an HTTP server ignores
+    /// `Range` headers. Such downloads are kept in memory and serve later reads of the
+    /// same object; larger objects fail with an error naming the limit.
+    pub fn set_whole_file_limit(&self, bytes: usize) {
+        self.store_registry.set_whole_file_limit(bytes);
+    }
+
+    /// Report download progress of remote objects read by queries as
+    /// `callback(location, fetched_bytes, expected_bytes)`. Counters restart with
+    /// each query, `expected_bytes` is 0 when reads skip fetching the object's size, as
+    /// they do with the cache disabled. Pass `undefined` to remove the callback.
+    pub fn on_io_progress(&self, callback: Option<js_sys::Function>) {
+        self.store_registry.progress().set_callback(callback);
+    }
+
+    /// Await `callback()` between batches while executing queries, so the host decides
+    /// when execution continues. It may return a promise, e.g. one resolved from
+    /// `requestIdleCallback` or `scheduler.postTask`. Pass `undefined` to remove it.
+    pub fn on_yield(&self, callback: Option<js_sys::Function>) {
+        self.scheduler.set_yield_callback(callback);
+    }
+
+    /// Set how `append_csv` / `append_json` handle columns the table doesn't have yet.
+    pub fn set_schema_evolution(&mut self, schema_evolution: SchemaEvolution) {
+        self.schema_evolution = schema_evolution;
+    }
+
+    /// Choose whether values that fail to cast, in queries and in appended data, raise an
+    /// error (the default) or become null.
+    pub fn set_cast_policy(&self, cast_policy: CastPolicy) {
+        *self.cast_policy.lock().unwrap() = cast_policy;
+        // the policy is applied while optimizing, so cached plans and results follow
+        // the old one
+        self.invalidate_all();
+    }
+
+    /// Set how many records `STORED AS JSON` / `NDJSON` external tables read to infer
+    /// their schema. Per-table `OPTIONS ('format.schema_infer_max_rec' '...')` take
+    /// precedence.
+    pub fn set_json_schema_infer_max_records(&self, records: usize) {
+        self.session_context
+            .state_ref()
+            .write()
+            .table_options_mut()
+            .json
+            .schema_infer_max_rec = records;
+    }
+
+    /// Append CSV text to in-memory table `name`, creating it if needed.
+    #[cfg(feature = "csv")]
+    pub async fn append_csv(&self, name: String, data: String, has_header: bool) -> Result<()> {
+        self.warnings.clear();
+        let batches = ingest::read_csv(data.as_bytes(), has_header, &self.warnings)?;
+        self.append_batches(&name, batches).await
+    }
+
+    /// Append newline-delimited JSON to in-memory table `name`, creating it if needed.
+    #[cfg(feature = "json")]
+    pub async fn append_json(&self, name: String, data: String) -> Result<()> {
+        self.warnings.clear();
+        let batches = ingest::read_json(data.as_bytes(), &self.warnings)?;
+        self.append_batches(&name, batches).await
+    }
+
+    /// Append an Arrow IPC stream or file to in-memory table `name`, creating it if
+    /// needed. The buffer is checked before decoding, malformed input fails with the
+    /// byte offset and message at fault.
+    pub async fn append_ipc(&self, name: String, data: Vec<u8>) -> Result<()> {
+        let batches = ipc_input::read(&data)?;
+        self.append_batches(&name, batches).await
+    }
+
+    /// Create table `name` that rows are pushed into with `append_rows`. `schema` is a JSON
+    /// list of `{"name", "data_type", "nullable"}` columns. `options` is an optional JSON
+    /// object with `max_rows` and `max_age_ms`; older rows beyond them are dropped.
+    pub fn create_live_table(
+        &self,
+        name: String,
+        schema: String,
+        options: Option<String>,
+    ) -> Result<()> {
+        let fields: Vec<listing::SchemaField> = serde_json::from_str(&schema)?;
+        let schema = listing::parse_schema(&fields)?;
+        let retention = live_table::retention_from_json(options.as_deref())?;
+        self.invalidate_table(&name);
+        self.session_context
+            .register_table(name, Arc::new(LiveTable::new(schema, retention)))?;
+        Ok(())
+    }
+
+    /// Append the rows of Arrow IPC `ipc_bytes` (stream or file) to live table `name`.
+    /// Columns are matched by name and cast as by `append_ipc`; missing ones are null.
+    pub async fn append_rows(&self, name: String, ipc_bytes: Vec<u8>) -> Result<()> {
+        let provider = self.session_context.table_provider(name.as_str()).await?;
+        let table = provider
+            .as_any()
+            .downcast_ref::<LiveTable>()
+            .ok_or_else(|| WasmError::Other(format!("{name} is not a live table")))?;
+        let cast_policy = *self.cast_policy.lock().unwrap();
+        let batches = ipc_input::read(&ipc_bytes)?
+            .iter()
+            .map(|batch| ingest::conform(batch, &table.schema(), cast_policy))
+            .collect::<Result<Vec<_>>>()?;
+        table.append(batches);
+        self.invalidate_table(&name);
+        self.notify_subscriptions(&name).await;
+        Ok(())
+    }
+
+    /// Give tables created afterwards by `append_csv` / `append_json` a `_row_id`
+    /// column. Ids are never reused, appended rows continue after the largest one.
+    pub fn set_row_ids(&mut self, enabled: bool) {
+        self.row_ids = enabled;
+    }
+
+    /// The rows of table `name` with the given `_row_id`s, in the current result format.
+    pub async fn get_rows_by_id(&self, name: String, ids: Vec<f64>) -> Result<String> {
+        let ids = ids
+            .into_iter()
+            .map(|id| Ok(row_id(id)?.to_string()))
+            .collect::<Result<Vec<_>>>()?;
+        let sql = format!(
+            // `name` is a table reference, as in `append_csv`
+            "SELECT * FROM {name} WHERE {ROW_ID_COLUMN} IN ({}) ORDER BY {ROW_ID_COLUMN}",
+            if ids.is_empty() {
+                "NULL".to_string()
+            } else {
+                ids.join(", ")
+            }
+        );
+        self.execute_inner(sql, &ExecuteOptions::default()).await
+    }
+
+    /// Set columns of the row of table `name` with `_row_id` `id`, from an object (or
+    /// its JSON text) of column values.
+    pub async fn update_row(&self, name: String, id: f64, values: JsValue) -> Result<()> {
+        let values: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&options_json(&values)?)?;
+        self.invalidate_table(&name);
+        row_ids::update(&self.session_context, &name, row_id(id)?, &values).await
+    }
+
+    /// Delete the rows of table `name` with the given `_row_id`s. Returns the number of
+    /// rows deleted.
+    pub async fn delete_rows(&self, name: String, ids: Vec<f64>) -> Result<usize> {
+        let ids = ids.into_iter().map(row_id).collect::<Result<Vec<_>>>()?;
+        self.invalidate_table(&name);
+        row_ids::delete(&self.session_context, &name, &ids).await
+    }
+
+    /// Register every `format` file below `url_prefix` as table `name`. Hive-style
+    /// directories (`year=2024/month=01/`) named in `partition_cols` are exposed as
+    /// columns, and filters on them skip non-matching directories. `options` takes the
+    /// same JSON object as `register_csv`.
+    pub async fn register_listing_table(
+        &self,
+        name: String,
+        url_prefix: String,
+        format: TableFormat,
+        partition_cols: Vec<String>,
+        options: Option<String>,
+    ) -> Result<()> {
+        let options = ReaderOptions::from_json(options.as_deref())?;
+        let table = listing::listing_table(
+            &self.session_context,
+            &url_prefix,
+            format,
+            partition_cols,
+            &options,
+        )
+        .await?;
+        self.invalidate_table(&name);
+        self.session_context.register_table(name, Arc::new(table))?;
+        Ok(())
+    }
+
+    /// Register the CSV file at `url` (or all CSV files below it when it ends with `/`) as
+    /// table `name`. `options` is an optional JSON object with `has_header`, `delimiter`,
+    /// `quote`, `escape`, `compression`, `max_records` and `schema`, a list of
+    /// `{"name", "data_type", "nullable"}` columns used instead of inferring them. Only
+    /// empty fields are read as null, DataFusion's CSV reader takes no null token.
+    #[cfg(feature = "csv")]
+    pub async fn register_csv(
+        &self,
+        name: String,
+        url: String,
+        options: Option<String>,
+    ) -> Result<()> {
+        self.register_listing_table(name, url, TableFormat::Csv, vec![], options)
+            .await
+    }
+
+    /// Load the `ReadableStream` of bytes `readable_stream` (e.g. a fetch body or
+    /// `File.stream()`) as table `name`, decoding it as it arrives. `schema` is a JSON list
+    /// of `{"name", "data_type", "nullable"}` columns; CSV data starts with a header line.
+    #[cfg(any(feature = "csv", feature = "json"))]
+    pub async fn register_stream_table(
+        &self,
+        name: String,
+        schema: String,
+        readable_stream: JsValue,
+        format: TableFormat,
+    ) -> Result<()> {
+        let fields: Vec<listing::SchemaField> = serde_json::from_str(&schema)?;
+        let schema = listing::parse_schema(&fields)?;
+        let decoder = StreamDecoder::new(format, schema.clone())?;
+        let batches = stream_ingest::read(&readable_stream, decoder).await?;
+        let table = MemTable::try_new(schema, vec![batches])?;
+        self.invalidate_table(&name);
+        self.session_context.register_table(name, Arc::new(table))?;
+        Ok(())
+    }
+
+    /// Check `url` before registering it: reachability (including CORS), range request
+    /// support, size, content type and ETag. Returns a report with `warnings` such as
+    /// "the whole file will be downloaded". Never fails, errors are in the report.
+    pub async fn probe_url(url: String) -> ProbeReport {
+        ProbeReport::probe(&url).await
+    }
+
+    /// Infer the schema of `url` (a file, or a directory when it ends with `/`) without
+    /// registering anything. `options` takes the same JSON object as `register_csv`.
+    /// Returns `{fields: [{name, data_type, nullable}]}`.
+    pub async fn infer_schema(
+        &self,
+        url: String,
+        format: TableFormat,
+        options: Option<String>,
+    ) -> Result<TableSchema> {
+        let options = ReaderOptions::from_json(options.as_deref())?;
+        let schema = listing::infer_schema(&self.session_context, &url, format, &options).await?;
+        Ok(TableSchema::new(&schema))
+    }
+
+    /// Describe the layout of the Parquet file at `url`: schema, key-value metadata
+    /// and, per row group, the sizes, compression, encodings and statistics of each column.
+    /// Only the footer is read.
+    #[cfg(feature = "parquet")]
+    pub async fn inspect_parquet(&self, url: String) -> Result<ParquetInfo> {
+        ParquetInfo::inspect(&self.session_context, &url).await
+    }
+
+    /// Register a GeoParquet file as table `name`. Geometry columns carry `geoarrow.wkb`
+    /// extension metadata. When `bbox` (`[xmin, ymin, xmax, ymax]`) is given, row groups
+    /// whose bounding box statistics don't intersect it are skipped.
+    #[cfg(feature = "parquet")]
+    pub async fn register_geoparquet(
+        &self,
+        name: String,
+        url: String,
+        bbox: Option<Vec<f64>>,
+    ) -> Result<()> {
+        let bbox = match bbox.as_deref() {
+            Some([xmin, ymin, xmax, ymax]) => Some([*xmin, *ymin, *xmax, *ymax]),
+            Some(_) => {
+                return Err(WasmError::Other(
+                    "bbox must be [xmin, ymin, xmax, ymax]".to_string(),
+                ))
+            }
+            None => None,
+        };
+        let table = GeoParquetTable::try_new(&self.session_context, &url, bbox).await?;
+        self.invalidate_table(&name);
+        self.session_context.register_table(name, Arc::new(table))?;
+        Ok(())
+    }
+
+    /// Run `sql` and bin its points into a `width` x `height` density image over `extent`
+    /// (`[xmin, ymin, xmax, ymax]`), returned as RGBA pixels for `ImageData`. The first two
+    /// result columns are x and y, an optional third one is the point weight.
+    pub async fn rasterize(
+        &self,
+        sql: String,
+        width: usize,
+        height: usize,
+        extent: Vec<f64>,
+    ) -> Result<js_sys::Uint8ClampedArray> {
+        let extent: [f64; 4] = extent
+            .try_into()
+            .map_err(|_| WasmError::Other("extent must be [xmin, ymin, xmax, ymax]".to_string()))?;
+        let mut results = self
+            .collect_statements(&sql, &ExecuteOptions::default(), None)
+            .await?;
+        let record_batches = results.pop().unwrap_or_default();
+        let pixels = crate::raster::rasterize(&record_batches, width, height, extent)?;
+        Ok(js_sys::Uint8ClampedArray::from(pixels.as_slice()))
+    }
+
+    /// Cumulative metrics of this session: `queries`, `failed_queries`,
+    /// `bytes_downloaded`, `cache_hits`, `cache_misses`, `cache_hit_rate`,
+    /// `memory_reserved`, `memory_peak` and `labels`, the totals per `execute_sql` label.
+    pub fn session_metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot(&self.store_registry)
+    }
+
+    /// Search table names, column names and column metadata for `text`, allowing
+    /// skipped letters. Returns up to `limit` (default 50) matches, best
+    /// first, each with `kind` (`table`, `column` or `metadata`), `catalog`, `schema`,
+    /// `table`, `column`, the matched `text` and a `score`.
+    pub async fn search_catalog(
+        &self,
+        text: String,
+        limit: Option<usize>,
+    ) -> Result<Vec<CatalogMatch>> {
+        catalog_search::search(&self.session_context, &text, limit.unwrap_or(50)).await
+    }
+
+    /// The last 100 queries as `{sql, label, tags, started_at, elapsed_ms,
+    /// bytes_downloaded, error}`, oldest first.
+    pub fn query_history(&self) -> Vec<QueryRecord> {
+        self.metrics.history()
+    }
+
+    pub fn clear_query_history(&self) {
+        self.metrics.clear_history();
+    }
+
+    /// Register table `name`, a single row with the current `session_metrics` each
+    /// time it's queried.
+    pub fn register_metrics_table(&self, name: String) -> Result<()> {
+        let table = MetricsTable::new(self.metrics.clone(), self.store_registry.clone());
+        self.invalidate_table(&name);
+        self.session_context
+            .register_table(name.as_str(), Arc::new(table))?;
+        Ok(())
+    }
+
+    /// Mount `tables` of this session into namespace `name`, creating it if needed.
+    /// Queries run with `{"namespace": name}` only see the tables of that namespace,
+    /// and tables they create stay in it. Object stores and credentials are shared with
+    /// this session, so they can still read and write any URL it can.
+    pub async fn mount_namespace(&self, name: String, tables: Vec<String>) -> Result<()> {
+        self.invalidate_all();
+        self.namespaces
+            .mount(&self.session_context, &name, &tables)
+            .await
+    }
+
+    /// Drop namespace `name` with the tables created in it. Returns whether it existed.
+    pub fn unmount_namespace(&self, name: String) -> bool {
+        self.invalidate_all();
+        self.namespaces.unmount(&name)
+    }
+
+    pub fn list_namespaces(&self) -> Vec<String> {
+        self.namespaces.names()
+    }
+
+    /// Parse `sql` without executing it. Returns a report with the statements parsed so
+    /// far and, on failure, the error position plus expected and found tokens.
+    pub fn check_sql(sql: String) -> ParseReport {
+        ParseReport::parse(&sql)
+    }
+
+    /// A DataFrame reading table `name`, for building a query with chained
+    /// `select`, `filter`, `aggregate`, `sort`, `limit` and `join` calls instead of SQL.
+    /// `collect` renders the result in the result format set when the frame was made.
+    pub async fn table(&self, name: String) -> Result<WasmDataFrame> {
+        let df = self.session_context.table(name.as_str()).await?;
+        Ok(WasmDataFrame::new(df, self.rendering()))
+    }
+
+    /// A DataFrame over the result of query `sql`, to refine with `WasmDataFrame`
+    /// methods.
+    pub async fn sql_dataframe(&self, sql: String) -> Result<WasmDataFrame> {
+        let df = self.session_context.sql(&sql).await?;
+        Ok(WasmDataFrame::new(df, self.rendering()))
+    }
+
+    /// Format `sql` with uppercase keywords, one clause per line and indented select
+    /// lists and subqueries. Statements are separated by `;` and a blank line, comments
+    /// are dropped. Fails like `execute_sql` on SQL that doesn't parse.
+    pub fn format_sql(sql: String) -> Result<String> {
+        sql_format::format_sql(&sql)
+    }
+
+    /// Package `sql` together with the DDL and a small data sample of every table it
+    /// references into a single JSON document, suitable for attaching to bug reports.
+    pub async fn export_repro_bundle(&self, sql: String) -> Result<String> {
+        ReproBundle::collect(&self.session_context, &sql)
+            .await?
+            .to_json()
+    }
+}
+
+impl Default for DataFusionContext {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl DataFusionContext {
-    async fn execute_inner(&self, sql: String) -> Result<String> {
-        let statements = DFParser::parse_sql(&sql)?;
-        let mut results = Vec::with_capacity(statements.len());
+    fn with_store_registry(store_registry: OpendalRegistry) -> Self {
+        crate::set_panic_hook();
+
+        let metrics = Arc::new(SessionMetrics::default());
+        let cast_policy = Arc::new(Mutex::new(CastPolicy::default()));
+        let session_context = Self::build_session(&store_registry, &metrics, &cast_policy);
+
+        logger::info("datafusion context is initialized");
+
+        Self {
+            session_context,
+            store_registry,
+            result_format: ResultFormat::Table,
+            result_renderer: None,
+            render_options: RenderOptions::default(),
+            schema_evolution: SchemaEvolution::default(),
+            cast_policy,
+            segments: Segments::default(),
+            pages: Pages::default(),
+            namespaces: Namespaces::default(),
+            scheduler: Scheduler::default(),
+            queue: QueryQueue::default(),
+            statement_filter: StatementFilter::default(),
+            subscriptions: Subscriptions::default(),
+            query_progress: QueryProgress::default(),
+            query_events: QueryEvents::default(),
+            metrics,
+            row_ids: false,
+            max_rows: None,
+            preview: Preview::default(),
+            resource_limits: ResourceLimits::default(),
+            last_result: Mutex::new(ResultInfo::default()),
+            warnings: Warnings::default(),
+            column_stats: false,
+            last_column_stats: Mutex::new(Vec::new()),
+            last_stats: Mutex::new(QueryStats::default()),
+            result_cache: ResultCache::default(),
+            plan_cache: PlanCache::default(),
+        }
+    }
+
+    /// A session with no tables reading through `store_registry`.
+    fn build_session(
+        store_registry: &OpendalRegistry,
+        metrics: &SessionMetrics,
+        cast_policy: &Arc<Mutex<CastPolicy>>,
+    ) -> Arc<SessionContext> {
+        let rt = Arc::new(
+            RuntimeEnvBuilder::new()
+                .with_disk_manager(DiskManagerConfig::Disabled)
+                .with_object_store_registry(Arc::new(store_registry.clone()))
+                .with_memory_pool(metrics.memory_pool())
+                .build()
+                .unwrap(),
+        );
+        let mut session_config = SessionConfig::new()
+            .with_target_partitions(1)
+            .with_information_schema(true);
+        // selective queries over remote Parquet read only the pages their filters can
+        // match, using the page index and bloom filters fetched with ranged reads
+        let parquet = &mut session_config.options_mut().execution.parquet;
+        parquet.pruning = true;
+        parquet.enable_page_index = true;
+        parquet.bloom_filter_on_read = true;
+        parquet.pushdown_filters = true;
+        parquet.reorder_filters = true;
+        // footer and metadata of most files in the first request
+        parquet.metadata_size_hint = Some(PARQUET_METADATA_SIZE_HINT);
+        let session_context = Arc::new(SessionContext::new_with_config_rt(session_config, rt));
+        session_context.add_analyzer_rule(Arc::new(CastPolicyRule::new(cast_policy.clone())));
+        session_context
+            .state_ref()
+            .write()
+            .register_file_format(Arc::new(NdJsonFormatFactory::default()), false)
+            .unwrap();
+        functions::register_optional(&session_context);
+        session_context
+    }
+
+    async fn execute_inner(&self, sql: String, options: &ExecuteOptions) -> Result<String> {
+        let results = self
+            .collect_statements(&sql, options, self.active_preview())
+            .await?;
+        let mut formatted = Vec::with_capacity(results.len());
+        for record_batches in results {
+            formatted.push(String::from_utf8(self.render(&record_batches)?)?);
+        }
+
+        Ok(formatted.join("\n"))
+    }
+
+    /// Execute every statement in `sql`, returning the batches of each one.
+    async fn collect_statements(
+        &self,
+        sql: &str,
+        options: &ExecuteOptions,
+        preview: Option<Preview>,
+    ) -> Result<Vec<Vec<RecordBatch>>> {
+        let priority = options.priority;
+        let parse_start = trace::now();
+        let mut statements = DFParser::parse_sql(sql)?;
+        let parse_end = trace::now();
+        let permit = self.queue.enter(sql, options.label.clone(), priority).await;
+        let tracer = self.store_registry.tracer();
+        tracer.start_query(permit.id(), sql, parse_start);
+        tracer.record_between("parse", parse_start, parse_end);
+        let statement_count = statements.len();
+        let report = |stage, statement, batches, rows| {
+            self.query_progress.report(&ProgressEvent {
+                query_id: permit.id(),
+                stage,
+                statement,
+                statements: statement_count,
+                batches,
+                rows,
+            })
+        };
+        report(QueryStage::Parsed, 0, 0, 0);
+        let ctx = self.query_context(sql, options)?;
+        self.check_statements(&ctx, &statements).await?;
+        let cache_key = self
+            .result_cache_key(&ctx, statements.make_contiguous(), options, preview)
+            .await?;
+        let cached = cache_key
+            .as_deref()
+            .and_then(|key| self.result_cache.get(key));
+        let hit = cached.is_some();
+        let modifies = !statements.iter().all(result_cache::is_query);
+        let _guard = self.scheduler.enter(priority);
+        self.store_registry.progress().reset();
+        self.warnings.clear();
+        let started_at = Utc::now();
+        let downloaded = self.store_registry.progress().downloaded();
+        self.metrics.memory().reset_query_peak();
+        let mut stats = QueryStats {
+            query_id: permit.id(),
+            ..Default::default()
+        };
+        let max_rows = match preview.and_then(|preview| preview.rows) {
+            Some(rows) => Some(self.max_rows.map_or(rows, |max_rows| max_rows.min(rows))),
+            None => self.max_rows,
+        };
+        let mut guard = ResourceGuard::new(self.resource_limits);
+        self.query_events.emit(
+            EventKind::Start,
+            &StartEvent {
+                query_id: permit.id(),
+                sql: sql.to_string(),
+                label: options.label.clone(),
+                started_at: started_at.timestamp_millis(),
+            },
+        );
+        let results = async {
+            if let Some(cached) = cached {
+                stats.rows = cached
+                    .results
+                    .iter()
+                    .flatten()
+                    .map(|batch| batch.num_rows())
+                    .sum();
+                stats.batches = cached.results.iter().map(|batches| batches.len()).sum();
+                self.warnings.extend(cached.info.warnings.clone());
+                *self.last_result.lock().unwrap() = cached.info;
+                if let (true, Some(last)) = (self.column_stats, cached.results.last()) {
+                    if let Some(first) = last.first() {
+                        let mut collector = ColumnStatsCollector::new(first.schema());
+                        for batch in last {
+                            collector.update(batch)?;
+                        }
+                        *self.last_column_stats.lock().unwrap() = collector.finish()?;
+                    }
+                }
+                return Ok(cached.results);
+            }
+
+            let mut results = Vec::with_capacity(statements.len());
+            for (index, statement) in statements.into_iter().enumerate() {
+                self.scheduler.yield_now(priority).await?;
+                if let Some(name) = table_stats::analyze_target(&statement) {
+                    self.analyze(&ctx, &name).await?;
+                    results.push(vec![]);
+                    continue;
+                }
+                let physical_plan = self
+                    .physical_plan(&ctx, statement, options, preview)
+                    .await?;
+                report(QueryStage::Planned, index, 0, 0);
+                report(QueryStage::Scanning, index, 0, 0);
+                let execute_start = trace::now();
+                let (mut batch_count, mut row_count) = (0, 0);
+                let mut column_stats = self
+                    .column_stats
+                    .then(|| ColumnStatsCollector::new(physical_plan.schema()));
+                let task_ctx = ctx.task_ctx();
+                let (batches, total_rows) = self
+                    .scheduler
+                    .collect_limited(
+                        physical_plan.clone(),
+                        task_ctx,
+                        priority,
+                        max_rows,
+                        |batch| {
+                            batch_count += 1;
+                            row_count += batch.num_rows();
+                            report(QueryStage::Batch, index, batch_count, row_count);
+                            self.query_events.emit(
+                                EventKind::Batch,
+                                &BatchEvent {
+                                    query_id: permit.id(),
+                                    statement: index,
+                                    batches: batch_count,
+                                    rows: row_count,
+                                    elapsed_ms: (Utc::now() - started_at).num_milliseconds(),
+                                },
+                            );
+                            if let Some(column_stats) = &mut column_stats {
+                                column_stats.update(batch)?;
+                            }
+                            let scanned = self.store_registry.progress().downloaded() - downloaded;
+                            guard.check(batch, scanned)
+                        },
+                    )
+                    .await?;
+                tracer.record(
+                    "execute",
+                    SpanKind::Internal,
+                    execute_start,
+                    vec![
+                        ("datafusion.statement", index.into()),
+                        ("datafusion.rows", total_rows.into()),
+                        ("datafusion.batches", batches.len().into()),
+                    ],
+                    None,
+                );
+                perf_marks::operators(&physical_plan, execute_start);
+                stats.rows += total_rows;
+                stats.batches += batches.len();
+                stats.bytes_scanned += metrics::bytes_scanned(&physical_plan);
+                stats.pruning.add(&PruningStats::from_plan(&physical_plan));
+                let rows = batches.iter().map(|batch| batch.num_rows()).sum();
+                *self.last_result.lock().unwrap() = ResultInfo {
+                    rows,
+                    total_rows,
+                    truncated: rows < total_rows,
+                    warnings: self.warnings.snapshot(),
+                };
+                if let Some(column_stats) = column_stats {
+                    *self.last_column_stats.lock().unwrap() = column_stats.finish()?;
+                }
+                results.push(batches);
+            }
+            Ok(results)
+        }
+        .await;
+
+        report(
+            QueryStage::Finished,
+            statement_count.saturating_sub(1),
+            stats.batches,
+            stats.rows,
+        );
+        stats.elapsed_ms = (Utc::now() - started_at).num_milliseconds();
+        stats.peak_memory = self.metrics.memory().query_peak();
+        stats.bytes_downloaded = self.store_registry.progress().downloaded() - downloaded;
+        match &results {
+            Ok(_) => logger::debug(format_args!(
+                "query {} finished in {} ms, {} rows{}: {sql}",
+                stats.query_id,
+                stats.elapsed_ms,
+                stats.rows,
+                if hit { " from the result cache" } else { "" }
+            )),
+            Err(err) => logger::warn(format_args!("query {} failed: {err}", stats.query_id)),
+        }
+        self.query_events.emit(
+            EventKind::End,
+            &EndEvent {
+                query_id: stats.query_id,
+                sql: sql.to_string(),
+                label: options.label.clone(),
+                elapsed_ms: stats.elapsed_ms,
+                rows: stats.rows,
+                batches: stats.batches,
+                error: results.as_ref().err().map(ToString::to_string),
+            },
+        );
+        *self.last_stats.lock().unwrap() = stats;
+        tracer.finish_query(results.as_ref().err().map(ToString::to_string));
+        perf_marks::measure("query", parse_start, trace::now());
+
+        if modifies {
+            self.invalidate_all();
+        } else if let (Some(key), Ok(results), false) = (cache_key, &results, hit) {
+            let info = self.last_result.lock().unwrap().clone();
+            let results = results.clone();
+            self.result_cache
+                .insert(key, CachedResult { results, info });
+        }
+        self.record_query(sql, options, started_at, downloaded, &results);
+        results
+    }
 
+    /// Execute every statement of `sql` but the last, and plan the last one.
+    async fn plan_last_statement(&self, sql: &str) -> Result<Arc<dyn ExecutionPlan>> {
+        let mut statements = DFParser::parse_sql(sql)?;
+        let last = statements
+            .pop_back()
+            .ok_or_else(|| WasmError::Other("no statement to execute".to_string()))?;
+        // the rest of the plan runs after the query leaves the queue
+        let _permit = self
+            .queue
+            .enter(sql, None, QueryPriority::Interactive)
+            .await;
+        let ctx = self.query_context(sql, &ExecuteOptions::default())?;
+        self.check_statements(&ctx, statements.iter().chain([&last]))
+            .await?;
+        self.store_registry.progress().reset();
+        let started_at = Utc::now();
+        let downloaded = self.store_registry.progress().downloaded();
+        if !statements.iter().chain([&last]).all(result_cache::is_query) {
+            self.invalidate_all();
+        }
+        let plan = async {
+            for statement in statements {
+                let physical_plan = self
+                    .physical_plan(&ctx, statement, &ExecuteOptions::default(), None)
+                    .await?;
+                self.scheduler
+                    .collect(physical_plan, ctx.task_ctx(), QueryPriority::Interactive)
+                    .await?;
+            }
+            self.physical_plan(&ctx, last, &ExecuteOptions::default(), None)
+                .await
+        }
+        .await;
+
+        // counted when planned, execution failures of the last statement aren't seen here
+        self.record_query(
+            sql,
+            &ExecuteOptions::default(),
+            started_at,
+            downloaded,
+            &plan,
+        );
+        plan
+    }
+
+    /// Run every statement past the statement filter before any of them runs.
+    async fn check_statements<'a>(
+        &self,
+        ctx: &SessionContext,
+        statements: impl IntoIterator<Item = &'a Statement>,
+    ) -> Result<()> {
+        if !self.statement_filter.is_set() {
+            return Ok(());
+        }
         for statement in statements {
-            let logical_plan = self
-                .session_context
-                .state()
-                .statement_to_plan(statement)
-                .await?;
-            let data_frame = self
-                .session_context
-                .execute_logical_plan(logical_plan)
-                .await?;
+            let info = StatementInfo::new(ctx, statement)?;
+            self.statement_filter.check(&info).await?;
+        }
+        Ok(())
+    }
+
+    /// The result cache key of `statements`, if the cache is enabled and they only read.
+    async fn result_cache_key(
+        &self,
+        ctx: &SessionContext,
+        statements: &[Statement],
+        options: &ExecuteOptions,
+        preview: Option<Preview>,
+    ) -> Result<Option<String>> {
+        if !self.result_cache.is_enabled() {
+            return Ok(None);
+        }
+
+        if !statements.iter().all(result_cache::is_query) {
+            return Ok(None);
+        }
+
+        let state = ctx.state();
+        let mut tables = Vec::new();
+        for statement in statements {
+            tables.extend(state.resolve_table_references(statement)?);
+            // now(), random() and uuid() differ between runs
+            let plan = state.statement_to_plan(statement.clone()).await?;
+            if !plan_cache::is_immutable(&plan) {
+                return Ok(None);
+            }
+        }
+        // views, the metrics table and information_schema change without a version bump
+        for table in &tables {
+            match ctx.table_provider(table.clone()).await {
+                Ok(provider) if provider.table_type() == TableType::Base => {}
+                _ => return Ok(None),
+            }
+        }
+        let settings = format!(
+            "{:?} {:?} {:?} {:?} {:?} {:?}",
+            self.max_rows,
+            preview,
+            options.namespace,
+            options.config,
+            state.config().options().execution.time_zone,
+            *self.cast_policy.lock().unwrap()
+        );
+        Ok(self.result_cache.key(statements, &tables, &settings))
+    }
+
+    fn record_query<T>(
+        &self,
+        sql: &str,
+        options: &ExecuteOptions,
+        started_at: DateTime<Utc>,
+        downloaded_before: u64,
+        result: &Result<T>,
+    ) {
+        let downloaded = self.store_registry.progress().downloaded() - downloaded_before;
+        let error = result.as_ref().err().map(|err| err.to_string());
+        self.metrics
+            .record_query(sql, options, started_at, downloaded, error);
+    }
+
+    /// The session to run `sql` in: this one, or a copy with the settings of the hint
+    /// comments leading `sql` and the `config` of `options` (see [`Pragmas`]). The copy
+    /// shares the catalogs, so tables created by the query stay registered, but `SET`
+    /// only changes the copy and lasts for the call. With a `namespace`, the copy only
+    /// sees the catalog of that namespace and `SET` is rejected.
+    fn query_context(&self, sql: &str, options: &ExecuteOptions) -> Result<Arc<SessionContext>> {
+        let pragmas = Pragmas::parse(sql)?;
+        let ctx = match &options.namespace {
+            Some(namespace) => self.namespaces.context(&self.session_context, namespace)?,
+            None => self.session_context.clone(),
+        };
+        if pragmas.is_empty() && options.config.is_empty() {
+            return Ok(ctx);
+        }
+
+        // a copy of the state, the shared session keeps its options
+        let mut state = ctx.state();
+        options.apply_config(state.config_mut().options_mut())?;
+        if let Some(time_zone) = pragmas.time_zone {
+            state.config_mut().options_mut().execution.time_zone = Some(time_zone);
+        }
+        Ok(Arc::new(SessionContext::new_with_state(state)))
+    }
+
+    /// Plan `statement`, narrowed to `preview` if it is a query. The optimized plans of
+    /// queries are kept in the plan cache.
+    async fn physical_plan(
+        &self,
+        ctx: &SessionContext,
+        mut statement: Statement,
+        options: &ExecuteOptions,
+        preview: Option<Preview>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        compression::detect_in_statement(&mut statement);
+        let tracer = self.store_registry.tracer();
+        if !result_cache::is_query(&statement) || !self.plan_cache.is_enabled() {
+            let start = trace::now();
+            let state = ctx.state();
+            let logical_plan = state.statement_to_plan(statement).await?;
+            if let Some((name, value)) = settings::set_variable(&logical_plan) {
+                // a namespace session is rebuilt for every call, its options come from
+                // the shared session
+                if options.namespace.is_some() {
+                    return Err(WasmError::StatementRejected {
+                        kind: "SET".to_string(),
+                        reason: "options can't be set in a namespace".to_string(),
+                    });
+                }
+                settings::apply(ctx, name, value)?;
+                let data_frame = ctx.read_batch(settings::setting_batch(ctx, name)?)?;
+                return Ok(data_frame.create_physical_plan().await?);
+            }
+            self.warnings.extend(warnings::implicit_casts(
+                &logical_plan,
+                state.config_options(),
+            ));
+            #[cfg(feature = "parquet")]
+            let location = parquet_info::external_table_location(&logical_plan)
+                .map(|location| location.to_string());
+            let data_frame = ctx.execute_logical_plan(logical_plan).await;
+            #[cfg(feature = "parquet")]
+            let data_frame = match (data_frame, location) {
+                (Err(err), Some(location)) => {
+                    Err(parquet_info::explain_error(ctx, &location, err.into()).await)
+                }
+                (data_frame, _) => data_frame.map_err(WasmError::from),
+            };
+            let data_frame = data_frame?;
+            tracer.record_between("plan", start, trace::now());
+            // the optimizer runs as part of physical planning here
+            let start = trace::now();
             let physical_plan = data_frame.create_physical_plan().await?;
+            tracer.record_between("physical_plan", start, trace::now());
+            return Ok(physical_plan);
+        }
 
-            let task_ctx = self.session_context.task_ctx();
-            let record_batches = collect(physical_plan, task_ctx).await?;
-            let formatted =
-                pretty_format_batches_with_options(&record_batches, &FormatOptions::default())?
-                    .to_string();
+        let state = ctx.state();
+        let key = format!(
+            "{:?}\n{:?}\n{:?}\n{statement}",
+            options.namespace,
+            options.config,
+            state.config().options().execution.time_zone
+        );
+        let optimized = match self.plan_cache.get(&key) {
+            Some((plan, warnings)) => {
+                self.warnings.extend(warnings);
+                plan
+            }
+            None => {
+                let start = trace::now();
+                let tables = state.resolve_table_references(&statement)?;
+                let logical_plan = state.statement_to_plan(statement).await?;
+                let warnings = warnings::implicit_casts(&logical_plan, state.config_options());
+                self.warnings.extend(warnings.clone());
+                tracer.record_between("plan", start, trace::now());
+                let start = trace::now();
+                let optimized = state.optimize(&logical_plan)?;
+                tracer.record_between("optimize", start, trace::now());
+                if plan_cache::is_immutable(&logical_plan) {
+                    self.plan_cache.insert(key, &optimized, &warnings, &tables);
+                }
+                optimized
+            }
+        };
+        let start = trace::now();
+        let physical_plan = match preview {
+            Some(preview) => {
+                preview
+                    .apply(DataFrame::new(state, optimized))?
+                    .create_physical_plan()
+                    .await?
+            }
+            None => {
+                DefaultPhysicalPlanner::default()
+                    .create_physical_plan(&optimized, &state)
+                    .await?
+            }
+        };
+        tracer.record_between("physical_plan", start, trace::now());
+        Ok(physical_plan)
+    }
+
+    async fn append_batches(&self, name: &str, batches: Vec<RecordBatch>) -> Result<()> {
+        self.invalidate_table(name);
+        let cast_policy = *self.cast_policy.lock().unwrap();
+        ingest::append(
+            &self.session_context,
+            name,
+            batches,
+            self.schema_evolution,
+            cast_policy,
+            self.row_ids,
+        )
+        .await?;
+        self.notify_subscriptions(name).await;
+        Ok(())
+    }
+
+    /// Collect the statistics of table `name` and re-register it to report them.
+    async fn analyze(&self, ctx: &SessionContext, name: &str) -> Result<StatisticsReport> {
+        let statistics = table_stats::collect(ctx, name).await?;
+        let provider = ctx.table_provider(name).await?;
+        let report = StatisticsReport::new(&provider.schema(), &statistics);
+        ctx.deregister_table(name)?;
+        ctx.register_table(name, Arc::new(StatisticsTable::new(provider, statistics)))?;
+        self.invalidate_table(name);
+        Ok(report)
+    }
+
+    /// Re-run the subscriptions reading table `name`, which got new rows.
+    async fn notify_subscriptions(&self, name: &str) {
+        for (sql, callback) in self.subscriptions.affected(name) {
+            self.deliver(sql, &callback).await;
+        }
+    }
+
+    /// Run subscribed query `sql` and pass its result or error to `callback`.
+    async fn deliver(&self, sql: String, callback: &JsCallback) {
+        // a failing callback doesn't fail the change that triggered it
+        let _ = match self.execute_inner(sql, &ExecuteOptions::default()).await {
+            Ok(result) => callback.0.call1(&JsValue::NULL, &result.into()),
+            Err(err) => callback
+                .0
+                .call2(&JsValue::NULL, &JsValue::UNDEFINED, &err.into()),
+        };
+    }
+
+    /// Forget cached results and plans reading table `name`, which is being replaced.
+    fn invalidate_table(&self, name: &str) {
+        self.result_cache.invalidate(name);
+        self.plan_cache.invalidate(name);
+    }
+
+    /// Forget every cached result and plan, after a statement that may have changed
+    /// any table or setting.
+    fn invalidate_all(&self) {
+        self.result_cache.invalidate_all();
+        self.plan_cache.clear();
+    }
+
+    /// The preview to plan queries with, if results are shown as a table.
+    fn active_preview(&self) -> Option<Preview> {
+        let table = self.result_format == ResultFormat::Table && self.result_renderer.is_none();
+        Some(self.preview).filter(|preview| table && !preview.is_empty())
+    }
+
+    fn rendering(&self) -> Rendering {
+        Rendering {
+            format: self.result_format,
+            renderer: self.result_renderer.clone(),
+            options: self.render_options.clone(),
+        }
+    }
 
-            results.push(formatted)
+    fn render(&self, record_batches: &[RecordBatch]) -> Result<Vec<u8>> {
+        match &self.result_renderer {
+            Some(renderer) => renderer.render(record_batches),
+            None => self
+                .result_format
+                .render_with(record_batches, &self.render_options),
         }
+    }
+}
+
+/// The JSON text of an options argument given as an object or a JSON string.
+fn options_json(options: &JsValue) -> Result<String> {
+    if options.is_undefined() || options.is_null() {
+        return Ok("{}".to_string());
+    }
+    match options.as_string() {
+        Some(json) => Ok(json),
+        None => js_sys::JSON::stringify(options)
+            .ok()
+            .and_then(|json| json.as_string())
+            .ok_or_else(|| WasmError::Other("options must be an object".to_string())),
+    }
+}
+
+/// A `_row_id` given as a JavaScript number.
+fn row_id(id: f64) -> Result<u64> {
+    if id < 0.0 || id.fract() != 0.0 || id > u64::MAX as f64 {
+        return Err(WasmError::Other(format!("invalid {ROW_ID_COLUMN} {id}")));
+    }
+    Ok(id as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run(ctx: &DataFusionContext, sql: &str) -> Result<Vec<Vec<RecordBatch>>> {
+        ctx.collect_statements(sql, &ExecuteOptions::default(), None)
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_cast_policy_switch_invalidates_caches() {
+        let ctx = DataFusionContext::new();
+        ctx.enable_result_cache(1 << 20);
+        run(&ctx, "CREATE TABLE t AS VALUES ('1'), ('x')")
+            .await
+            .unwrap();
+        let sql = "SELECT CAST(column1 AS INT) AS v FROM t";
+
+        // planned and cached under the failing policy
+        assert!(run(&ctx, sql).await.is_err());
+        ctx.set_cast_policy(CastPolicy::Null);
+        let results = run(&ctx, sql).await.unwrap();
+        assert_eq!(results[0][0].column(0).null_count(), 1);
+
+        // the null result is cached, switching back must not replay it
+        ctx.set_cast_policy(CastPolicy::Error);
+        assert!(run(&ctx, sql).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_set_only_changes_its_session() {
+        let ctx = DataFusionContext::new();
+        let batch_size = |ctx: &DataFusionContext| {
+            ctx.session_context
+                .state()
+                .config()
+                .options()
+                .execution
+                .batch_size
+        };
+        let default = batch_size(&ctx);
+
+        let options = ExecuteOptions {
+            config: [("datafusion.execution.batch_size".to_string(), 1024.into())].into(),
+            ..Default::default()
+        };
+        ctx.collect_statements("SET datafusion.execution.batch_size = 7", &options, None)
+            .await
+            .unwrap();
+        assert_eq!(batch_size(&ctx), default);
+
+        run(&ctx, "SET datafusion.execution.batch_size = 7")
+            .await
+            .unwrap();
+        assert_eq!(batch_size(&ctx), 7);
+    }
 
-        Ok(format!("{}", results.join("\n")))
+    #[tokio::test]
+    async fn test_volatile_results_are_not_cached() {
+        let ctx = DataFusionContext::new();
+        ctx.enable_result_cache(1 << 20);
+        let sql = "SELECT random() AS v";
+        let first = run(&ctx, sql).await.unwrap();
+        let second = run(&ctx, sql).await.unwrap();
+        assert_ne!(first[0][0], second[0][0]);
     }
 }
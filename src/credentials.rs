@@ -0,0 +1,326 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Credentials refreshed by the host, so expired tokens don't fail long sessions.
+//!
+//! Stores built while a credential callback is configured retry a request that
+//! failed with 401 or 403 once, after asking the callback for new credentials.
+
+use std::ops::Range;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::channel::mpsc;
+use futures::stream::BoxStream;
+use futures::{Future, FutureExt, SinkExt, StreamExt};
+use js_sys::Promise;
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts,
+    PutOptions, PutPayload, PutResult, Result,
+};
+use serde::Deserialize;
+use url::Url;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::logger;
+use crate::object_store::OpendalRegistry;
+use crate::progress::JsCallback;
+use crate::unsafe_opendal_store::ForceSend;
+
+/// What the callback resolves to, as an object or its JSON text.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    #[serde(default)]
+    pub session_token: String,
+}
+
+/// Call `callback(url)` and parse the credentials it returns or resolves to.
+pub async fn request(callback: &JsCallback, url: &Url) -> Result<Credentials> {
+    let mut value = callback
+        .0
+        .call1(&JsValue::NULL, &JsValue::from_str(url.as_str()))
+        .map_err(callback_error)?;
+    if let Some(promise) = value.dyn_ref::<Promise>() {
+        value = JsFuture::from(promise.clone())
+            .await
+            .map_err(callback_error)?;
+    }
+
+    let json = match value.as_string() {
+        Some(json) => json,
+        None => js_sys::JSON::stringify(&value)
+            .map_err(callback_error)?
+            .as_string()
+            .unwrap_or_default(),
+    };
+    serde_json::from_str(&json).map_err(|err| generic_error(err.to_string()))
+}
+
+/// Whether the server rejected the credentials of a request.
+pub fn is_auth_error(err: &object_store::Error) -> bool {
+    matches!(
+        err,
+        object_store::Error::PermissionDenied { .. } | object_store::Error::Unauthenticated { .. }
+    )
+}
+
+/// Wraps the store of `url`, rebuilding it with refreshed credentials on the first
+/// authentication failure of each request.
+#[derive(Debug)]
+pub struct RefreshingStore {
+    registry: OpendalRegistry,
+    url: Url,
+    store: RwLock<Arc<dyn ObjectStore>>,
+}
+
+impl RefreshingStore {
+    pub fn new(registry: OpendalRegistry, url: Url, store: Arc<dyn ObjectStore>) -> Self {
+        Self {
+            registry,
+            url,
+            store: RwLock::new(store),
+        }
+    }
+
+    fn current(&self) -> Arc<dyn ObjectStore> {
+        self.store.read().unwrap().clone()
+    }
+
+    async fn retry<T, F, Fut>(&self, f: F) -> Result<T>
+    where
+        F: Fn(Arc<dyn ObjectStore>) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        match f(self.current()).await {
+            Err(err) if is_auth_error(&err) => f(self.refresh().await?).await,
+            result => result,
+        }
+    }
+
+    /// Rebuild the store with new credentials from the callback.
+    async fn refresh(&self) -> Result<Arc<dyn ObjectStore>> {
+        logger::warn(format_args!(
+            "{} rejected the credentials, asking for new ones",
+            self.url
+        ));
+        let store = ForceSend::new(self.registry.refresh_credentials(&self.url)).await?;
+        *self.store.write().unwrap() = store.clone();
+        Ok(store)
+    }
+}
+
+impl std::fmt::Display for RefreshingStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Refreshing({})", self.current())
+    }
+}
+
+#[async_trait]
+impl ObjectStore for RefreshingStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.retry(|store| {
+            let (payload, opts) = (payload.clone(), opts.clone());
+            async move { store.put_opts(location, payload, opts).await }
+        })
+        .await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        // the upload keeps the store it was started with, parts aren't retried
+        self.retry(|store| {
+            let opts = opts.clone();
+            async move { store.put_multipart_opts(location, opts).await }
+        })
+        .await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.retry(|store| {
+            let options = options.clone();
+            async move { store.get_opts(location, options).await }
+        })
+        .await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.retry(|store| {
+            let range = range.clone();
+            async move { store.get_range(location, range).await }
+        })
+        .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.retry(|store| async move { store.get_ranges(location, ranges).await })
+            .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.retry(|store| async move { store.head(location).await })
+            .await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.retry(|store| async move { store.delete(location).await })
+            .await
+    }
+
+    /// Retries a listing rejected on its first page, later pages stream through.
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned();
+        // the listing borrows its store, which a refresh replaces, so a future owning
+        // the store feeds the returned stream
+        let (mut sender, receiver) = mpsc::channel(0);
+        let feed = async move {
+            let mut store = self.current();
+            let mut refreshed = false;
+            loop {
+                let mut listing = store.list(prefix.as_ref());
+                match listing.next().await {
+                    Some(Err(err)) if !refreshed && is_auth_error(&err) => {
+                        drop(listing);
+                        refreshed = true;
+                        match self.refresh().await {
+                            Ok(new_store) => store = new_store,
+                            Err(err) => {
+                                let _ = sender.send(Err(err)).await;
+                                return;
+                            }
+                        }
+                    }
+                    first => {
+                        let mut listing = futures::stream::iter(first).chain(listing);
+                        while let Some(meta) = listing.next().await {
+                            if sender.send(meta).await.is_err() {
+                                return;
+                            }
+                        }
+                        return;
+                    }
+                }
+            }
+        };
+        let feed = feed
+            .into_stream()
+            .filter_map(|()| futures::future::ready(None));
+        futures::stream::select(receiver, feed).boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.retry(|store| async move { store.list_with_delimiter(prefix).await })
+            .await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.retry(|store| async move { store.copy(from, to).await })
+            .await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.retry(|store| async move { store.rename(from, to).await })
+            .await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.retry(|store| async move { store.copy_if_not_exists(from, to).await })
+            .await
+    }
+}
+
+fn callback_error(err: JsValue) -> object_store::Error {
+    generic_error(
+        err.as_string()
+            .unwrap_or_else(|| format!("credential callback failed: {err:?}")),
+    )
+}
+
+fn generic_error(message: String) -> object_store::Error {
+    object_store::Error::Generic {
+        store: "credentials",
+        source: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_credentials() {
+        let credentials: Credentials =
+            serde_json::from_str(r#"{"access_key_id": "a", "secret_access_key": "b"}"#).unwrap();
+        assert_eq!(credentials.access_key_id, "a");
+        assert_eq!(credentials.session_token, "");
+        assert!(serde_json::from_str::<Credentials>(r#"{"access_key_id": "a"}"#).is_err());
+    }
+
+    #[test]
+    fn test_is_auth_error() {
+        let denied = object_store::Error::PermissionDenied {
+            path: "a".to_string(),
+            source: "403".into(),
+        };
+        assert!(is_auth_error(&denied));
+        let missing = object_store::Error::NotFound {
+            path: "a".to_string(),
+            source: "404".into(),
+        };
+        assert!(!is_auth_error(&missing));
+    }
+
+    #[tokio::test]
+    async fn test_list_and_upload_without_refresh() {
+        let store = RefreshingStore::new(
+            OpendalRegistry::new(),
+            Url::parse("s3://bucket").unwrap(),
+            Arc::new(object_store::memory::InMemory::new()),
+        );
+        for name in ["a", "b", "c"] {
+            let mut upload = store
+                .put_multipart_opts(&Path::from(name), PutMultipartOpts::default())
+                .await
+                .unwrap();
+            upload
+                .put_part(PutPayload::from_static(b"1"))
+                .await
+                .unwrap();
+            upload.complete().await.unwrap();
+        }
+
+        let listed = store
+            .list(None)
+            .map(|meta| meta.unwrap().location.to_string())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(listed, ["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,296 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A DataFrame handle for building queries from JavaScript without SQL strings.
+//! Expressions are given as SQL expression text, e.g. `"price * quantity AS total"`.
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::common::JoinType;
+use datafusion::dataframe::DataFrame;
+use datafusion::logical_expr::{Expr, SortExpr};
+use wasm_bindgen::prelude::*;
+
+use crate::error::{Result, WasmError};
+use crate::listing::TableSchema;
+use crate::readable_stream::{self, ChunkEncoder};
+use crate::result_format::{RenderOptions, ResultFormat, ResultRenderer};
+
+/// How `collect` renders, taken from the context when the frame was created.
+#[derive(Clone)]
+pub struct Rendering {
+    pub format: ResultFormat,
+    pub renderer: Option<Arc<dyn ResultRenderer>>,
+    pub options: RenderOptions,
+}
+
+impl Rendering {
+    fn render(&self, record_batches: &[RecordBatch]) -> Result<Vec<u8>> {
+        match &self.renderer {
+            Some(renderer) => renderer.render(record_batches),
+            None => self.format.render_with(record_batches, &self.options),
+        }
+    }
+}
+
+/// An immutable query plan. Every transformation returns a new frame, and nothing
+/// runs until `collect` or `stream`.
+#[wasm_bindgen]
+pub struct WasmDataFrame {
+    df: DataFrame,
+    rendering: Rendering,
+}
+
+impl WasmDataFrame {
+    pub fn new(df: DataFrame, rendering: Rendering) -> Self {
+        Self { df, rendering }
+    }
+
+    fn with(&self, df: DataFrame) -> Self {
+        Self::new(df, self.rendering.clone())
+    }
+
+    /// Parse `sql`, an expression with an optional trailing `AS alias`.
+    fn expr(&self, sql: &str) -> Result<Expr> {
+        let (sql, alias) = split_alias(sql);
+        let expr = self.df.parse_sql_expr(sql)?;
+        Ok(match alias {
+            Some(alias) => expr.alias(alias),
+            None => expr,
+        })
+    }
+
+    fn exprs(&self, sql: &[String]) -> Result<Vec<Expr>> {
+        sql.iter().map(|sql| self.expr(sql)).collect()
+    }
+
+    /// Parse `sql`, an expression followed by optional `ASC` / `DESC` and
+    /// `NULLS FIRST` / `NULLS LAST` as in `ORDER BY`.
+    fn sort_expr(&self, sql: &str) -> Result<SortExpr> {
+        let mut sql = sql.trim();
+        let mut nulls_first = None;
+        for (suffix, first) in [(" NULLS FIRST", true), (" NULLS LAST", false)] {
+            if let Some(rest) = strip_suffix(sql, suffix) {
+                (sql, nulls_first) = (rest.trim_end(), Some(first));
+                break;
+            }
+        }
+        let mut asc = true;
+        for (suffix, ascending) in [(" ASC", true), (" DESC", false)] {
+            if let Some(rest) = strip_suffix(sql, suffix) {
+                (sql, asc) = (rest.trim_end(), ascending);
+                break;
+            }
+        }
+        // nulls come last in ascending and first in descending order, as in SQL
+        Ok(self.expr(sql)?.sort(asc, nulls_first.unwrap_or(!asc)))
+    }
+}
+
+#[wasm_bindgen]
+impl WasmDataFrame {
+    /// Keep the columns computed by `exprs`, e.g. `["name", "price * 1.2 AS gross"]`.
+    pub fn select(&self, exprs: Vec<String>) -> Result<WasmDataFrame> {
+        Ok(self.with(self.df.clone().select(self.exprs(&exprs)?)?))
+    }
+
+    /// Keep the rows matching `predicate`, e.g. `"price > 10 AND name LIKE 'a%'"`.
+    pub fn filter(&self, predicate: String) -> Result<WasmDataFrame> {
+        Ok(self.with(self.df.clone().filter(self.expr(&predicate)?)?))
+    }
+
+    /// Group by `group_by` and compute `aggregates`, e.g. `["sum(price) AS total"]`.
+    pub fn aggregate(
+        &self,
+        group_by: Vec<String>,
+        aggregates: Vec<String>,
+    ) -> Result<WasmDataFrame> {
+        let df = self
+            .df
+            .clone()
+            .aggregate(self.exprs(&group_by)?, self.exprs(&aggregates)?)?;
+        Ok(self.with(df))
+    }
+
+    /// Sort by `exprs`, each written as in `ORDER BY`, e.g. `["total DESC", "name"]`.
+    pub fn sort(&self, exprs: Vec<String>) -> Result<WasmDataFrame> {
+        let exprs = exprs
+            .iter()
+            .map(|sql| self.sort_expr(sql))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self.with(self.df.clone().sort(exprs)?))
+    }
+
+    /// Skip `skip` rows and keep at most `fetch` of the rest, all when `undefined`.
+    pub fn limit(&self, skip: usize, fetch: Option<usize>) -> Result<WasmDataFrame> {
+        Ok(self.with(self.df.clone().limit(skip, fetch)?))
+    }
+
+    /// Join with `right` on equal `left_on` and `right_on` columns. `join_type` is
+    /// `inner`, `left`, `right`, `full`, `left_semi`, `left_anti`, `right_semi` or
+    /// `right_anti`. Qualify columns with their table name when both sides have them.
+    pub fn join(
+        &self,
+        right: &WasmDataFrame,
+        join_type: String,
+        left_on: Vec<String>,
+        right_on: Vec<String>,
+    ) -> Result<WasmDataFrame> {
+        let left_on = left_on.iter().map(String::as_str).collect::<Vec<_>>();
+        let right_on = right_on.iter().map(String::as_str).collect::<Vec<_>>();
+        let df = self.df.clone().join(
+            right.df.clone(),
+            parse_join_type(&join_type)?,
+            &left_on,
+            &right_on,
+            None,
+        )?;
+        Ok(self.with(df))
+    }
+
+    /// The schema of the result as `{fields: [{name, data_type, nullable}]}`.
+    pub fn schema(&self) -> TableSchema {
+        TableSchema::new(self.df.schema().inner())
+    }
+
+    /// Run the plan and render the result in the context's result format.
+    pub async fn collect(&self) -> Result<String> {
+        let batches = self.df.clone().collect().await?;
+        Ok(String::from_utf8(self.rendering.render(&batches)?)?)
+    }
+
+    /// Run the plan and return a `ReadableStream` of the result, encoded like
+    /// `execute_sql_readable_stream`.
+    pub async fn stream(&self, format: String) -> Result<JsValue> {
+        let stream = self.df.clone().execute_stream().await?;
+        let encoder = ChunkEncoder::new(
+            &format,
+            &stream.schema(),
+            self.rendering.options.ipc_compression,
+        )?;
+        readable_stream::readable_stream(stream, encoder)
+    }
+}
+
+fn parse_join_type(join_type: &str) -> Result<JoinType> {
+    Ok(match join_type.to_ascii_lowercase().as_str() {
+        "inner" => JoinType::Inner,
+        "left" => JoinType::Left,
+        "right" => JoinType::Right,
+        "full" => JoinType::Full,
+        "left_semi" => JoinType::LeftSemi,
+        "left_anti" => JoinType::LeftAnti,
+        "right_semi" => JoinType::RightSemi,
+        "right_anti" => JoinType::RightAnti,
+        _ => return Err(WasmError::Other(format!("unknown join type {join_type}"))),
+    })
+}
+
+/// `sql` without a case-insensitive `suffix`.
+fn strip_suffix<'a>(sql: &'a str, suffix: &str) -> Option<&'a str> {
+    let start = sql.len().checked_sub(suffix.len())?;
+    sql.get(start..)?
+        .eq_ignore_ascii_case(suffix)
+        .then(|| &sql[..start])
+}
+
+/// Split a trailing `AS alias` off `sql`, ignoring `AS` inside parentheses and
+/// quotes such as in `CAST(x AS INT)`.
+fn split_alias(sql: &str) -> (&str, Option<&str>) {
+    let bytes = sql.as_bytes();
+    let (mut depth, mut quote) = (0usize, None);
+    let mut split = None;
+    for (i, &byte) in bytes.iter().enumerate() {
+        match (quote, byte) {
+            (Some(open), _) if byte == open => quote = None,
+            (Some(_), _) => {}
+            (None, b'\'' | b'"') => quote = Some(byte),
+            (None, b'(') => depth += 1,
+            (None, b')') => depth = depth.saturating_sub(1),
+            (None, b' ') if depth == 0 && is_as(&bytes[i..]) => split = Some(i),
+            _ => {}
+        }
+    }
+    match split {
+        Some(i) => {
+            let alias = sql[i + 4..].trim();
+            let alias = alias
+                .strip_prefix('"')
+                .and_then(|alias| alias.strip_suffix('"'))
+                .unwrap_or(alias);
+            (sql[..i].trim(), Some(alias))
+        }
+        None => (sql.trim(), None),
+    }
+}
+
+fn is_as(bytes: &[u8]) -> bool {
+    bytes.len() >= 4 && bytes[..4].eq_ignore_ascii_case(b" as ")
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    #[test]
+    fn test_split_alias() {
+        assert_eq!(split_alias("a + 1 AS b"), ("a + 1", Some("b")));
+        assert_eq!(split_alias("CAST(a AS INT)"), ("CAST(a AS INT)", None));
+        assert_eq!(split_alias("'x as y' as \"Z\""), ("'x as y'", Some("Z")));
+    }
+
+    #[tokio::test]
+    async fn test_build_query() {
+        let ctx = SessionContext::new();
+        ctx.sql("CREATE TABLE t AS VALUES ('a', 1), ('a', 2), ('b', NULL)")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let df = WasmDataFrame::new(
+            ctx.table("t").await.unwrap(),
+            Rendering {
+                format: ResultFormat::Json,
+                renderer: None,
+                options: RenderOptions::default(),
+            },
+        );
+        let df = df
+            .filter("column1 <> 'c'".to_string())
+            .unwrap()
+            .aggregate(
+                vec!["column1 AS name".to_string()],
+                vec!["sum(column2) AS total".to_string()],
+            )
+            .unwrap()
+            .sort(vec!["total DESC".to_string()])
+            .unwrap()
+            .limit(0, Some(1))
+            .unwrap();
+        assert!(df.schema().fields.iter().any(|field| field.name == "total"));
+
+        let batches = df.df.clone().collect().await.unwrap();
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 1);
+        // the null total of `b` sorts first in descending order
+        assert!(batch.column(1).is_null(0));
+    }
+}
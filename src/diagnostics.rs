@@ -0,0 +1,181 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Structured parse diagnostics for editors.
+
+use datafusion::sql::parser::DFParser;
+use datafusion::sql::sqlparser::dialect::GenericDialect;
+use datafusion::sql::sqlparser::parser::{Parser, ParserError};
+use datafusion::sql::sqlparser::tokenizer::{Token, Tokenizer};
+use serde::Serialize;
+use tsify_next::Tsify;
+
+#[derive(Debug, Serialize, Tsify)]
+#[tsify(missing_as_null)]
+pub struct ParseError {
+    pub message: String,
+    /// 1-based line of the offending token, 0 when unknown.
+    pub line: u64,
+    /// 1-based column of the offending token, 0 when unknown.
+    pub column: u64,
+    pub expected: Option<String>,
+    pub found: Option<String>,
+}
+
+#[derive(Debug, Serialize, Tsify)]
+#[tsify(into_wasm_abi, missing_as_null)]
+pub struct ParseReport {
+    pub valid: bool,
+    /// Statements parsed successfully before the first error, in canonical SQL.
+    pub statements: Vec<String>,
+    pub error: Option<ParseError>,
+}
+
+impl ParseReport {
+    pub fn parse(sql: &str) -> Self {
+        let mut statements = Vec::new();
+        // `DFParser::new` drops token locations, so tokenize with them here
+        let dialect = GenericDialect {};
+        let tokens = match Tokenizer::new(&dialect, sql).tokenize_with_location() {
+            Ok(tokens) => tokens,
+            Err(err) => return Self::failed(statements, err.into(), 0, 0),
+        };
+        let mut parser = DFParser {
+            parser: Parser::new(&dialect).with_tokens_with_locations(tokens),
+        };
+
+        let mut expecting_statement_delimiter = false;
+        loop {
+            while parser.parser.consume_token(&Token::SemiColon) {
+                expecting_statement_delimiter = false;
+            }
+
+            let next = parser.parser.peek_token();
+            if next == Token::EOF {
+                break;
+            }
+            let (line, column) = (next.location.line, next.location.column);
+            if expecting_statement_delimiter {
+                let err = ParserError::ParserError(format!(
+                    "Expected: end of statement, found: {}",
+                    next.token
+                ));
+                return Self::failed(statements, err, line, column);
+            }
+
+            match parser.parse_statement() {
+                Ok(statement) => statements.push(statement.to_string()),
+                Err(err) => return Self::failed(statements, err, line, column),
+            }
+            expecting_statement_delimiter = true;
+        }
+
+        Self {
+            valid: true,
+            statements,
+            error: None,
+        }
+    }
+
+    fn failed(statements: Vec<String>, err: ParserError, line: u64, column: u64) -> Self {
+        let message = match &err {
+            ParserError::TokenizerError(message) | ParserError::ParserError(message) => {
+                message.clone()
+            }
+            ParserError::RecursionLimitExceeded => err.to_string(),
+        };
+        let (expected, found) = split_expected_found(&message);
+        // prefer the exact location reported by the parser over the statement start
+        let (line, column) = error_location(&message).unwrap_or((line, column));
+
+        Self {
+            valid: false,
+            statements,
+            error: Some(ParseError {
+                message,
+                line,
+                column,
+                expected,
+                found,
+            }),
+        }
+    }
+}
+
+/// Pull the two halves out of sqlparser's `Expected: X, found: Y at Line: L, Column: C`.
+fn split_expected_found(message: &str) -> (Option<String>, Option<String>) {
+    let Some(rest) = message.strip_prefix("Expected: ") else {
+        return (None, None);
+    };
+    let Some((expected, found)) = rest.split_once(", found: ") else {
+        return (Some(rest.to_string()), None);
+    };
+    let found = match found.rfind(" at Line: ") {
+        Some(index) => &found[..index],
+        None => found,
+    };
+
+    // some of sqlparser's messages say `an expression:`
+    let expected = expected.trim_end_matches(':');
+    (Some(expected.to_string()), Some(found.to_string()))
+}
+
+/// Extract the trailing `at Line: L, Column: C` from a sqlparser error message.
+fn error_location(message: &str) -> Option<(u64, u64)> {
+    let (_, location) = message.rsplit_once(" at Line: ")?;
+    let (line, column) = location.split_once(", Column: ")?;
+    Some((line.trim().parse().ok()?, column.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_sql() {
+        let report = ParseReport::parse("SELECT 1; SELECT 2");
+
+        assert!(report.valid);
+        assert_eq!(report.statements, vec!["SELECT 1", "SELECT 2"]);
+        assert!(report.error.is_none());
+    }
+
+    #[test]
+    fn test_error_keeps_partial_statements() {
+        let report = ParseReport::parse("SELECT 1;\nSELECT a FROM t WHERE )");
+
+        assert!(!report.valid);
+        assert_eq!(report.statements, vec!["SELECT 1"]);
+        let error = report.error.unwrap();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.expected.as_deref(), Some("an expression"));
+        assert_eq!(error.found.as_deref(), Some(")"));
+    }
+
+    #[test]
+    fn test_split_expected_found() {
+        assert_eq!(
+            split_expected_found("Expected: an expression, found: FROM at Line: 1, Column: 8"),
+            (Some("an expression".to_string()), Some("FROM".to_string()))
+        );
+        assert_eq!(split_expected_found("Unterminated string"), (None, None));
+        assert_eq!(
+            error_location("Expected: ), found: EOF at Line: 3, Column: 14"),
+            Some((3, 14))
+        );
+    }
+}
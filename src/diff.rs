@@ -0,0 +1,180 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Row-level differences between the results of two queries, matched by key
+//! columns.
+
+use std::collections::HashMap;
+
+use datafusion::arrow::array::{RecordBatch, UInt32Array};
+use datafusion::arrow::compute::{concat_batches, take_record_batch};
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::row::{RowConverter, SortField};
+
+use crate::error::{Result, WasmError};
+
+/// Rows only in the second result, only in the first, and rows whose key is in
+/// both but whose other values differ, before and after, in the same order.
+#[derive(Debug)]
+pub struct QueryDiff {
+    pub added: RecordBatch,
+    pub removed: RecordBatch,
+    pub changed_before: RecordBatch,
+    pub changed_after: RecordBatch,
+}
+
+pub fn diff(
+    schema: &SchemaRef,
+    before: &[RecordBatch],
+    after_schema: &SchemaRef,
+    after: &[RecordBatch],
+    key_columns: &[String],
+) -> Result<QueryDiff> {
+    if schema.fields() != after_schema.fields() {
+        return Err(WasmError::Other(
+            "both queries must return the same columns".to_string(),
+        ));
+    }
+    if key_columns.is_empty() {
+        return Err(WasmError::Other("no key columns given".to_string()));
+    }
+    let keys = key_columns
+        .iter()
+        .map(|name| Ok(schema.index_of(name)?))
+        .collect::<Result<Vec<_>>>()?;
+
+    let before = concat_batches(schema, before)?;
+    let after = concat_batches(schema, after)?;
+    let key_converter = RowConverter::new(
+        keys.iter()
+            .map(|index| SortField::new(schema.field(*index).data_type().clone()))
+            .collect(),
+    )?;
+    let row_converter = RowConverter::new(
+        schema
+            .fields()
+            .iter()
+            .map(|field| SortField::new(field.data_type().clone()))
+            .collect(),
+    )?;
+    let key_rows = |batch: &RecordBatch| {
+        let columns = keys
+            .iter()
+            .map(|index| batch.column(*index).clone())
+            .collect::<Vec<_>>();
+        key_converter.convert_columns(&columns)
+    };
+    let (before_keys, after_keys) = (key_rows(&before)?, key_rows(&after)?);
+    let before_rows = row_converter.convert_columns(before.columns())?;
+    let after_rows = row_converter.convert_columns(after.columns())?;
+
+    let mut unmatched = HashMap::with_capacity(before.num_rows());
+    for (index, key) in before_keys.iter().enumerate() {
+        if unmatched.insert(key, index).is_some() {
+            return Err(duplicate_key(key_columns));
+        }
+    }
+
+    let (mut added, mut changed_before, mut changed_after) = (vec![], vec![], vec![]);
+    let mut seen = HashMap::with_capacity(after.num_rows());
+    for (index, key) in after_keys.iter().enumerate() {
+        if seen.insert(key, index).is_some() {
+            return Err(duplicate_key(key_columns));
+        }
+        match unmatched.remove(&key) {
+            Some(previous) if before_rows.row(previous) != after_rows.row(index) => {
+                changed_before.push(previous as u32);
+                changed_after.push(index as u32);
+            }
+            Some(_) => {}
+            None => added.push(index as u32),
+        }
+    }
+    let mut removed = unmatched
+        .into_values()
+        .map(|index| index as u32)
+        .collect::<Vec<_>>();
+    removed.sort_unstable();
+
+    let take = |batch: &RecordBatch, indices: Vec<u32>| {
+        take_record_batch(batch, &UInt32Array::from(indices))
+    };
+    Ok(QueryDiff {
+        added: take(&after, added)?,
+        removed: take(&before, removed)?,
+        changed_before: take(&before, changed_before)?,
+        changed_after: take(&after, changed_after)?,
+    })
+}
+
+fn duplicate_key(key_columns: &[String]) -> WasmError {
+    WasmError::Other(format!(
+        "key ({}) is not unique in the results",
+        key_columns.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::{Int32Array, StringArray};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+
+    use super::*;
+
+    fn batch(ids: Vec<i32>, names: Vec<&str>) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(ids)),
+                Arc::new(StringArray::from(names)),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn ids(batch: &RecordBatch) -> Vec<i32> {
+        let column = batch.column(0).as_any().downcast_ref::<Int32Array>();
+        column.unwrap().values().to_vec()
+    }
+
+    #[test]
+    fn test_added_removed_changed() {
+        let before = batch(vec![1, 2, 3], vec!["a", "b", "c"]);
+        let after = batch(vec![3, 2, 4], vec!["c", "B", "d"]);
+        let schema = before.schema();
+        let diff = diff(&schema, &[before], &schema, &[after], &["id".to_string()]).unwrap();
+
+        assert_eq!(ids(&diff.added), vec![4]);
+        assert_eq!(ids(&diff.removed), vec![1]);
+        assert_eq!(ids(&diff.changed_before), vec![2]);
+        assert_eq!(ids(&diff.changed_after), vec![2]);
+    }
+
+    #[test]
+    fn test_duplicate_key() {
+        let before = batch(vec![1, 1], vec!["a", "b"]);
+        let schema = before.schema();
+        let err = diff(&schema, &[before], &schema, &[], &["id".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("not unique"));
+    }
+}
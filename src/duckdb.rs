@@ -0,0 +1,107 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Moving tables to and from DuckDB-WASM as Arrow IPC streams.
+//!
+//! From DuckDB, pass `tableToIPC(await conn.query(sql), "stream")` to
+//! `register_from_duckdb`. To DuckDB, pass the bytes of `export_to_duckdb` to
+//! `conn.insertArrowFromIPCStream(bytes, { name })`. Both sides use the IPC
+//! stream framing; the bytes are transferable, so they can cross to DuckDB's
+//! worker without another copy.
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+
+use crate::error::Result;
+use crate::result_format::write_ipc_stream;
+use crate::IpcCompression;
+
+/// An uncompressed IPC stream of `record_batches` that DuckDB-WASM can insert.
+pub fn to_ipc(schema: &SchemaRef, record_batches: &[RecordBatch]) -> Result<Vec<u8>> {
+    let schema = compatible_schema(schema);
+    let record_batches = record_batches
+        .iter()
+        .map(|batch| {
+            let columns = batch
+                .columns()
+                .iter()
+                .zip(schema.fields())
+                .map(|(column, field)| cast(column, field.data_type()))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            Ok(RecordBatch::try_new(schema.clone(), columns)?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // DuckDB-WASM doesn't read compressed buffers
+    write_ipc_stream(&schema, &record_batches, IpcCompression::None)
+}
+
+/// `schema` with the view types DuckDB-WASM's Arrow reader lacks replaced.
+fn compatible_schema(schema: &SchemaRef) -> SchemaRef {
+    let fields = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let data_type = match field.data_type() {
+                DataType::Utf8View => DataType::Utf8,
+                DataType::BinaryView => DataType::Binary,
+                data_type => data_type.clone(),
+            };
+            Field::clone(field).with_data_type(data_type)
+        })
+        .collect::<Vec<_>>();
+    Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc_input;
+    use datafusion::arrow::array::{Int32Array, StringViewArray};
+
+    #[test]
+    fn test_view_types_become_plain() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("name", DataType::Utf8View, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringViewArray::from(vec!["a", "b"])),
+            ],
+        )
+        .unwrap();
+
+        let data = to_ipc(&schema, &[batch]).unwrap();
+        let (read_schema, batches) = ipc_input::read_with_schema(&data).unwrap();
+        assert_eq!(read_schema.field(1).data_type(), &DataType::Utf8);
+        assert_eq!(batches[0].num_rows(), 2);
+    }
+
+    #[test]
+    fn test_empty_result_keeps_schema() {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let data = to_ipc(&schema, &[]).unwrap();
+        let (read_schema, batches) = ipc_input::read_with_schema(&data).unwrap();
+        assert_eq!(read_schema.fields().len(), 1);
+        assert!(batches.is_empty());
+    }
+}
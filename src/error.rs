@@ -16,8 +16,21 @@
 // under the License.
 
 use thiserror::Error;
+use wasm_bindgen::prelude::wasm_bindgen;
 use wasm_bindgen::JsValue;
 
+#[wasm_bindgen(typescript_custom_section)]
+const TS_ERROR_NAMES: &str = r#"
+/**
+ * `name` of the `Error`s thrown for failures a host may handle. Other failures
+ * throw the message as a string.
+ */
+export type DataFusionErrorName =
+    | "QuotaExceededError"
+    | "ResourceLimitError"
+    | "StatementRejectedError";
+"#;
+
 pub type Result<T> = std::result::Result<T, WasmError>;
 
 #[derive(Error, Debug)]
@@ -25,19 +38,77 @@ pub enum WasmError {
     #[error("failed to parse: {0}")]
     ParserError(#[from] datafusion::sql::sqlparser::parser::ParserError),
     #[error("datafusion error: {0}")]
-    DataFusionError(#[from] datafusion::error::DataFusionError),
+    DataFusionError(#[source] datafusion::error::DataFusionError),
     #[error("arrow error: {0}")]
     ArrowError(#[from] datafusion::arrow::error::ArrowError),
     #[error("io error: {0}")]
     IoError(#[from] std::io::Error),
     #[error("utf8 error: {0}")]
     Utf8Error(#[from] std::string::FromUtf8Error),
+    #[cfg(feature = "parquet")]
+    #[error("parquet error: {0}")]
+    ParquetError(#[from] parquet::errors::ParquetError),
+    #[error("json error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("storage quota exceeded: {requested} bytes requested, {available} available")]
+    QuotaExceeded { requested: u64, available: u64 },
+    #[error("invalid Arrow IPC data at byte {offset}: {reason}")]
+    InvalidIpc { offset: usize, reason: String },
+    #[error("query exceeded the {resource} limit: {used} > {limit}")]
+    ResourceExhausted {
+        resource: &'static str,
+        limit: u64,
+        used: u64,
+    },
+    #[error("{kind} statement rejected: {reason}")]
+    StatementRejected { kind: String, reason: String },
     #[error("other error: {0}")]
     Other(String),
 }
 
-impl Into<JsValue> for WasmError {
-    fn into(self) -> JsValue {
-        JsValue::from_str(&self.to_string())
+impl From<datafusion::error::DataFusionError> for WasmError {
+    fn from(err: datafusion::error::DataFusionError) -> Self {
+        // a refused object store write, e.g. of `COPY TO`, keeps its type
+        if let datafusion::error::DataFusionError::ObjectStore(object_store::Error::Generic {
+            source,
+            ..
+        }) = err.find_root()
+        {
+            if let Some(&WasmError::QuotaExceeded {
+                requested,
+                available,
+            }) = source.downcast_ref::<WasmError>()
+            {
+                return WasmError::QuotaExceeded {
+                    requested,
+                    available,
+                };
+            }
+        }
+        WasmError::DataFusionError(err)
+    }
+}
+
+impl From<WasmError> for JsValue {
+    fn from(err: WasmError) -> Self {
+        match err {
+            // an `Error` with `name` set, so hosts can tell it apart from I/O failures
+            WasmError::QuotaExceeded { .. } => {
+                let error = js_sys::Error::new(&err.to_string());
+                error.set_name("QuotaExceededError");
+                error.into()
+            }
+            WasmError::ResourceExhausted { .. } => {
+                let error = js_sys::Error::new(&err.to_string());
+                error.set_name("ResourceLimitError");
+                error.into()
+            }
+            WasmError::StatementRejected { .. } => {
+                let error = js_sys::Error::new(&err.to_string());
+                error.set_name("StatementRejectedError");
+                error.into()
+            }
+            _ => JsValue::from_str(&err.to_string()),
+        }
     }
 }
@@ -0,0 +1,155 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Query lifecycle events for embedders, such as tab spinners and history panels.
+
+use std::sync::{Arc, Mutex};
+
+use js_sys::Function;
+use serde::Serialize;
+use tsify_next::Tsify;
+use wasm_bindgen::JsValue;
+
+use crate::error::{Result, WasmError};
+use crate::progress::JsCallback;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Start,
+    Batch,
+    End,
+}
+
+impl EventKind {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "start" => Ok(Self::Start),
+            "batch" => Ok(Self::Batch),
+            "end" => Ok(Self::End),
+            _ => Err(WasmError::Other(format!(
+                "unknown query event {name}, expected start, batch or end"
+            ))),
+        }
+    }
+}
+
+/// A query began running.
+#[derive(Debug, Clone, Serialize, Tsify)]
+pub struct StartEvent {
+    pub query_id: u64,
+    pub sql: String,
+    pub label: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub started_at: i64,
+}
+
+/// A statement of a query produced another batch.
+#[derive(Debug, Clone, Serialize, Tsify)]
+pub struct BatchEvent {
+    pub query_id: u64,
+    pub statement: usize,
+    /// Batches and rows the statement produced so far.
+    pub batches: usize,
+    pub rows: usize,
+    pub elapsed_ms: i64,
+}
+
+/// A query finished, `error` being set if it failed.
+#[derive(Debug, Clone, Serialize, Tsify)]
+pub struct EndEvent {
+    pub query_id: u64,
+    pub sql: String,
+    pub label: Option<String>,
+    pub elapsed_ms: i64,
+    pub rows: usize,
+    pub batches: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Listeners {
+    start: Vec<JsCallback>,
+    batch: Vec<JsCallback>,
+    end: Vec<JsCallback>,
+}
+
+impl Listeners {
+    fn of(&mut self, kind: EventKind) -> &mut Vec<JsCallback> {
+        match kind {
+            EventKind::Start => &mut self.start,
+            EventKind::Batch => &mut self.batch,
+            EventKind::End => &mut self.end,
+        }
+    }
+}
+
+/// Calls every listener of an event with the event as a JS object.
+#[derive(Debug, Default, Clone)]
+pub struct QueryEvents {
+    listeners: Arc<Mutex<Listeners>>,
+}
+
+impl QueryEvents {
+    pub fn add(&self, kind: EventKind, callback: Function) {
+        self.listeners
+            .lock()
+            .unwrap()
+            .of(kind)
+            .push(JsCallback(callback));
+    }
+
+    /// Remove `callback`, compared by identity, from the listeners of `kind`.
+    pub fn remove(&self, kind: EventKind, callback: &Function) {
+        let mut listeners = self.listeners.lock().unwrap();
+        let callbacks = listeners.of(kind);
+        if let Some(index) = callbacks
+            .iter()
+            .position(|listener| listener.0 == *callback)
+        {
+            callbacks.remove(index);
+        }
+    }
+
+    pub fn emit(&self, kind: EventKind, event: &impl Serialize) {
+        let callbacks = self.listeners.lock().unwrap().of(kind).clone();
+        if callbacks.is_empty() {
+            return;
+        }
+        let Ok(json) = serde_json::to_string(event) else {
+            return;
+        };
+        let Ok(event) = js_sys::JSON::parse(&json) else {
+            return;
+        };
+        // called without holding the lock, a listener may add or remove listeners;
+        // a failing listener doesn't fail the query
+        for callback in callbacks {
+            let _ = callback.0.call1(&JsValue::NULL, &event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_event_kind() {
+        assert_eq!(EventKind::parse("batch").unwrap(), EventKind::Batch);
+        assert!(EventKind::parse("finish").is_err());
+    }
+}
@@ -0,0 +1,97 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Per-call options of `execute_sql`.
+
+use std::collections::BTreeMap;
+
+use datafusion::common::config::ConfigOptions;
+use serde::Deserialize;
+use tsify_next::Tsify;
+
+use crate::error::{Result, WasmError};
+use crate::scheduling::QueryPriority;
+
+/// Options given as a JSON object, e.g. `{"label": "dashboard:sales"}`.
+#[derive(Debug, Clone, Default, Deserialize, Tsify)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecuteOptions {
+    /// Name the query is attributed to in the history and metrics.
+    pub label: Option<String>,
+    /// Free-form key/value tags, kept in the history.
+    pub tags: BTreeMap<String, String>,
+    /// Namespace to run in, see `mount_namespace`.
+    pub namespace: Option<String>,
+    /// `"interactive"` (the default) or `"background"`.
+    #[tsify(type = "\"interactive\" | \"background\"")]
+    pub priority: QueryPriority,
+    /// Session options overridden for this call only, e.g.
+    /// `{"datafusion.execution.batch_size": 1024}`.
+    #[tsify(type = "Record<string, string | number | boolean>")]
+    pub config: BTreeMap<String, serde_json::Value>,
+}
+
+impl ExecuteOptions {
+    /// Apply the `config` overrides to `options`.
+    pub fn apply_config(&self, options: &mut ConfigOptions) -> Result<()> {
+        for (key, value) in &self.config {
+            let value = match value {
+                serde_json::Value::String(value) => value.clone(),
+                serde_json::Value::Null => {
+                    return Err(WasmError::Other(format!("no value given for option {key}")))
+                }
+                value => value.to_string(),
+            };
+            options.set(key, &value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let options: ExecuteOptions = serde_json::from_str(
+            r#"{"label": "dashboard:sales", "tags": {"team": "growth"}, "namespace": "a", "priority": "background"}"#,
+        )
+        .unwrap();
+        assert_eq!(options.label.as_deref(), Some("dashboard:sales"));
+        assert_eq!(options.tags["team"], "growth");
+        assert_eq!(options.namespace.as_deref(), Some("a"));
+        assert_eq!(options.priority, QueryPriority::Background);
+        assert!(serde_json::from_str::<ExecuteOptions>(r#"{"lable": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_apply_config() {
+        let options: ExecuteOptions = serde_json::from_str(
+            r#"{"config": {"datafusion.execution.batch_size": 1024, "datafusion.optimizer.max_passes": "1"}}"#,
+        )
+        .unwrap();
+        let mut config = ConfigOptions::default();
+        options.apply_config(&mut config).unwrap();
+        assert_eq!(config.execution.batch_size, 1024);
+        assert_eq!(config.optimizer.max_passes, 1);
+
+        let options: ExecuteOptions =
+            serde_json::from_str(r#"{"config": {"datafusion.no_such_option": 1}}"#).unwrap();
+        assert!(options.apply_config(&mut config).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Arrow extension types known to the session.
+//!
+//! Extension types are carried as field metadata, which survives column
+//! references through planning. Registered names can be attached to columns
+//! of schema overrides and are reported by schema descriptions.
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use datafusion::arrow::array::{Array, ArrayRef, AsArray, RecordBatch, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+
+use crate::error::{Result, WasmError};
+
+pub const EXTENSION_NAME_KEY: &str = "ARROW:extension:name";
+pub const EXTENSION_METADATA_KEY: &str = "ARROW:extension:metadata";
+
+const UUID: &str = "arrow.uuid";
+
+fn registry() -> &'static RwLock<HashMap<String, Vec<DataType>>> {
+    static EXTENSIONS: OnceLock<RwLock<HashMap<String, Vec<DataType>>>> = OnceLock::new();
+    EXTENSIONS.get_or_init(|| {
+        let strings = vec![DataType::Utf8, DataType::LargeUtf8, DataType::Utf8View];
+        let binaries = vec![
+            DataType::Binary,
+            DataType::LargeBinary,
+            DataType::BinaryView,
+        ];
+        RwLock::new(HashMap::from([
+            (UUID.to_string(), vec![DataType::FixedSizeBinary(16)]),
+            ("arrow.json".to_string(), strings.clone()),
+            ("geoarrow.wkb".to_string(), binaries),
+            ("geoarrow.wkt".to_string(), strings),
+        ]))
+    })
+}
+
+/// Register extension type `name`, stored as any of `storage_types`, replacing
+/// a previous registration of the same name.
+pub fn register_extension_type(name: impl Into<String>, storage_types: Vec<DataType>) {
+    registry()
+        .write()
+        .unwrap()
+        .insert(name.into(), storage_types);
+}
+
+pub fn extension_type_names() -> Vec<String> {
+    let mut names: Vec<String> = registry().read().unwrap().keys().cloned().collect();
+    names.sort();
+    names
+}
+
+/// The extension type name of `field`, if it has one.
+pub fn extension_name(field: &Field) -> Option<&str> {
+    field.metadata().get(EXTENSION_NAME_KEY).map(String::as_str)
+}
+
+/// `field` tagged as extension type `name`, which must be registered and accept
+/// the field's type as storage.
+pub fn with_extension(field: Field, name: &str) -> Result<Field> {
+    let registry = registry().read().unwrap();
+    let storage_types = registry
+        .get(name)
+        .ok_or_else(|| WasmError::Other(format!("unknown extension type {name}")))?;
+    if !storage_types.contains(field.data_type()) {
+        return Err(WasmError::Other(format!(
+            "column {}: extension type {name} can't be stored as {}",
+            field.name(),
+            field.data_type()
+        )));
+    }
+
+    let mut metadata = field.metadata().clone();
+    metadata.insert(EXTENSION_NAME_KEY.to_string(), name.to_string());
+    Ok(field.with_metadata(metadata))
+}
+
+/// Replace columns whose storage isn't readable as text, currently UUIDs, with
+/// their canonical string form for the text formats.
+pub fn decode_for_display(batch: &RecordBatch) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    let is_uuid = |field: &Field| {
+        extension_name(field) == Some(UUID) && field.data_type() == &DataType::FixedSizeBinary(16)
+    };
+    if !schema.fields().iter().any(|field| is_uuid(field)) {
+        return Ok(batch.clone());
+    }
+
+    let mut fields = Vec::with_capacity(batch.num_columns());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        if is_uuid(field) {
+            fields.push(Arc::new(Field::new(
+                field.name(),
+                DataType::Utf8,
+                field.is_nullable(),
+            )));
+            columns.push(uuid_strings(column));
+        } else {
+            fields.push(field.clone());
+            columns.push(column.clone());
+        }
+    }
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone())),
+        columns,
+    )?)
+}
+
+fn uuid_strings(array: &ArrayRef) -> ArrayRef {
+    let array = array.as_fixed_size_binary();
+    Arc::new(
+        (0..array.len())
+            .map(|row| {
+                array.is_valid(row).then(|| {
+                    let hex: String = array
+                        .value(row)
+                        .iter()
+                        .map(|byte| format!("{byte:02x}"))
+                        .collect();
+                    format!(
+                        "{}-{}-{}-{}-{}",
+                        &hex[0..8],
+                        &hex[8..12],
+                        &hex[12..16],
+                        &hex[16..20],
+                        &hex[20..32]
+                    )
+                })
+            })
+            .collect::<StringArray>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::FixedSizeBinaryArray;
+
+    #[test]
+    fn test_with_extension() {
+        let field =
+            with_extension(Field::new("id", DataType::FixedSizeBinary(16), false), UUID).unwrap();
+        assert_eq!(extension_name(&field), Some(UUID));
+        assert!(with_extension(Field::new("id", DataType::Utf8, false), UUID).is_err());
+        assert!(with_extension(Field::new("id", DataType::Utf8, false), "x.unknown").is_err());
+
+        register_extension_type("x.ip", vec![DataType::FixedSizeBinary(4)]);
+        assert!(extension_type_names().contains(&"x.ip".to_string()));
+    }
+
+    #[test]
+    fn test_decode_uuid() {
+        let field =
+            with_extension(Field::new("id", DataType::FixedSizeBinary(16), true), UUID).unwrap();
+        let ids = FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+            vec![Some((0u8..16).collect::<Vec<_>>()), None].into_iter(),
+            16,
+        )
+        .unwrap();
+        let batch =
+            RecordBatch::try_new(Arc::new(Schema::new(vec![field])), vec![Arc::new(ids)]).unwrap();
+
+        let decoded = decode_for_display(&batch).unwrap();
+        let ids = decoded.column(0).as_string::<i32>();
+        assert_eq!(ids.value(0), "00010203-0405-0607-0809-0a0b0c0d0e0f");
+        assert!(ids.is_null(1));
+    }
+}
@@ -0,0 +1,382 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Full-text search: `fts_match(text, query)` (alias `match`), backed by an inverted
+//! index that `DataFusionContext::fts_index` builds over table columns.
+//!
+//! The index is keyed by document text, so it stays correct as tables change: values
+//! it doesn't know are matched by indexing them on the fly.
+
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use datafusion::arrow::array::{Array, AsArray, BooleanArray};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::DataType;
+use datafusion::execution::context::SessionContext;
+use datafusion::execution::FunctionRegistry;
+use datafusion::logical_expr::{ColumnarValue, ScalarUDF, ScalarUDFImpl, Signature, Volatility};
+use datafusion::prelude::ident;
+
+use crate::error::{Result, WasmError};
+
+const FUNCTION_NAME: &str = "fts_match";
+
+/// Register `fts_match` with an empty index, unless it is registered already.
+pub fn register(ctx: &SessionContext) {
+    let registered = ctx
+        .state_ref()
+        .read()
+        .scalar_functions()
+        .contains_key(FUNCTION_NAME);
+    if !registered {
+        ctx.register_udf(ScalarUDF::from(FtsMatch::default()));
+    }
+}
+
+/// Add every value of `column` of table `name` to the index. Returns the number of
+/// distinct documents indexed.
+pub async fn index_column(ctx: &SessionContext, name: &str, column: &str) -> Result<usize> {
+    let udf = ctx.udf(FUNCTION_NAME)?;
+    let fts = udf
+        .inner()
+        .as_any()
+        .downcast_ref::<FtsMatch>()
+        .ok_or_else(|| WasmError::Other(format!("{FUNCTION_NAME} was replaced")))?;
+    let batches = ctx
+        .table(name)
+        .await?
+        .select(vec![ident(column)])?
+        .collect()
+        .await?;
+
+    let mut index = fts.index.write().unwrap();
+    let documents = index.documents.len();
+    for batch in batches {
+        let values = cast(batch.column(0), &DataType::Utf8)?;
+        for value in values.as_string::<i32>().iter().flatten() {
+            index.add(value);
+        }
+    }
+    Ok(index.documents.len() - documents)
+}
+
+#[derive(Debug)]
+struct FtsMatch {
+    signature: Signature,
+    aliases: Vec<String>,
+    index: RwLock<InvertedIndex>,
+}
+
+impl Default for FtsMatch {
+    fn default() -> Self {
+        Self {
+            signature: Signature::exact(vec![DataType::Utf8, DataType::Utf8], Volatility::Stable),
+            aliases: vec!["match".to_string()],
+            index: RwLock::default(),
+        }
+    }
+}
+
+impl ScalarUDFImpl for FtsMatch {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        FUNCTION_NAME
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> datafusion::error::Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> datafusion::error::Result<ColumnarValue> {
+        let arrays = ColumnarValue::values_to_arrays(args)?;
+        let texts = cast(&arrays[0], &DataType::Utf8)?;
+        let queries = cast(&arrays[1], &DataType::Utf8)?;
+        let (texts, queries) = (texts.as_string::<i32>(), queries.as_string::<i32>());
+
+        let index = self.index.read().unwrap();
+        // documents matching each query, as it's usually the same for every row
+        let mut matches = HashMap::<&str, (Query, HashSet<u32>)>::new();
+        let result: BooleanArray = (0..texts.len())
+            .map(|row| {
+                if texts.is_null(row) || queries.is_null(row) {
+                    return None;
+                }
+                let (query, documents) =
+                    matches.entry(queries.value(row)).or_insert_with_key(|q| {
+                        let query = Query::parse(q);
+                        let documents = index.search(&query);
+                        (query, documents)
+                    });
+                let text = texts.value(row);
+                Some(match index.documents.get(text) {
+                    Some(id) => documents.contains(id),
+                    None => {
+                        let mut document = InvertedIndex::default();
+                        document.add(text);
+                        !document.search(query).is_empty()
+                    }
+                })
+            })
+            .collect();
+        Ok(ColumnarValue::Array(Arc::new(result)))
+    }
+}
+
+/// Lowercased alphanumeric words.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+#[derive(Debug, Default)]
+struct InvertedIndex {
+    /// Document ids by text.
+    documents: HashMap<String, u32>,
+    /// Positions of each term in each document, by term, sorted for prefix lookups.
+    postings: BTreeMap<String, HashMap<u32, Vec<u32>>>,
+}
+
+impl InvertedIndex {
+    fn add(&mut self, text: &str) {
+        if self.documents.contains_key(text) {
+            return;
+        }
+        let id = self.documents.len() as u32;
+        self.documents.insert(text.to_string(), id);
+        for (position, term) in tokenize(text).enumerate() {
+            self.postings
+                .entry(term)
+                .or_default()
+                .entry(id)
+                .or_default()
+                .push(position as u32);
+        }
+    }
+
+    /// Documents with every required clause of `query` and none of the excluded ones.
+    fn search(&self, query: &Query) -> HashSet<u32> {
+        let mut required = query.required.iter();
+        let Some(first) = required.next() else {
+            return HashSet::new();
+        };
+        let mut documents = self.clause(first);
+        for clause in required {
+            let matching = self.clause(clause);
+            documents.retain(|id| matching.contains(id));
+        }
+        for clause in &query.excluded {
+            for id in self.clause(clause) {
+                documents.remove(&id);
+            }
+        }
+        documents
+    }
+
+    fn clause(&self, clause: &Clause) -> HashSet<u32> {
+        match clause {
+            Clause::Term(term) => self
+                .postings
+                .get(term)
+                .map(|postings| postings.keys().copied().collect())
+                .unwrap_or_default(),
+            Clause::Prefix(prefix) => self
+                .postings
+                .range(prefix.clone()..)
+                .take_while(|(term, _)| term.starts_with(prefix.as_str()))
+                .flat_map(|(_, postings)| postings.keys().copied())
+                .collect(),
+            Clause::Phrase(terms) => {
+                let Some(postings) = terms
+                    .iter()
+                    .map(|term| self.postings.get(term))
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    return HashSet::new();
+                };
+                postings[0]
+                    .iter()
+                    .filter(|(id, starts)| {
+                        starts.iter().any(|start| {
+                            postings[1..].iter().enumerate().all(|(offset, term)| {
+                                term.get(*id).is_some_and(|positions| {
+                                    positions.contains(&(start + offset as u32 + 1))
+                                })
+                            })
+                        })
+                    })
+                    .map(|(id, _)| *id)
+                    .collect()
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Clause {
+    Term(String),
+    /// `term*`
+    Prefix(String),
+    /// `"several terms"`, in order.
+    Phrase(Vec<String>),
+}
+
+/// Words that must all appear, with `term*` prefixes, `"quoted phrases"` and `-excluded`
+/// words or phrases.
+#[derive(Debug, Default, PartialEq)]
+struct Query {
+    required: Vec<Clause>,
+    excluded: Vec<Clause>,
+}
+
+impl Query {
+    fn parse(query: &str) -> Self {
+        let mut parsed = Self::default();
+        let mut rest = query.trim_start();
+        while !rest.is_empty() {
+            let (excluded, after) = match rest.strip_prefix('-') {
+                Some(after) => (true, after),
+                None => (false, rest),
+            };
+            let (clauses, after) = match after.strip_prefix('"') {
+                Some(quoted) => {
+                    let (phrase, after) = quoted.split_once('"').unwrap_or((quoted, ""));
+                    let terms = tokenize(phrase).collect::<Vec<_>>();
+                    let clause = match terms.len() {
+                        0 => vec![],
+                        1 => vec![Clause::Term(terms[0].clone())],
+                        _ => vec![Clause::Phrase(terms)],
+                    };
+                    (clause, after)
+                }
+                None => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    let (word, after) = after.split_at(end);
+                    let prefix = word.ends_with('*');
+                    let mut terms = tokenize(word).collect::<Vec<_>>();
+                    let last = terms.pop().map(|term| match prefix {
+                        true => Clause::Prefix(term),
+                        false => Clause::Term(term),
+                    });
+                    let mut clauses = terms.into_iter().map(Clause::Term).collect::<Vec<_>>();
+                    clauses.extend(last);
+                    (clauses, after)
+                }
+            };
+            match excluded {
+                true => parsed.excluded.extend(clauses),
+                false => parsed.required.extend(clauses),
+            }
+            rest = after.trim_start();
+        }
+        parsed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::{RecordBatch, StringArray};
+    use datafusion::arrow::datatypes::{Field, Schema};
+    use datafusion::datasource::MemTable;
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(
+            Query::parse(r#"Rust wasm* -"web worker" -java"#),
+            Query {
+                required: vec![
+                    Clause::Term("rust".to_string()),
+                    Clause::Prefix("wasm".to_string())
+                ],
+                excluded: vec![
+                    Clause::Phrase(vec!["web".to_string(), "worker".to_string()]),
+                    Clause::Term("java".to_string())
+                ],
+            }
+        );
+        assert_eq!(Query::parse("  "), Query::default());
+    }
+
+    #[test]
+    fn test_search() {
+        let mut index = InvertedIndex::default();
+        index.add("A query engine in WebAssembly");
+        index.add("A web worker runs the query engine");
+        index.add("Engine, query: reversed");
+
+        let search = |query| {
+            let mut ids = index
+                .search(&Query::parse(query))
+                .into_iter()
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+        assert_eq!(search("query engine*"), vec![0, 1, 2]);
+        assert_eq!(search(r#""query engine""#), vec![0, 1]);
+        assert_eq!(search("engine -web"), vec![0, 2]);
+        assert_eq!(search("missing"), Vec::<u32>::new());
+        assert_eq!(search("-web"), Vec::<u32>::new());
+    }
+
+    #[tokio::test]
+    async fn test_fts_match() {
+        let ctx = SessionContext::new();
+        register(&ctx);
+        let schema = Arc::new(Schema::new(vec![Field::new("body", DataType::Utf8, true)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from(vec![
+                Some("DataFusion in the browser"),
+                Some("Arrow columnar memory"),
+                None,
+            ]))],
+        )
+        .unwrap();
+        let table = MemTable::try_new(schema, vec![vec![batch]]).unwrap();
+        ctx.register_table("docs", Arc::new(table)).unwrap();
+
+        let query = "SELECT count(*) FROM docs WHERE fts_match(body, 'browser datafus*')";
+        let count = |batches: Vec<RecordBatch>| {
+            batches[0]
+                .column(0)
+                .as_primitive::<datafusion::arrow::datatypes::Int64Type>()
+                .value(0)
+        };
+        // unindexed values are matched directly
+        let batches = ctx.sql(query).await.unwrap().collect().await.unwrap();
+        assert_eq!(count(batches), 1);
+
+        assert_eq!(index_column(&ctx, "docs", "body").await.unwrap(), 2);
+        assert_eq!(index_column(&ctx, "docs", "body").await.unwrap(), 0);
+        let batches = ctx.sql(query).await.unwrap().collect().await.unwrap();
+        assert_eq!(count(batches), 1);
+    }
+}
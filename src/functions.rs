@@ -0,0 +1,70 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Optional SQL function packs, each compiled in by a cargo feature.
+
+use datafusion::execution::context::SessionContext;
+
+/// Register every function pack compiled into this build with `ctx`. Returns their
+/// feature names. Registering again is harmless, and keeps full-text indexes.
+#[allow(unused_mut, unused_variables)]
+pub fn register_optional(ctx: &SessionContext) -> Vec<&'static str> {
+    let mut packs = Vec::new();
+    #[cfg(feature = "crypto")]
+    {
+        for udf in datafusion::functions::crypto::functions() {
+            ctx.register_udf(udf.as_ref().clone());
+        }
+        packs.push("crypto");
+    }
+    #[cfg(feature = "functions-json")]
+    {
+        datafusion_functions_json::register_all(&mut *ctx.state_ref().write()).unwrap();
+        packs.push("functions-json");
+    }
+    #[cfg(feature = "geo")]
+    {
+        crate::geo_functions::register(ctx);
+        packs.push("geo");
+    }
+    #[cfg(feature = "fts")]
+    {
+        crate::fts::register(ctx);
+        packs.push("fts");
+    }
+    packs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "crypto")]
+    #[tokio::test]
+    async fn test_crypto() {
+        let ctx = SessionContext::new();
+        ctx.deregister_udf("md5");
+        assert!(register_optional(&ctx).contains(&"crypto"));
+        let batches = ctx
+            .sql("SELECT md5('a')")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(batches[0].num_rows(), 1);
+    }
+}
@@ -0,0 +1,274 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Geospatial SQL functions, so spatial filters run in the engine:
+//! `haversine_distance`, `point_in_polygon` and `h3_cell`.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, ArrayRef, AsArray, BooleanArray, Float64Array, StringArray};
+use datafusion::arrow::datatypes::{DataType, Float64Type, Int64Type};
+use datafusion::common::{exec_datafusion_err, Result};
+use datafusion::execution::context::SessionContext;
+use datafusion::logical_expr::{
+    ColumnarValue, ScalarUDF, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use h3o::{LatLng, Resolution};
+use serde_json::Value;
+
+use crate::result_format::{parse_wkb, parse_wkt};
+
+/// Mean Earth radius in meters.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Register the geo functions with `ctx`.
+pub fn register(ctx: &SessionContext) {
+    for kind in [Kind::Haversine, Kind::PointInPolygon, Kind::H3Cell] {
+        ctx.register_udf(ScalarUDF::from(GeoFunction::new(kind)));
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    /// `haversine_distance(lat1, lon1, lat2, lon2)`, great-circle distance in meters.
+    Haversine,
+    /// `point_in_polygon(lon, lat, polygon)`, with the (multi)polygon as WKT or WKB.
+    PointInPolygon,
+    /// `h3_cell(lat, lon, resolution)`, the H3 cell index as a hex string.
+    H3Cell,
+}
+
+#[derive(Debug)]
+struct GeoFunction {
+    kind: Kind,
+    signature: Signature,
+}
+
+impl GeoFunction {
+    fn new(kind: Kind) -> Self {
+        use DataType::*;
+        let signature = match kind {
+            Kind::Haversine => Signature::exact(vec![Float64; 4], Volatility::Immutable),
+            Kind::PointInPolygon => Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![Float64, Float64, Utf8]),
+                    TypeSignature::Exact(vec![Float64, Float64, Binary]),
+                ],
+                Volatility::Immutable,
+            ),
+            Kind::H3Cell => Signature::exact(vec![Float64, Float64, Int64], Volatility::Immutable),
+        };
+        Self { kind, signature }
+    }
+}
+
+impl ScalarUDFImpl for GeoFunction {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        match self.kind {
+            Kind::Haversine => "haversine_distance",
+            Kind::PointInPolygon => "point_in_polygon",
+            Kind::H3Cell => "h3_cell",
+        }
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(match self.kind {
+            Kind::Haversine => DataType::Float64,
+            Kind::PointInPolygon => DataType::Boolean,
+            Kind::H3Cell => DataType::Utf8,
+        })
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        let arrays = ColumnarValue::values_to_arrays(args)?;
+        let result: ArrayRef = match self.kind {
+            Kind::Haversine => Arc::new(haversine_array(&arrays)),
+            Kind::PointInPolygon => Arc::new(point_in_polygon_array(&arrays)?),
+            Kind::H3Cell => Arc::new(h3_cell_array(&arrays)?),
+        };
+        Ok(ColumnarValue::Array(result))
+    }
+}
+
+fn haversine_array(arrays: &[ArrayRef]) -> Float64Array {
+    let [lat1, lon1, lat2, lon2] = [0, 1, 2, 3].map(|i| arrays[i].as_primitive::<Float64Type>());
+    (0..lat1.len())
+        .map(|row| {
+            if [lat1, lon1, lat2, lon2].iter().any(|a| a.is_null(row)) {
+                return None;
+            }
+            Some(haversine(
+                (lat1.value(row), lon1.value(row)),
+                (lat2.value(row), lon2.value(row)),
+            ))
+        })
+        .collect()
+}
+
+/// Great-circle distance in meters between two `(lat, lon)` points in degrees.
+fn haversine(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lat1, lat2) = (a.0.to_radians(), b.0.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (b.1 - a.1).to_radians();
+    let h = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * h.sqrt().asin()
+}
+
+fn point_in_polygon_array(arrays: &[ArrayRef]) -> Result<BooleanArray> {
+    let lon = arrays[0].as_primitive::<Float64Type>();
+    let lat = arrays[1].as_primitive::<Float64Type>();
+    let polygons = arrays[2].as_ref();
+    (0..lon.len())
+        .map(|row| {
+            if lon.is_null(row) || lat.is_null(row) || polygons.is_null(row) {
+                return Ok(None);
+            }
+            let geometry = match polygons.data_type() {
+                DataType::Binary => parse_wkb(polygons.as_binary::<i32>().value(row)),
+                _ => parse_wkt(polygons.as_string::<i32>().value(row)),
+            }
+            .map_err(|err| exec_datafusion_err!("point_in_polygon: {err}"))?;
+            contains(&geometry, (lon.value(row), lat.value(row))).map(Some)
+        })
+        .collect()
+}
+
+/// Whether the GeoJSON (multi)polygon `geometry` contains `point`, holes excluded.
+fn contains(geometry: &Value, point: (f64, f64)) -> Result<bool> {
+    let polygons = match (geometry["type"].as_str(), &geometry["coordinates"]) {
+        (Some("Polygon"), Value::Array(rings)) => vec![rings],
+        (Some("MultiPolygon"), Value::Array(polygons)) => {
+            polygons.iter().filter_map(Value::as_array).collect()
+        }
+        (kind, _) => {
+            return Err(exec_datafusion_err!(
+                "point_in_polygon: expected a Polygon or MultiPolygon, got {}",
+                kind.unwrap_or("an unknown geometry")
+            ))
+        }
+    };
+    Ok(polygons.into_iter().any(|rings| {
+        let mut rings = rings.iter().map(ring);
+        rings.next().is_some_and(|outer| in_ring(&outer, point))
+            && !rings.any(|hole| in_ring(&hole, point))
+    }))
+}
+
+fn ring(coordinates: &Value) -> Vec<(f64, f64)> {
+    coordinates
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|position| Some((position[0].as_f64()?, position[1].as_f64()?)))
+        .collect()
+}
+
+/// Even-odd ray casting.
+fn in_ring(ring: &[(f64, f64)], (x, y): (f64, f64)) -> bool {
+    let mut inside = false;
+    for (i, &(xi, yi)) in ring.iter().enumerate() {
+        let (xj, yj) = ring[(i + ring.len() - 1) % ring.len()];
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn h3_cell_array(arrays: &[ArrayRef]) -> Result<StringArray> {
+    let lat = arrays[0].as_primitive::<Float64Type>();
+    let lon = arrays[1].as_primitive::<Float64Type>();
+    let resolution = arrays[2].as_primitive::<Int64Type>();
+    (0..lat.len())
+        .map(|row| {
+            if lat.is_null(row) || lon.is_null(row) || resolution.is_null(row) {
+                return Ok(None);
+            }
+            h3_cell(lat.value(row), lon.value(row), resolution.value(row)).map(Some)
+        })
+        .collect()
+}
+
+fn h3_cell(lat: f64, lon: f64, resolution: i64) -> Result<String> {
+    let resolution = u8::try_from(resolution)
+        .ok()
+        .and_then(|resolution| Resolution::try_from(resolution).ok())
+        .ok_or_else(|| exec_datafusion_err!("h3_cell: resolution {resolution} is not in 0..=15"))?;
+    let point = LatLng::new(lat, lon).map_err(|err| exec_datafusion_err!("h3_cell: {err}"))?;
+    Ok(point.to_cell(resolution).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_haversine() {
+        // Paris to London is about 344 km
+        let distance = haversine((48.8566, 2.3522), (51.5074, -0.1278));
+        assert!((distance - 343_500.0).abs() < 2_000.0, "{distance}");
+        assert_eq!(haversine((10.0, 20.0), (10.0, 20.0)), 0.0);
+    }
+
+    #[test]
+    fn test_point_in_polygon() {
+        let polygon =
+            parse_wkt("POLYGON ((0 0, 10 0, 10 10, 0 10, 0 0), (4 4, 6 4, 6 6, 4 6, 4 4))")
+                .unwrap();
+        assert!(contains(&polygon, (1.0, 1.0)).unwrap());
+        assert!(!contains(&polygon, (5.0, 5.0)).unwrap());
+        assert!(!contains(&polygon, (11.0, 1.0)).unwrap());
+
+        let multi =
+            parse_wkt("MULTIPOLYGON (((0 0, 1 0, 1 1, 0 0)), ((5 5, 6 5, 6 6, 5 5)))").unwrap();
+        assert!(contains(&multi, (5.9, 5.5)).unwrap());
+        assert!(contains(&parse_wkt("POINT (1 1)").unwrap(), (1.0, 1.0)).is_err());
+    }
+
+    #[test]
+    fn test_h3_cell() {
+        assert_eq!(h3_cell(48.8566, 2.3522, 0).unwrap().len(), 15);
+        assert!(h3_cell(48.8566, 2.3522, 16).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sql() {
+        let ctx = SessionContext::new();
+        register(&ctx);
+        let batches = ctx
+            .sql(
+                "SELECT point_in_polygon(1, 1, 'POLYGON ((0 0, 2 0, 2 2, 0 2, 0 0))') AS inside, \
+                 haversine_distance(0, 0, 0, 1) AS meters",
+            )
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert!(batches[0].column(0).as_boolean().value(0));
+        let meters = batches[0].column(1).as_primitive::<Float64Type>().value(0);
+        assert!((meters - 111_195.0).abs() < 10.0, "{meters}");
+    }
+}
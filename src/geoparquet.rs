@@ -0,0 +1,343 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! GeoParquet support.
+//!
+//! Geometry columns declared in the file's `geo` metadata are exposed with
+//! `geoarrow.wkb` extension metadata, and row groups can be pruned against a
+//! bounding box using the file bbox and GeoParquet 1.1 bbox covering columns.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::{Field, Schema, SchemaRef};
+use datafusion::catalog::Session;
+use datafusion::common::config::TableOptions;
+use datafusion::common::DFSchema;
+use datafusion::datasource::listing::PartitionedFile;
+use datafusion::datasource::physical_plan::parquet::ParquetAccessPlan;
+use datafusion::datasource::physical_plan::{FileScanConfig, ParquetExec};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::execution::context::SessionContext;
+use datafusion::execution::object_store::ObjectStoreUrl;
+use datafusion::logical_expr::utils::conjunction;
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::ExecutionPlan;
+use object_store::path::Path;
+use parquet::arrow::arrow_reader::{ArrowReaderMetadata, ArrowReaderOptions};
+use parquet::file::metadata::ParquetMetaData;
+use parquet::file::statistics::Statistics;
+use serde::Deserialize;
+
+use crate::error::{Result, WasmError};
+use crate::extension::{EXTENSION_METADATA_KEY, EXTENSION_NAME_KEY};
+use crate::parquet_info::fetch_metadata;
+
+const GEO_METADATA_KEY: &str = "geo";
+
+/// The `geo` key-value metadata of a GeoParquet file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeoMetadata {
+    pub primary_column: String,
+    pub columns: HashMap<String, GeoColumn>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeoColumn {
+    pub encoding: String,
+    pub crs: Option<serde_json::Value>,
+    pub bbox: Option<Vec<f64>>,
+    pub covering: Option<Covering>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Covering {
+    pub bbox: BboxCovering,
+}
+
+/// Paths of the columns holding per-row bounding boxes, e.g. `["bbox", "xmin"]`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BboxCovering {
+    pub xmin: Vec<String>,
+    pub ymin: Vec<String>,
+    pub xmax: Vec<String>,
+    pub ymax: Vec<String>,
+}
+
+impl GeoMetadata {
+    pub fn from_parquet(metadata: &ParquetMetaData) -> Result<Option<Self>> {
+        let value = metadata
+            .file_metadata()
+            .key_value_metadata()
+            .and_then(|kvs| kvs.iter().find(|kv| kv.key == GEO_METADATA_KEY))
+            .and_then(|kv| kv.value.as_deref());
+        match value {
+            Some(value) => Ok(Some(serde_json::from_str(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Tag geometry columns with GeoArrow extension metadata.
+    pub fn annotate_schema(&self, schema: &Schema) -> Schema {
+        let fields = schema
+            .fields()
+            .iter()
+            .map(|field| match self.columns.get(field.name()) {
+                Some(column) if column.encoding.eq_ignore_ascii_case("WKB") => {
+                    let mut metadata = field.metadata().clone();
+                    metadata.insert(EXTENSION_NAME_KEY.to_string(), "geoarrow.wkb".to_string());
+                    if let Some(crs) = &column.crs {
+                        metadata.insert(
+                            EXTENSION_METADATA_KEY.to_string(),
+                            serde_json::json!({ "crs": crs }).to_string(),
+                        );
+                    }
+                    Arc::new(Field::clone(field).with_metadata(metadata))
+                }
+                _ => field.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        Schema::new_with_metadata(fields, schema.metadata().clone())
+    }
+
+    /// Row groups that may contain geometries intersecting `bbox`
+    /// (`[xmin, ymin, xmax, ymax]`) in the primary geometry column.
+    pub fn prune_row_groups(&self, metadata: &ParquetMetaData, bbox: &[f64; 4]) -> Vec<bool> {
+        let num_row_groups = metadata.num_row_groups();
+        let Some(column) = self.columns.get(&self.primary_column) else {
+            return vec![true; num_row_groups];
+        };
+
+        if let Some(file_bbox) = &column.bbox {
+            if file_bbox.len() >= 4
+                && !intersects(
+                    &[file_bbox[0], file_bbox[1], file_bbox[2], file_bbox[3]],
+                    bbox,
+                )
+            {
+                return vec![false; num_row_groups];
+            }
+        }
+
+        let Some(covering) = &column.covering else {
+            return vec![true; num_row_groups];
+        };
+        let schema = metadata.file_metadata().schema_descr();
+        let column_index = |path: &[String]| {
+            let path = path.join(".");
+            (0..schema.num_columns()).find(|i| schema.column(*i).path().string() == path)
+        };
+        let (Some(xmin), Some(ymin), Some(xmax), Some(ymax)) = (
+            column_index(&covering.bbox.xmin),
+            column_index(&covering.bbox.ymin),
+            column_index(&covering.bbox.xmax),
+            column_index(&covering.bbox.ymax),
+        ) else {
+            return vec![true; num_row_groups];
+        };
+
+        metadata
+            .row_groups()
+            .iter()
+            .map(|row_group| {
+                let stat = |index: usize, min: bool| {
+                    row_group
+                        .column(index)
+                        .statistics()
+                        .and_then(|stats| float_statistic(stats, min))
+                };
+                match (
+                    stat(xmin, true),
+                    stat(ymin, true),
+                    stat(xmax, false),
+                    stat(ymax, false),
+                ) {
+                    (Some(xmin), Some(ymin), Some(xmax), Some(ymax)) => {
+                        intersects(&[xmin, ymin, xmax, ymax], bbox)
+                    }
+                    // without statistics the row group has to be scanned
+                    _ => true,
+                }
+            })
+            .collect()
+    }
+}
+
+fn float_statistic(stats: &Statistics, min: bool) -> Option<f64> {
+    match stats {
+        Statistics::Double(stats) if min => stats.min_opt().copied(),
+        Statistics::Double(stats) => stats.max_opt().copied(),
+        Statistics::Float(stats) if min => stats.min_opt().map(|v| *v as f64),
+        Statistics::Float(stats) => stats.max_opt().map(|v| *v as f64),
+        _ => None,
+    }
+}
+
+fn intersects(a: &[f64; 4], b: &[f64; 4]) -> bool {
+    a[0] <= b[2] && b[0] <= a[2] && a[1] <= b[3] && b[1] <= a[3]
+}
+
+/// A single GeoParquet file scanned with an optional row group selection.
+#[derive(Debug)]
+pub struct GeoParquetTable {
+    object_store_url: ObjectStoreUrl,
+    path: Path,
+    size: u64,
+    schema: SchemaRef,
+    access_plan: Option<ParquetAccessPlan>,
+}
+
+impl GeoParquetTable {
+    pub async fn try_new(ctx: &SessionContext, url: &str, bbox: Option<[f64; 4]>) -> Result<Self> {
+        let (object_store_url, meta, metadata) = fetch_metadata(ctx, url).await?;
+        let reader_metadata =
+            ArrowReaderMetadata::try_new(metadata.clone(), ArrowReaderOptions::default())?;
+
+        let geo = GeoMetadata::from_parquet(&metadata)?
+            .ok_or_else(|| WasmError::Other(format!("{url} has no GeoParquet metadata")))?;
+        let schema = Arc::new(geo.annotate_schema(reader_metadata.schema()));
+
+        let access_plan = bbox.map(|bbox| {
+            let mut plan = ParquetAccessPlan::new_all(metadata.num_row_groups());
+            for (index, keep) in geo
+                .prune_row_groups(&metadata, &bbox)
+                .into_iter()
+                .enumerate()
+            {
+                if !keep {
+                    plan.skip(index);
+                }
+            }
+            plan
+        });
+
+        Ok(Self {
+            object_store_url,
+            path: meta.location,
+            size: meta.size as u64,
+            schema,
+            access_plan,
+        })
+    }
+}
+
+#[async_trait]
+impl TableProvider for GeoParquetTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    // filters prune row groups, pages and bloom filters, rows are checked again above
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> datafusion::error::Result<Vec<TableProviderFilterPushDown>> {
+        Ok(vec![TableProviderFilterPushDown::Inexact; filters.len()])
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
+        let mut file = PartitionedFile::new(self.path.to_string(), self.size);
+        if let Some(access_plan) = &self.access_plan {
+            file = file.with_extensions(Arc::new(access_plan.clone()));
+        }
+
+        let config = FileScanConfig::new(self.object_store_url.clone(), self.schema.clone())
+            .with_file(file)
+            .with_projection(projection.cloned())
+            .with_limit(limit);
+        let table_options = TableOptions::default_from_session_config(state.config_options());
+        let mut builder =
+            ParquetExec::builder(config).with_table_parquet_options(table_options.parquet);
+        if let Some(predicate) = conjunction(filters.to_vec()) {
+            let schema = DFSchema::try_from(self.schema.as_ref().clone())?;
+            builder = builder.with_predicate(state.create_physical_expr(predicate, &schema)?);
+        }
+        Ok(builder.build_arc())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_geo_metadata() {
+        let geo: GeoMetadata = serde_json::from_str(
+            r#"{
+                "version": "1.1.0",
+                "primary_column": "geometry",
+                "columns": {
+                    "geometry": {
+                        "encoding": "WKB",
+                        "geometry_types": ["Point"],
+                        "bbox": [0.0, 0.0, 10.0, 10.0],
+                        "covering": {"bbox": {
+                            "xmin": ["bbox", "xmin"], "ymin": ["bbox", "ymin"],
+                            "xmax": ["bbox", "xmax"], "ymax": ["bbox", "ymax"]
+                        }}
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let schema = Schema::new(vec![
+            Field::new("id", datafusion::arrow::datatypes::DataType::Int32, false),
+            Field::new(
+                "geometry",
+                datafusion::arrow::datatypes::DataType::Binary,
+                true,
+            ),
+        ]);
+
+        let annotated = geo.annotate_schema(&schema);
+        assert!(annotated.field(0).metadata().is_empty());
+        assert_eq!(
+            annotated
+                .field(1)
+                .metadata()
+                .get(EXTENSION_NAME_KEY)
+                .unwrap(),
+            "geoarrow.wkb"
+        );
+    }
+
+    #[test]
+    fn test_intersects() {
+        assert!(intersects(&[0.0, 0.0, 10.0, 10.0], &[5.0, 5.0, 15.0, 15.0]));
+        assert!(!intersects(
+            &[0.0, 0.0, 10.0, 10.0],
+            &[11.0, 0.0, 15.0, 10.0]
+        ));
+    }
+}
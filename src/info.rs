@@ -0,0 +1,107 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Version and build information of this binary.
+
+use serde::Serialize;
+use tsify_next::Tsify;
+use wasm_bindgen::JsValue;
+
+use crate::runtime::{Runtime, RuntimeApis};
+
+#[derive(Debug, Clone, Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct EngineInfo {
+    pub version: String,
+    pub datafusion_version: String,
+    pub arrow_version: String,
+    pub git_hash: String,
+    pub features: Vec<String>,
+    pub profile: String,
+}
+
+impl EngineInfo {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            datafusion_version: datafusion::DATAFUSION_VERSION.to_string(),
+            arrow_version: arrow_version(),
+            git_hash: env!("DATAFUSION_WASM_GIT_HASH").to_string(),
+            features: env!("DATAFUSION_WASM_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .map(str::to_string)
+                .collect(),
+            profile: env!("DATAFUSION_WASM_PROFILE").to_string(),
+        }
+    }
+}
+
+/// What the page offers for multi-threaded execution, and the runtime hosting it.
+#[derive(Debug, Clone, Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct Capabilities {
+    /// `crossOriginIsolated`, required for `SharedArrayBuffer` on the web.
+    pub cross_origin_isolated: bool,
+    pub shared_array_buffer: bool,
+    /// Whether queries run on multiple threads. This build is single threaded, so
+    /// it runs the same with or without the above.
+    pub threads: bool,
+    /// The JavaScript runtime, which decides e.g. whether `file://` URLs are readable.
+    pub runtime: Runtime,
+    pub apis: RuntimeApis,
+}
+
+impl Capabilities {
+    pub fn detect() -> Self {
+        let runtime = Runtime::detect();
+        let global = js_sys::global();
+        let has = |name: &str| {
+            js_sys::Reflect::get(&global, &JsValue::from_str(name))
+                .map(|value| !value.is_undefined())
+                .unwrap_or(false)
+        };
+        let cross_origin_isolated = js_sys::Reflect::get(&global, &"crossOriginIsolated".into())
+            .ok()
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+
+        Self {
+            cross_origin_isolated,
+            shared_array_buffer: has("SharedArrayBuffer"),
+            threads: false,
+            runtime,
+            apis: RuntimeApis::detect(runtime),
+        }
+    }
+}
+
+/// arrow doesn't export its version, but arrow and parquet are released in
+/// lockstep and parquet stamps its version into the `created_by` string.
+#[cfg(feature = "parquet")]
+fn arrow_version() -> String {
+    parquet::file::properties::DEFAULT_CREATED_BY
+        .rsplit(' ')
+        .next()
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+#[cfg(not(feature = "parquet"))]
+fn arrow_version() -> String {
+    "unknown".to_string()
+}
@@ -0,0 +1,343 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Appending CSV / NDJSON text to in-memory tables.
+
+#[cfg(any(feature = "csv", test))]
+use std::borrow::Cow;
+#[cfg(any(feature = "csv", feature = "json", test))]
+use std::io::{Cursor, Seek};
+use std::sync::Arc;
+
+use datafusion::arrow::array::{new_null_array, ArrayRef, RecordBatch};
+use datafusion::arrow::compute::{can_cast_types, cast_with_options};
+use datafusion::arrow::datatypes::{Field, Schema, SchemaRef};
+use datafusion::datasource::MemTable;
+use datafusion::execution::context::SessionContext;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::cast_policy::CastPolicy;
+use crate::error::{Result, WasmError};
+use crate::row_ids;
+#[cfg(any(feature = "csv", feature = "json", test))]
+use crate::warnings::{Warning, WarningKind, Warnings};
+
+/// Records read to infer the schema of appended data.
+#[cfg(any(feature = "csv", feature = "json", test))]
+const INFER_SCHEMA_RECORDS: usize = 1000;
+
+/// What to do when appended data doesn't match the table schema.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchemaEvolution {
+    /// Reject data with unknown columns.
+    #[default]
+    Error,
+    /// Add unknown columns as nullable, existing rows get nulls.
+    AddColumns,
+}
+
+/// Malformed lines reported by number in a warning.
+#[cfg(any(feature = "csv", test))]
+const REPORTED_LINES: usize = 10;
+
+/// Read CSV text, skipping records with a different number of fields than the
+/// first one.
+#[cfg(any(feature = "csv", test))]
+pub fn read_csv(data: &[u8], has_header: bool, warnings: &Warnings) -> Result<Vec<RecordBatch>> {
+    let (data, skipped) = drop_malformed_records(data);
+    if !skipped.is_empty() {
+        let lines = skipped
+            .iter()
+            .take(REPORTED_LINES)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let more = if skipped.len() > REPORTED_LINES {
+            ", ..."
+        } else {
+            ""
+        };
+        warnings.push(Warning::new(
+            WarningKind::MalformedRecords,
+            format!(
+                "skipped {} CSV lines with the wrong number of fields: {lines}{more}",
+                skipped.len()
+            ),
+        ));
+    }
+
+    let mut cursor = Cursor::new(data.as_ref());
+    let (schema, records) = datafusion::arrow::csv::reader::Format::default()
+        .with_header(has_header)
+        .infer_schema(&mut cursor, Some(INFER_SCHEMA_RECORDS))?;
+    cursor.rewind()?;
+    warn_sampled(records, warnings);
+
+    let reader = datafusion::arrow::csv::ReaderBuilder::new(Arc::new(schema))
+        .with_header(has_header)
+        .build(cursor)?;
+    Ok(reader.collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+#[cfg(any(feature = "json", test))]
+pub fn read_json(data: &[u8], warnings: &Warnings) -> Result<Vec<RecordBatch>> {
+    let mut cursor = std::io::BufReader::new(Cursor::new(data));
+    let (schema, records) = datafusion::arrow::json::reader::infer_json_schema(
+        &mut cursor,
+        Some(INFER_SCHEMA_RECORDS),
+    )?;
+    cursor.rewind()?;
+    warn_sampled(records, warnings);
+
+    let reader = datafusion::arrow::json::ReaderBuilder::new(Arc::new(schema)).build(cursor)?;
+    Ok(reader.collect::<std::result::Result<Vec<_>, _>>()?)
+}
+
+#[cfg(any(feature = "csv", feature = "json", test))]
+fn warn_sampled(records: usize, warnings: &Warnings) {
+    if records >= INFER_SCHEMA_RECORDS {
+        warnings.push(Warning::new(
+            WarningKind::SchemaInference,
+            format!(
+                "schema inferred from the first {INFER_SCHEMA_RECORDS} records, \
+                 later values that don't fit it fail or follow the cast policy"
+            ),
+        ));
+    }
+}
+
+/// Drop CSV records whose number of fields differs from the first record's,
+/// returning the remaining data and the 1-based lines the dropped records start on.
+#[cfg(any(feature = "csv", test))]
+fn drop_malformed_records(data: &[u8]) -> (Cow<'_, [u8]>, Vec<usize>) {
+    // (start, end, line, fields) of every record, quoted newlines don't end one
+    let mut records = Vec::new();
+    let (mut start, mut fields, mut line, mut record_line) = (0, 1, 1, 1);
+    let mut quoted = false;
+    for (i, byte) in data.iter().enumerate() {
+        match byte {
+            b'"' => quoted = !quoted,
+            b',' if !quoted => fields += 1,
+            b'\n' => {
+                line += 1;
+                if !quoted {
+                    records.push((start, i + 1, record_line, fields));
+                    (start, fields, record_line) = (i + 1, 1, line);
+                }
+            }
+            _ => {}
+        }
+    }
+    if start < data.len() {
+        records.push((start, data.len(), record_line, fields));
+    }
+
+    let is_blank = |start: usize, end: usize| data[start..end].iter().all(u8::is_ascii_whitespace);
+    let Some(expected) = records
+        .iter()
+        .find(|(start, end, ..)| !is_blank(*start, *end))
+        .map(|record| record.3)
+    else {
+        return (Cow::Borrowed(data), Vec::new());
+    };
+    let (kept, dropped): (Vec<_>, Vec<_>) = records
+        .into_iter()
+        .partition(|(start, end, _, fields)| *fields == expected || is_blank(*start, *end));
+    if dropped.is_empty() {
+        return (Cow::Borrowed(data), Vec::new());
+    }
+    let data = kept
+        .iter()
+        .flat_map(|(start, end, ..)| &data[*start..*end])
+        .copied()
+        .collect();
+    let lines = dropped.iter().map(|record| record.2).collect();
+    (Cow::Owned(data), lines)
+}
+
+/// Append `batches` to table `name`, creating it if it doesn't exist. With
+/// `row_ids`, a new table gets a `_row_id` column; tables that have one number
+/// appended rows after their largest id.
+pub async fn append(
+    ctx: &SessionContext,
+    name: &str,
+    batches: Vec<RecordBatch>,
+    evolution: SchemaEvolution,
+    cast_policy: CastPolicy,
+    row_ids: bool,
+) -> Result<()> {
+    if batches.is_empty() {
+        return Ok(());
+    }
+
+    let (schema, mut existing, batches) = if ctx.table_exist(name)? {
+        let table = ctx.table(name).await?;
+        let schema = Arc::new(table.schema().as_arrow().clone());
+        let existing = table.collect().await?;
+        let batches = if row_ids::has_row_ids(&schema) {
+            row_ids::assign(batches, row_ids::next_id(&existing)?)?
+        } else {
+            batches
+        };
+        (schema, existing, batches)
+    } else {
+        let batches = if row_ids {
+            row_ids::assign(batches, 0)?
+        } else {
+            batches
+        };
+        (batches[0].schema(), vec![], batches)
+    };
+    let first = &batches[0];
+
+    let merged = merge_schema(&schema, &first.schema(), evolution)?;
+    let mut all = Vec::with_capacity(existing.len() + batches.len());
+    for batch in existing.drain(..).chain(batches) {
+        all.push(conform(&batch, &merged, cast_policy)?);
+    }
+
+    let table = MemTable::try_new(merged, vec![all])?;
+    ctx.deregister_table(name)?;
+    ctx.register_table(name, Arc::new(table))?;
+    Ok(())
+}
+
+/// The table schema after appending data of `incoming` schema.
+fn merge_schema(
+    existing: &SchemaRef,
+    incoming: &SchemaRef,
+    evolution: SchemaEvolution,
+) -> Result<SchemaRef> {
+    let mut fields: Vec<Field> = existing
+        .fields()
+        .iter()
+        .map(|f| f.as_ref().clone())
+        .collect();
+
+    for field in incoming.fields() {
+        match existing.field_with_name(field.name()) {
+            Ok(current) => {
+                if !can_cast_types(field.data_type(), current.data_type()) {
+                    return Err(WasmError::Other(format!(
+                        "column {} is {} but appended data has {}",
+                        field.name(),
+                        current.data_type(),
+                        field.data_type()
+                    )));
+                }
+            }
+            Err(_) if evolution == SchemaEvolution::AddColumns => {
+                fields.push(field.as_ref().clone().with_nullable(true));
+            }
+            Err(_) => {
+                return Err(WasmError::Other(format!(
+                    "appended data has unknown column {}",
+                    field.name()
+                )))
+            }
+        }
+    }
+
+    // appended rows hold nulls for the columns they lack
+    for field in fields.iter_mut().take(existing.fields().len()) {
+        if incoming.field_with_name(field.name()).is_err() {
+            *field = field.clone().with_nullable(true);
+        }
+    }
+
+    Ok(Arc::new(Schema::new_with_metadata(
+        fields,
+        existing.metadata().clone(),
+    )))
+}
+
+/// Reorder, cast and null-fill the columns of `batch` to match `schema`.
+pub fn conform(
+    batch: &RecordBatch,
+    schema: &SchemaRef,
+    cast_policy: CastPolicy,
+) -> Result<RecordBatch> {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| match batch.column_by_name(field.name()) {
+            Some(column) if column.data_type() == field.data_type() => Ok(column.clone()),
+            Some(column) => Ok(cast_with_options(
+                column,
+                field.data_type(),
+                &cast_policy.cast_options(),
+            )?),
+            None => Ok(new_null_array(field.data_type(), batch.num_rows())),
+        })
+        .collect::<Result<Vec<ArrayRef>>>()?;
+
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_columns() {
+        let warnings = Warnings::default();
+        let existing = read_json(b"{\"a\": 1}\n{\"a\": 2}\n", &warnings).unwrap();
+        let incoming = read_json(b"{\"a\": 3, \"b\": \"x\"}\n", &warnings).unwrap();
+
+        assert!(merge_schema(
+            &existing[0].schema(),
+            &incoming[0].schema(),
+            SchemaEvolution::Error
+        )
+        .is_err());
+
+        let merged = merge_schema(
+            &existing[0].schema(),
+            &incoming[0].schema(),
+            SchemaEvolution::AddColumns,
+        )
+        .unwrap();
+        assert_eq!(merged.fields().len(), 2);
+
+        let padded = conform(&existing[0], &merged, CastPolicy::Error).unwrap();
+        assert_eq!(padded.column(1).null_count(), 2);
+    }
+
+    #[test]
+    fn test_read_csv() {
+        let warnings = Warnings::default();
+        let batches = read_csv(b"id,name\n1,a\n2,b\n", true, &warnings).unwrap();
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(batches[0].schema().field(1).name(), "name");
+        assert!(warnings.snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_read_csv_skips_malformed_lines() {
+        let warnings = Warnings::default();
+        let data = b"id,name\n1,\"a,\nb\"\n2\n3,c,extra\n4,d";
+        let batches = read_csv(data, true, &warnings).unwrap();
+        let rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(rows, 2);
+
+        let warnings = warnings.snapshot();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::MalformedRecords);
+        assert!(warnings[0].message.ends_with(": 4, 5"), "{warnings:?}");
+    }
+}
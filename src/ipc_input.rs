@@ -0,0 +1,295 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Reading Arrow IPC buffers from the host, checked message by message first
+//! so malformed input fails with the offset and message at fault.
+
+use std::collections::HashSet;
+use std::io::Cursor;
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::arrow::ipc::reader::{FileReader, StreamReader};
+use datafusion::arrow::ipc::{self, root_as_message, CompressionType, MessageHeader};
+
+use crate::error::{Result, WasmError};
+use crate::result_format::IpcCompression;
+
+const FILE_MAGIC: &[u8] = b"ARROW1";
+const CONTINUATION: i32 = -1;
+
+/// Decode an IPC stream, or an IPC file starting with `ARROW1`.
+pub fn read(data: &[u8]) -> Result<Vec<RecordBatch>> {
+    Ok(read_with_schema(data)?.1)
+}
+
+/// Like [`read`], also returning the schema, which is known even without batches.
+pub fn read_with_schema(data: &[u8]) -> Result<(SchemaRef, Vec<RecordBatch>)> {
+    if data.starts_with(FILE_MAGIC) {
+        // magic padded to 8 bytes, then the messages in stream framing
+        validate(data, FILE_MAGIC.len() + 2)?;
+        let reader = FileReader::try_new(Cursor::new(data), None).map_err(|err| invalid(0, err))?;
+        return Ok((reader.schema(), collect(reader)?));
+    }
+
+    validate(data, 0)?;
+    let reader = StreamReader::try_new(Cursor::new(data), None).map_err(|err| invalid(0, err))?;
+    Ok((reader.schema(), collect(reader)?))
+}
+
+fn collect(
+    reader: impl Iterator<Item = std::result::Result<RecordBatch, datafusion::arrow::error::ArrowError>>,
+) -> Result<Vec<RecordBatch>> {
+    reader
+        .enumerate()
+        .map(|(index, batch)| {
+            batch.map_err(|err| invalid(0, format!("record batch {index}: {err}")))
+        })
+        .collect()
+}
+
+fn invalid(offset: usize, reason: impl ToString) -> WasmError {
+    WasmError::InvalidIpc {
+        offset,
+        reason: reason.to_string(),
+    }
+}
+
+/// Walk the messages framed from `start`, checking lengths, order, dictionaries and
+/// compression codecs without decoding any buffers.
+fn validate(data: &[u8], start: usize) -> Result<()> {
+    let mut offset = start;
+    let mut index = 0;
+    let mut schema_seen = false;
+    let mut required_dictionaries = HashSet::new();
+    let mut dictionaries = HashSet::new();
+
+    while offset < data.len() {
+        let mut metadata_start = offset + 4;
+        let mut length = read_i32(data, offset)?;
+        if length == CONTINUATION {
+            length = read_i32(data, offset + 4)?;
+            metadata_start += 4;
+        }
+        if length == 0 {
+            // end-of-stream marker
+            break;
+        }
+        let metadata_end = usize::try_from(length)
+            .ok()
+            .map(|length| metadata_start + length)
+            .ok_or_else(|| {
+                invalid(
+                    offset,
+                    format!("message {index} has negative length {length}"),
+                )
+            })?;
+        if metadata_end > data.len() {
+            return Err(invalid(
+                offset,
+                format!(
+                    "truncated: message {index} metadata needs {} bytes, {} left",
+                    metadata_end - metadata_start,
+                    data.len().saturating_sub(metadata_start)
+                ),
+            ));
+        }
+
+        let message = root_as_message(&data[metadata_start..metadata_end]).map_err(|err| {
+            invalid(
+                offset,
+                format!("message {index} is not an IPC message: {err}"),
+            )
+        })?;
+        let header = message.header_type();
+        let body_end = usize::try_from(message.bodyLength())
+            .ok()
+            .and_then(|body| metadata_end.checked_add(body))
+            .filter(|body_end| *body_end <= data.len())
+            .ok_or_else(|| {
+                invalid(
+                    offset,
+                    format!(
+                        "truncated: {header:?} message {index} body needs {} bytes, {} left",
+                        message.bodyLength(),
+                        data.len() - metadata_end
+                    ),
+                )
+            })?;
+
+        match header {
+            MessageHeader::Schema => {
+                if schema_seen {
+                    return Err(invalid(
+                        offset,
+                        format!("message {index} is a second schema"),
+                    ));
+                }
+                schema_seen = true;
+                if let Some(fields) = message
+                    .header_as_schema()
+                    .and_then(|schema| schema.fields())
+                {
+                    for field in fields.iter() {
+                        dictionary_ids(field, &mut required_dictionaries);
+                    }
+                }
+            }
+            _ if !schema_seen => {
+                return Err(invalid(
+                    offset,
+                    format!("{header:?} message {index} comes before the schema"),
+                ));
+            }
+            MessageHeader::DictionaryBatch => {
+                let batch = message.header_as_dictionary_batch().unwrap();
+                if !required_dictionaries.contains(&batch.id()) {
+                    return Err(invalid(
+                        offset,
+                        format!(
+                            "dictionary batch {index} has id {} that no field uses",
+                            batch.id()
+                        ),
+                    ));
+                }
+                check_compression(
+                    batch.data().and_then(|data| data.compression()),
+                    offset,
+                    index,
+                )?;
+                dictionaries.insert(batch.id());
+            }
+            MessageHeader::RecordBatch => {
+                if let Some(missing) = required_dictionaries.difference(&dictionaries).next() {
+                    return Err(invalid(
+                        offset,
+                        format!("record batch {index} needs dictionary {missing}, which wasn't sent before it"),
+                    ));
+                }
+                let batch = message.header_as_record_batch().unwrap();
+                check_compression(batch.compression(), offset, index)?;
+            }
+            _ => {
+                return Err(invalid(
+                    offset,
+                    format!("message {index} is an unsupported {header:?} message"),
+                ))
+            }
+        }
+
+        offset = body_end;
+        index += 1;
+    }
+
+    if !schema_seen {
+        return Err(invalid(start, "no schema message"));
+    }
+    Ok(())
+}
+
+fn read_i32(data: &[u8], offset: usize) -> Result<i32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| i32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| invalid(offset, "truncated: message length cut off"))
+}
+
+fn dictionary_ids(field: ipc::Field<'_>, ids: &mut HashSet<i64>) {
+    if let Some(dictionary) = field.dictionary() {
+        ids.insert(dictionary.id());
+    }
+    if let Some(children) = field.children() {
+        for child in children.iter() {
+            dictionary_ids(child, ids);
+        }
+    }
+}
+
+fn check_compression(
+    compression: Option<ipc::BodyCompression<'_>>,
+    offset: usize,
+    index: usize,
+) -> Result<()> {
+    let Some(compression) = compression else {
+        return Ok(());
+    };
+    let codec = match compression.codec() {
+        CompressionType::LZ4_FRAME => IpcCompression::Lz4,
+        CompressionType::ZSTD => IpcCompression::Zstd,
+        other => {
+            return Err(invalid(
+                offset,
+                format!("message {index} uses unknown codec {other:?}"),
+            ))
+        }
+    };
+    if !IpcCompression::supported().contains(&codec) {
+        return Err(invalid(
+            offset,
+            format!(
+                "message {index} is {}-compressed, which this build can't decode",
+                codec.name()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result_format::write_ipc_stream;
+    use datafusion::arrow::array::{ArrayRef, Int32Array};
+    use std::sync::Arc;
+
+    fn stream() -> (RecordBatch, Vec<u8>) {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "v",
+            Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+        )])
+        .unwrap();
+        let data = write_ipc_stream(
+            &batch.schema(),
+            std::slice::from_ref(&batch),
+            IpcCompression::None,
+        )
+        .unwrap();
+        (batch, data)
+    }
+
+    #[test]
+    fn test_read_stream() {
+        let (batch, data) = stream();
+        assert_eq!(read(&data).unwrap(), vec![batch]);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let (_, data) = stream();
+        let err = read(&data[..data.len() - 20]).unwrap_err().to_string();
+        assert!(
+            err.contains("truncated: RecordBatch message 1 body"),
+            "{err}"
+        );
+
+        let err = read(&data[..12]).unwrap_err().to_string();
+        assert!(err.contains("truncated: message 0 metadata"), "{err}");
+        assert!(read(&[])
+            .unwrap_err()
+            .to_string()
+            .contains("no schema message"));
+    }
+}
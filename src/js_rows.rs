@@ -0,0 +1,84 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Results as JavaScript objects, with 64-bit integers as `BigInt`.
+
+use datafusion::arrow::array::{Array, AsArray, RecordBatch};
+use datafusion::arrow::datatypes::{
+    DataType, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type,
+    UInt32Type, UInt64Type, UInt8Type,
+};
+use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
+use js_sys::{Array as JsArray, BigInt, Object, Reflect, Uint8Array};
+use wasm_bindgen::JsValue;
+
+use crate::error::{Result, WasmError};
+
+/// An array of row objects. Int64 / UInt64 become `BigInt`, other numbers become
+/// `number`, and types without a lossless JS counterpart (decimals, dates, nested
+/// values, ...) their display string.
+pub fn to_rows(record_batches: &[RecordBatch], options: &FormatOptions) -> Result<JsArray> {
+    let rows = JsArray::new();
+    for batch in record_batches {
+        let schema = batch.schema();
+        let names: Vec<JsValue> = schema
+            .fields()
+            .iter()
+            .map(|field| JsValue::from_str(field.name()))
+            .collect();
+        for row in 0..batch.num_rows() {
+            let object = Object::new();
+            for (name, column) in names.iter().zip(batch.columns()) {
+                let value = to_value(column.as_ref(), row, options)?;
+                Reflect::set(&object, name, &value)
+                    .map_err(|_| WasmError::Other("failed to build result row".to_string()))?;
+            }
+            rows.push(&object);
+        }
+    }
+    Ok(rows)
+}
+
+fn to_value(array: &dyn Array, row: usize, options: &FormatOptions) -> Result<JsValue> {
+    if array.is_null(row) {
+        return Ok(JsValue::NULL);
+    }
+
+    Ok(match array.data_type() {
+        DataType::Boolean => JsValue::from_bool(array.as_boolean().value(row)),
+        DataType::Int8 => JsValue::from(array.as_primitive::<Int8Type>().value(row)),
+        DataType::Int16 => JsValue::from(array.as_primitive::<Int16Type>().value(row)),
+        DataType::Int32 => JsValue::from(array.as_primitive::<Int32Type>().value(row)),
+        DataType::Int64 => BigInt::from(array.as_primitive::<Int64Type>().value(row)).into(),
+        DataType::UInt8 => JsValue::from(array.as_primitive::<UInt8Type>().value(row)),
+        DataType::UInt16 => JsValue::from(array.as_primitive::<UInt16Type>().value(row)),
+        DataType::UInt32 => JsValue::from(array.as_primitive::<UInt32Type>().value(row)),
+        DataType::UInt64 => BigInt::from(array.as_primitive::<UInt64Type>().value(row)).into(),
+        DataType::Float32 => JsValue::from(array.as_primitive::<Float32Type>().value(row)),
+        DataType::Float64 => JsValue::from(array.as_primitive::<Float64Type>().value(row)),
+        DataType::Utf8 => JsValue::from_str(array.as_string::<i32>().value(row)),
+        DataType::LargeUtf8 => JsValue::from_str(array.as_string::<i64>().value(row)),
+        DataType::Utf8View => JsValue::from_str(array.as_string_view().value(row)),
+        DataType::Binary => Uint8Array::from(array.as_binary::<i32>().value(row)).into(),
+        DataType::LargeBinary => Uint8Array::from(array.as_binary::<i64>().value(row)).into(),
+        DataType::BinaryView => Uint8Array::from(array.as_binary_view().value(row)).into(),
+        _ => {
+            let formatter = ArrayFormatter::try_new(array, options)?;
+            JsValue::from_str(&formatter.value(row).to_string())
+        }
+    })
+}
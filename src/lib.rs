@@ -15,15 +15,94 @@
 // specific language governing permissions and limitations
 // under the License.
 
+mod c_data;
+mod cache;
+mod cast_policy;
+mod catalog_search;
+mod column_stats;
+mod compression;
 mod console;
 pub mod core;
+mod credentials;
+mod dataframe;
+mod diagnostics;
+mod diff;
+mod duckdb;
 pub mod error;
+mod events;
+mod execute_options;
+mod extension;
+#[cfg(feature = "fts")]
+mod fts;
+mod functions;
+#[cfg(feature = "geo")]
+mod geo_functions;
+#[cfg(feature = "parquet")]
+mod geoparquet;
+mod info;
+mod ingest;
+mod ipc_input;
+mod js_rows;
+mod listing;
+mod live_table;
+mod logger;
+mod metrics;
+mod namespace;
+mod node_fs;
 mod object_store;
+mod pages;
+mod panic_hook;
+#[cfg(feature = "parquet")]
+mod parquet_info;
+mod parquet_reader;
+#[cfg(feature = "parquet")]
+mod parquet_writer;
+mod perf_marks;
+mod plan_cache;
+mod pragma;
+mod preview;
+mod probe;
+mod progress;
+mod queue;
+mod quota;
+mod raster;
+mod readable_stream;
+mod repro;
+mod resource_limits;
+mod result_cache;
 mod result_format;
+mod row_ids;
+mod runtime;
+mod scheduling;
+mod segments;
+mod settings;
+mod sql_format;
+mod sql_options;
+mod statement_filter;
+#[cfg(any(feature = "csv", feature = "json"))]
+mod stream_ingest;
+mod subscriptions;
+mod table_stats;
+mod trace;
 mod unsafe_opendal_store;
+mod warnings;
+mod whole_file;
+#[cfg(feature = "worker")]
+mod worker;
 
-pub use result_format::ResultFormat;
+pub use c_data::ArrowCData;
+pub use cast_policy::CastPolicy;
+pub use dataframe::WasmDataFrame;
+pub use ingest::SchemaEvolution;
+pub use listing::TableFormat;
+pub use logger::LogLevel;
+pub use result_format::{
+    register_result_renderer, result_renderer, result_renderer_names, IpcCompression, JsonNumbers,
+    ResultFormat, ResultRenderer,
+};
+pub use scheduling::QueryPriority;
+pub use segments::IpcSegment;
 
 fn set_panic_hook() {
-    std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+    panic_hook::install();
 }
@@ -0,0 +1,350 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Listing tables over a directory of files, with hive-style partitioning.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::common::GetExt;
+use datafusion::datasource::file_format::csv::CsvFormat;
+use datafusion::datasource::file_format::file_compression_type::FileCompressionType;
+use datafusion::datasource::file_format::json::{JsonFormat, JsonFormatFactory};
+#[cfg(feature = "parquet")]
+use datafusion::datasource::file_format::parquet::ParquetFormat;
+use datafusion::datasource::file_format::{FileFormat, FileFormatFactory};
+use datafusion::datasource::listing::{
+    ListingOptions, ListingTable, ListingTableConfig, ListingTableUrl,
+};
+use datafusion::execution::context::{SessionContext, SessionState};
+use serde::{Deserialize, Serialize};
+use tsify_next::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::compression;
+use crate::error::{Result, WasmError};
+use crate::extension;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Parquet,
+    Csv,
+    /// Newline-delimited JSON.
+    Json,
+}
+
+impl TableFormat {
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            TableFormat::Parquet => ".parquet",
+            TableFormat::Csv => ".csv",
+            TableFormat::Json => ".json",
+        }
+    }
+}
+
+/// `STORED AS NDJSON`, an alias of the newline-delimited `JSON` format.
+#[derive(Debug, Default)]
+pub struct NdJsonFormatFactory(JsonFormatFactory);
+
+impl GetExt for NdJsonFormatFactory {
+    fn get_ext(&self) -> String {
+        "ndjson".to_string()
+    }
+}
+
+impl FileFormatFactory for NdJsonFormatFactory {
+    fn create(
+        &self,
+        state: &SessionState,
+        format_options: &HashMap<String, String>,
+    ) -> datafusion::error::Result<Arc<dyn FileFormat>> {
+        self.0.create(state, format_options)
+    }
+
+    fn default(&self) -> Arc<dyn FileFormat> {
+        self.0.default()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Build a [`ListingTable`] over every file below `url_prefix`. Directories
+/// named `col=value` for each of `partition_cols` become string columns, and
+/// filters on them prune whole directories.
+pub async fn listing_table(
+    ctx: &SessionContext,
+    url_prefix: &str,
+    format: TableFormat,
+    partition_cols: Vec<String>,
+    reader_options: &ReaderOptions,
+) -> Result<ListingTable> {
+    let table_url = ListingTableUrl::parse(url_prefix)?;
+    let options = reader_options
+        .listing_options(url_prefix, format)?
+        .with_table_partition_cols(
+            partition_cols
+                .into_iter()
+                .map(|col| (col, DataType::Utf8))
+                .collect(),
+        );
+    let schema = match reader_options.schema()? {
+        Some(schema) => schema,
+        None => options.infer_schema(&ctx.state(), &table_url).await?,
+    };
+
+    let config = ListingTableConfig::new(table_url)
+        .with_listing_options(options)
+        .with_schema(schema);
+    Ok(ListingTable::try_new(config)?)
+}
+
+/// Reader options given as a JSON object. Options that don't apply to the format
+/// are ignored.
+#[derive(Debug, Default, Deserialize, Tsify)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReaderOptions {
+    pub has_header: Option<bool>,
+    pub delimiter: Option<char>,
+    pub quote: Option<char>,
+    pub escape: Option<char>,
+    /// Columns to read instead of inferring them, optionally tagged with a registered
+    /// extension type.
+    pub schema: Option<Vec<SchemaField>>,
+    /// Records read to infer CSV / JSON schemas.
+    pub max_records: Option<usize>,
+    /// `gzip`, `bzip2`, `xz` or `zstd`. Detected from the URL when not set.
+    pub compression: Option<String>,
+}
+
+impl ReaderOptions {
+    pub fn from_json(options: Option<&str>) -> Result<Self> {
+        match options {
+            Some(options) if !options.trim().is_empty() => Ok(serde_json::from_str(options)?),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Listing options for `format` files at `url`, compressed as configured or as
+    /// detected from the URL.
+    fn listing_options(&self, url: &str, format: TableFormat) -> Result<ListingOptions> {
+        let compression = match &self.compression {
+            Some(name) => Some(compression::parse(name)?),
+            None => compression::detect(url),
+        };
+        let compression = match (format, compression) {
+            (TableFormat::Csv | TableFormat::Json, Some(compression)) => {
+                FileCompressionType::from(compression)
+            }
+            _ => FileCompressionType::UNCOMPRESSED,
+        };
+        Ok(
+            ListingOptions::new(self.file_format(format, compression)?).with_file_extension(
+                format!("{}{}", format.file_extension(), compression.get_ext()),
+            ),
+        )
+    }
+
+    /// The schema override, if any.
+    fn schema(&self) -> Result<Option<SchemaRef>> {
+        self.schema.as_deref().map(parse_schema).transpose()
+    }
+
+    fn file_format(
+        &self,
+        format: TableFormat,
+        compression: FileCompressionType,
+    ) -> Result<Arc<dyn FileFormat>> {
+        Ok(match format {
+            #[cfg(feature = "parquet")]
+            TableFormat::Parquet => Arc::new(ParquetFormat::default()),
+            #[cfg(feature = "csv")]
+            TableFormat::Csv => {
+                let mut csv = CsvFormat::default().with_file_compression_type(compression);
+                if let Some(has_header) = self.has_header {
+                    csv = csv.with_has_header(has_header);
+                }
+                if let Some(delimiter) = self.delimiter {
+                    csv = csv.with_delimiter(single_byte("delimiter", delimiter)?);
+                }
+                if let Some(quote) = self.quote {
+                    csv = csv.with_quote(single_byte("quote", quote)?);
+                }
+                if let Some(escape) = self.escape {
+                    csv = csv.with_escape(Some(single_byte("escape", escape)?));
+                }
+                if let Some(max_records) = self.max_records {
+                    csv = csv.with_schema_infer_max_rec(max_records);
+                }
+                Arc::new(csv)
+            }
+            #[cfg(feature = "json")]
+            TableFormat::Json => {
+                let mut json = JsonFormat::default().with_file_compression_type(compression);
+                if let Some(max_records) = self.max_records {
+                    json = json.with_schema_infer_max_rec(max_records);
+                }
+                Arc::new(json)
+            }
+            #[allow(unreachable_patterns)]
+            format => return Err(format_disabled(format)),
+        })
+    }
+}
+
+/// Infer the schema of the file at `url`, or of every `format` file below it when
+/// it ends with `/`, without registering a table.
+pub async fn infer_schema(
+    ctx: &SessionContext,
+    url: &str,
+    format: TableFormat,
+    options: &ReaderOptions,
+) -> Result<SchemaRef> {
+    let table_url = ListingTableUrl::parse(url)?;
+    Ok(options
+        .listing_options(url, format)?
+        .infer_schema(&ctx.state(), &table_url)
+        .await?)
+}
+
+/// The error for a format whose cargo feature this build was compiled without.
+pub fn format_disabled(format: TableFormat) -> WasmError {
+    WasmError::Other(format!(
+        "{format:?} support is not compiled into this build, enable the `{}` feature",
+        format.file_extension().trim_start_matches('.')
+    ))
+}
+
+fn single_byte(option: &str, value: char) -> Result<u8> {
+    u8::try_from(value)
+        .map_err(|_| WasmError::Other(format!("{option} {value:?} is not a single byte")))
+}
+
+#[derive(Debug, Serialize, Deserialize, Tsify)]
+pub struct SchemaField {
+    pub name: String,
+    /// Arrow type name, e.g. `Int64`, `Utf8` or `Timestamp(Millisecond, None)`.
+    pub data_type: String,
+    #[serde(default = "nullable_default")]
+    pub nullable: bool,
+    /// Arrow extension type name, e.g. `arrow.uuid`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension: Option<String>,
+}
+
+impl SchemaField {
+    pub fn new(field: &Field) -> Self {
+        Self {
+            name: field.name().clone(),
+            data_type: field.data_type().to_string(),
+            nullable: field.is_nullable(),
+            extension: extension::extension_name(field).map(str::to_string),
+        }
+    }
+}
+
+/// The schema made of `fields`.
+pub fn parse_schema(fields: &[SchemaField]) -> Result<SchemaRef> {
+    let fields = fields
+        .iter()
+        .map(|field| {
+            let data_type = DataType::from_str(&field.data_type)
+                .map_err(|err| WasmError::Other(format!("column {}: {err}", field.name)))?;
+            let column = Field::new(&field.name, data_type, field.nullable);
+            match &field.extension {
+                Some(name) => extension::with_extension(column, name),
+                None => Ok(column),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Arc::new(Schema::new(fields)))
+}
+
+fn nullable_default() -> bool {
+    true
+}
+
+/// A flat description of a schema: `{fields: [{name, data_type, nullable}]}`.
+#[derive(Debug, Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct TableSchema {
+    pub fields: Vec<SchemaField>,
+}
+
+impl TableSchema {
+    pub fn new(schema: &SchemaRef) -> Self {
+        let fields = schema
+            .fields()
+            .iter()
+            .map(|field| SchemaField::new(field))
+            .collect();
+        Self { fields }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_options() {
+        let options =
+            ReaderOptions::from_json(Some(r#"{"has_header": false, "delimiter": ";"}"#)).unwrap();
+        assert_eq!(options.has_header, Some(false));
+        assert_eq!(options.delimiter, Some(';'));
+        assert!(ReaderOptions::from_json(None)
+            .unwrap()
+            .max_records
+            .is_none());
+        assert!(ReaderOptions::from_json(Some(r#"{"unknown": 1}"#)).is_err());
+    }
+
+    #[test]
+    fn test_schema_override() {
+        let options = ReaderOptions::from_json(Some(
+            r#"{"schema": [{"name": "id", "data_type": "Int64", "nullable": false}, {"name": "price", "data_type": "Decimal128(10, 2)"}]}"#,
+        ))
+        .unwrap();
+        let schema = options.schema().unwrap().unwrap();
+        assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+        assert!(!schema.field(0).is_nullable());
+        assert_eq!(schema.field(1).data_type(), &DataType::Decimal128(10, 2));
+
+        let options =
+            ReaderOptions::from_json(Some(r#"{"schema": [{"name": "a", "data_type": "Nope"}]}"#))
+                .unwrap();
+        assert!(options.schema().is_err());
+    }
+
+    #[test]
+    fn test_table_schema() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        assert_eq!(
+            serde_json::to_string(&TableSchema::new(&schema)).unwrap(),
+            r#"{"fields":[{"name":"id","data_type":"Int64","nullable":false},{"name":"name","data_type":"Utf8","nullable":true}]}"#
+        );
+    }
+}
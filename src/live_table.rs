@@ -0,0 +1,234 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Append-only in-memory tables fed continuously from JS, with optional
+//! retention by row count and age.
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::catalog::Session;
+use datafusion::datasource::{MemTable, TableProvider, TableType};
+use datafusion::logical_expr::Expr;
+use datafusion::physical_plan::ExecutionPlan;
+use serde::Deserialize;
+use tsify_next::Tsify;
+
+use crate::error::Result;
+
+/// Which rows a live table keeps. Rows are dropped oldest first.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Tsify)]
+#[serde(default, deny_unknown_fields)]
+pub struct Retention {
+    pub max_rows: Option<usize>,
+    /// Drop rows appended longer ago than this.
+    pub max_age_ms: Option<i64>,
+}
+
+#[derive(Debug, Default)]
+struct LiveState {
+    /// Appended batches with the time they were appended, oldest first.
+    batches: VecDeque<(DateTime<Utc>, RecordBatch)>,
+    rows: usize,
+}
+
+/// A table whose rows are appended in batches and scanned as a snapshot.
+#[derive(Debug)]
+pub struct LiveTable {
+    schema: SchemaRef,
+    retention: Retention,
+    state: Mutex<LiveState>,
+}
+
+impl LiveTable {
+    pub fn new(schema: SchemaRef, retention: Retention) -> Self {
+        Self {
+            schema,
+            retention,
+            state: Mutex::new(LiveState::default()),
+        }
+    }
+
+    /// Append `batches`, which must have the table schema, then apply the retention.
+    pub fn append(&self, batches: Vec<RecordBatch>) {
+        self.append_at(batches, Utc::now());
+    }
+
+    fn append_at(&self, batches: Vec<RecordBatch>, now: DateTime<Utc>) {
+        let mut state = self.state.lock().unwrap();
+        for batch in batches.into_iter().filter(|batch| batch.num_rows() > 0) {
+            state.rows += batch.num_rows();
+            state.batches.push_back((now, batch));
+        }
+        self.retain(&mut state, now);
+    }
+
+    /// Rows currently held.
+    #[cfg(test)]
+    pub fn num_rows(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        self.retain(&mut state, Utc::now());
+        state.rows
+    }
+
+    fn snapshot(&self, now: DateTime<Utc>) -> Vec<RecordBatch> {
+        let mut state = self.state.lock().unwrap();
+        self.retain(&mut state, now);
+        state
+            .batches
+            .iter()
+            .map(|(_, batch)| batch.clone())
+            .collect()
+    }
+
+    fn retain(&self, state: &mut LiveState, now: DateTime<Utc>) {
+        if let Some(max_age_ms) = self.retention.max_age_ms {
+            let oldest = now - Duration::milliseconds(max_age_ms);
+            while let Some((_, batch)) = state
+                .batches
+                .front()
+                .filter(|(appended, _)| *appended < oldest)
+            {
+                state.rows -= batch.num_rows();
+                state.batches.pop_front();
+            }
+        }
+        if let Some(max_rows) = self.retention.max_rows {
+            while state.rows > max_rows {
+                let (appended, batch) = state.batches.pop_front().unwrap();
+                let excess = state.rows - max_rows;
+                if batch.num_rows() > excess {
+                    // keep the newest rows of the batch
+                    let kept = batch.slice(excess, batch.num_rows() - excess);
+                    state.batches.push_front((appended, kept));
+                    state.rows -= excess;
+                } else {
+                    state.rows -= batch.num_rows();
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for LiveTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
+        MemTable::try_new(self.schema.clone(), vec![self.snapshot(Utc::now())])?
+            .scan(state, projection, filters, limit)
+            .await
+    }
+}
+
+/// Parse the retention options of `create_live_table`.
+pub fn retention_from_json(options: Option<&str>) -> Result<Retention> {
+    match options {
+        Some(options) if !options.trim().is_empty() => Ok(serde_json::from_str(options)?),
+        _ => Ok(Retention::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::Int64Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+
+    use super::*;
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![Field::new("v", DataType::Int64, false)]))
+    }
+
+    fn batch(values: Vec<i64>) -> RecordBatch {
+        RecordBatch::try_new(schema(), vec![Arc::new(Int64Array::from(values))]).unwrap()
+    }
+
+    fn values(table: &LiveTable, now: DateTime<Utc>) -> Vec<i64> {
+        table
+            .snapshot(now)
+            .iter()
+            .flat_map(|batch| {
+                let column = batch.column(0).as_any().downcast_ref::<Int64Array>();
+                column.unwrap().values().to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_max_rows_keeps_newest() {
+        let retention = Retention {
+            max_rows: Some(3),
+            ..Default::default()
+        };
+        let table = LiveTable::new(schema(), retention);
+        table.append(vec![batch(vec![1, 2])]);
+        table.append(vec![batch(vec![3, 4])]);
+
+        assert_eq!(values(&table, Utc::now()), vec![2, 3, 4]);
+        assert_eq!(table.num_rows(), 3);
+    }
+
+    #[test]
+    fn test_max_age_drops_old_batches() {
+        let retention = Retention {
+            max_age_ms: Some(1000),
+            ..Default::default()
+        };
+        let table = LiveTable::new(schema(), retention);
+        let start = Utc::now();
+        table.append_at(vec![batch(vec![1])], start);
+        table.append_at(vec![batch(vec![2])], start + Duration::milliseconds(800));
+
+        assert_eq!(
+            values(&table, start + Duration::milliseconds(900)),
+            vec![1, 2]
+        );
+        assert_eq!(
+            values(&table, start + Duration::milliseconds(1500)),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn test_retention_options() {
+        let retention = retention_from_json(Some(r#"{"max_rows": 10}"#)).unwrap();
+        assert_eq!(retention.max_rows, Some(10));
+        assert!(retention_from_json(Some(r#"{"rows": 10}"#)).is_err());
+    }
+}
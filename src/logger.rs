@@ -0,0 +1,130 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Leveled engine logs, written to the console or to a host callback.
+
+use std::fmt::Display;
+use std::sync::Mutex;
+
+use js_sys::Function;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+
+use crate::console;
+use crate::progress::JsCallback;
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn name(&self) -> &'static str {
+        match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
+struct Logger {
+    level: LogLevel,
+    sink: Option<JsCallback>,
+}
+
+static LOGGER: Mutex<Logger> = Mutex::new(Logger {
+    level: LogLevel::Info,
+    sink: None,
+});
+
+/// Log messages up to `level`, `Info` by default.
+pub fn set_level(level: LogLevel) {
+    LOGGER.lock().unwrap().level = level;
+}
+
+/// Send messages to `sink(level, message)` instead of the console, `None` to go back.
+pub fn set_sink(sink: Option<Function>) {
+    LOGGER.lock().unwrap().sink = sink.map(JsCallback);
+}
+
+pub fn enabled(level: LogLevel) -> bool {
+    level != LogLevel::Off && level <= LOGGER.lock().unwrap().level
+}
+
+/// Log `message`, formatted only when `level` is enabled, e.g. with `format_args!`.
+pub fn log(level: LogLevel, message: impl Display) {
+    if !enabled(level) {
+        return;
+    }
+    let message = message.to_string();
+    let sink = LOGGER.lock().unwrap().sink.clone();
+    match sink {
+        // a failing sink mustn't fail the query that logged
+        Some(JsCallback(sink)) => {
+            let _ = sink.call2(
+                &JsValue::NULL,
+                &JsValue::from_str(level.name()),
+                &JsValue::from_str(&message),
+            );
+        }
+        None => match level {
+            LogLevel::Error => console::error(&message),
+            LogLevel::Warn => console::warn(&message),
+            LogLevel::Info => console::info(&message),
+            _ => console::debug(&message),
+        },
+    }
+}
+
+pub fn warn(message: impl Display) {
+    log(LogLevel::Warn, message)
+}
+
+pub fn info(message: impl Display) {
+    log(LogLevel::Info, message)
+}
+
+pub fn debug(message: impl Display) {
+    log(LogLevel::Debug, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels() {
+        set_level(LogLevel::Warn);
+        assert!(enabled(LogLevel::Error));
+        assert!(enabled(LogLevel::Warn));
+        assert!(!enabled(LogLevel::Info));
+        assert!(!enabled(LogLevel::Off));
+
+        set_level(LogLevel::Off);
+        assert!(!enabled(LogLevel::Error));
+        set_level(LogLevel::Info);
+    }
+}
@@ -0,0 +1,509 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Cumulative metrics of a session, for "engine stats" pages.
+
+use std::any::Any;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use datafusion::arrow::array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::catalog::Session;
+use datafusion::datasource::{MemTable, TableProvider, TableType};
+use datafusion::execution::memory_pool::{
+    MemoryConsumer, MemoryPool, MemoryReservation, UnboundedMemoryPool,
+};
+use datafusion::logical_expr::Expr;
+use datafusion::physical_plan::ExecutionPlan;
+use serde::Serialize;
+use tsify_next::Tsify;
+
+use crate::execute_options::ExecuteOptions;
+use crate::object_store::OpendalRegistry;
+
+/// Queries kept in the history.
+const HISTORY_LIMIT: usize = 100;
+
+/// A memory pool that remembers the largest amount ever reserved from it.
+#[derive(Debug)]
+pub struct PeakMemoryPool {
+    inner: Arc<dyn MemoryPool>,
+    peak: AtomicUsize,
+    /// Peak since [`Self::reset_query_peak`].
+    query_peak: AtomicUsize,
+}
+
+impl Default for PeakMemoryPool {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(UnboundedMemoryPool::default()),
+            peak: AtomicUsize::new(0),
+            query_peak: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl PeakMemoryPool {
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    pub fn query_peak(&self) -> usize {
+        self.query_peak.load(Ordering::Relaxed)
+    }
+
+    pub fn reset_query_peak(&self) {
+        self.query_peak
+            .store(self.inner.reserved(), Ordering::Relaxed);
+    }
+
+    fn update_peak(&self) {
+        let reserved = self.inner.reserved();
+        self.peak.fetch_max(reserved, Ordering::Relaxed);
+        self.query_peak.fetch_max(reserved, Ordering::Relaxed);
+    }
+}
+
+impl MemoryPool for PeakMemoryPool {
+    fn register(&self, consumer: &MemoryConsumer) {
+        self.inner.register(consumer)
+    }
+
+    fn unregister(&self, consumer: &MemoryConsumer) {
+        self.inner.unregister(consumer)
+    }
+
+    fn grow(&self, reservation: &MemoryReservation, additional: usize) {
+        self.inner.grow(reservation, additional);
+        self.update_peak();
+    }
+
+    fn shrink(&self, reservation: &MemoryReservation, shrink: usize) {
+        self.inner.shrink(reservation, shrink)
+    }
+
+    fn try_grow(
+        &self,
+        reservation: &MemoryReservation,
+        additional: usize,
+    ) -> datafusion::error::Result<()> {
+        self.inner.try_grow(reservation, additional)?;
+        self.update_peak();
+        Ok(())
+    }
+
+    fn reserved(&self) -> usize {
+        self.inner.reserved()
+    }
+}
+
+/// Counters kept by the context itself. I/O and cache counters live in the
+/// [`OpendalRegistry`] and are read when taking a snapshot.
+#[derive(Debug, Default)]
+pub struct SessionMetrics {
+    queries: AtomicU64,
+    failed_queries: AtomicU64,
+    memory: Arc<PeakMemoryPool>,
+    labels: Mutex<BTreeMap<String, LabelMetrics>>,
+    history: Mutex<VecDeque<QueryRecord>>,
+}
+
+/// Totals of the queries run with one label.
+#[derive(Debug, Clone, Default, Serialize, Tsify)]
+pub struct LabelMetrics {
+    pub queries: u64,
+    pub failed_queries: u64,
+    pub bytes_downloaded: u64,
+    pub elapsed_ms: i64,
+}
+
+/// One entry of the query history.
+#[derive(Debug, Clone, Serialize, Tsify)]
+#[tsify(into_wasm_abi, missing_as_null, hashmap_as_object)]
+pub struct QueryRecord {
+    pub sql: String,
+    pub label: Option<String>,
+    pub tags: BTreeMap<String, String>,
+    /// RFC 3339 start time.
+    pub started_at: String,
+    pub elapsed_ms: i64,
+    pub bytes_downloaded: u64,
+    pub error: Option<String>,
+}
+
+impl SessionMetrics {
+    /// The pool to run the session with, so memory peaks are tracked.
+    pub fn memory_pool(&self) -> Arc<dyn MemoryPool> {
+        self.memory.clone()
+    }
+
+    pub fn memory(&self) -> &PeakMemoryPool {
+        &self.memory
+    }
+
+    /// Count a finished query and add it to the history. `bytes_downloaded` is what
+    /// the registry fetched while it ran, including fetches of concurrent queries.
+    pub fn record_query(
+        &self,
+        sql: &str,
+        options: &ExecuteOptions,
+        started_at: DateTime<Utc>,
+        bytes_downloaded: u64,
+        error: Option<String>,
+    ) {
+        let failed = error.is_some();
+        let elapsed_ms = (Utc::now() - started_at).num_milliseconds();
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        if failed {
+            self.failed_queries.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if let Some(label) = &options.label {
+            let mut labels = self.labels.lock().unwrap();
+            let metrics = labels.entry(label.clone()).or_default();
+            metrics.queries += 1;
+            metrics.failed_queries += failed as u64;
+            metrics.bytes_downloaded += bytes_downloaded;
+            metrics.elapsed_ms += elapsed_ms;
+        }
+
+        let mut history = self.history.lock().unwrap();
+        if history.len() == HISTORY_LIMIT {
+            history.pop_front();
+        }
+        history.push_back(QueryRecord {
+            sql: sql.to_string(),
+            label: options.label.clone(),
+            tags: options.tags.clone(),
+            started_at: started_at.to_rfc3339(),
+            elapsed_ms,
+            bytes_downloaded,
+            error,
+        });
+    }
+
+    /// The last queries run, oldest first.
+    pub fn history(&self) -> Vec<QueryRecord> {
+        self.history.lock().unwrap().iter().cloned().collect()
+    }
+
+    pub fn clear_history(&self) {
+        self.history.lock().unwrap().clear();
+    }
+
+    pub fn snapshot(&self, registry: &OpendalRegistry) -> MetricsSnapshot {
+        let (cache_hits, cache_misses) = registry.cache_hit_counts();
+        let lookups = cache_hits + cache_misses;
+        MetricsSnapshot {
+            queries: self.queries.load(Ordering::Relaxed),
+            failed_queries: self.failed_queries.load(Ordering::Relaxed),
+            bytes_downloaded: registry.progress().downloaded(),
+            cache_hits,
+            cache_misses,
+            cache_hit_rate: if lookups == 0 {
+                0.0
+            } else {
+                cache_hits as f64 / lookups as f64
+            },
+            memory_reserved: self.memory.reserved() as u64,
+            memory_peak: self.memory.peak() as u64,
+            labels: self.labels.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// Statistics of one `execute_sql` call, over all its statements.
+#[derive(Debug, Clone, Default, Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct QueryStats {
+    /// Id the query was queued with, see `running_queries`.
+    pub query_id: u64,
+    pub elapsed_ms: i64,
+    /// Rows produced, including rows dropped by the row limit.
+    pub rows: usize,
+    pub batches: usize,
+    /// Most memory reserved at once while the query ran. Concurrent queries share it.
+    pub peak_memory: usize,
+    /// Bytes read by file scans, as reported by their `bytes_scanned` metric.
+    pub bytes_scanned: usize,
+    /// Bytes fetched from remote objects, excluding cache hits.
+    pub bytes_downloaded: u64,
+    pub pruning: PruningStats,
+}
+
+/// What Parquet scans skipped, from their metrics. Row groups are pruned by their
+/// statistics, then by bloom filters; rows of the rest by the page index, then by
+/// filters evaluated while decoding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Tsify)]
+pub struct PruningStats {
+    pub row_groups_pruned_statistics: usize,
+    pub row_groups_matched_statistics: usize,
+    pub row_groups_pruned_bloom_filter: usize,
+    pub row_groups_matched_bloom_filter: usize,
+    pub page_index_rows_pruned: usize,
+    pub page_index_rows_matched: usize,
+    pub pushdown_rows_pruned: usize,
+    pub pushdown_rows_matched: usize,
+}
+
+impl PruningStats {
+    /// The pruning metrics of the scans of an executed `plan`.
+    pub fn from_plan(plan: &Arc<dyn ExecutionPlan>) -> Self {
+        Self {
+            row_groups_pruned_statistics: sum_metric(plan, "row_groups_pruned_statistics"),
+            row_groups_matched_statistics: sum_metric(plan, "row_groups_matched_statistics"),
+            row_groups_pruned_bloom_filter: sum_metric(plan, "row_groups_pruned_bloom_filter"),
+            row_groups_matched_bloom_filter: sum_metric(plan, "row_groups_matched_bloom_filter"),
+            page_index_rows_pruned: sum_metric(plan, "page_index_rows_pruned"),
+            page_index_rows_matched: sum_metric(plan, "page_index_rows_matched"),
+            pushdown_rows_pruned: sum_metric(plan, "pushdown_rows_pruned"),
+            pushdown_rows_matched: sum_metric(plan, "pushdown_rows_matched"),
+        }
+    }
+
+    pub fn add(&mut self, other: &Self) {
+        self.row_groups_pruned_statistics += other.row_groups_pruned_statistics;
+        self.row_groups_matched_statistics += other.row_groups_matched_statistics;
+        self.row_groups_pruned_bloom_filter += other.row_groups_pruned_bloom_filter;
+        self.row_groups_matched_bloom_filter += other.row_groups_matched_bloom_filter;
+        self.page_index_rows_pruned += other.page_index_rows_pruned;
+        self.page_index_rows_matched += other.page_index_rows_matched;
+        self.pushdown_rows_pruned += other.pushdown_rows_pruned;
+        self.pushdown_rows_matched += other.pushdown_rows_matched;
+    }
+}
+
+/// The `bytes_scanned` metric summed over every operator of an executed `plan`.
+pub fn bytes_scanned(plan: &Arc<dyn ExecutionPlan>) -> usize {
+    sum_metric(plan, "bytes_scanned")
+}
+
+/// Metric `name` summed over every operator of an executed `plan`.
+fn sum_metric(plan: &Arc<dyn ExecutionPlan>, name: &str) -> usize {
+    let own = plan
+        .metrics()
+        .and_then(|metrics| metrics.sum_by_name(name))
+        .map_or(0, |value| value.as_usize());
+    own + plan
+        .children()
+        .into_iter()
+        .map(|child| sum_metric(child, name))
+        .sum::<usize>()
+}
+
+#[derive(Debug, Clone, Serialize, Tsify)]
+#[tsify(into_wasm_abi, hashmap_as_object)]
+pub struct MetricsSnapshot {
+    pub queries: u64,
+    pub failed_queries: u64,
+    /// Bytes fetched from remote objects, excluding cache hits.
+    pub bytes_downloaded: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    /// Share of range cache lookups that hit, 0 before the first lookup.
+    pub cache_hit_rate: f64,
+    /// Bytes currently reserved by running operators.
+    pub memory_reserved: u64,
+    /// Largest number of bytes reserved at once.
+    pub memory_peak: u64,
+    /// Totals per query label.
+    pub labels: BTreeMap<String, LabelMetrics>,
+}
+
+impl MetricsSnapshot {
+    fn schema() -> SchemaRef {
+        let uint64 = |name| Field::new(name, DataType::UInt64, false);
+        Arc::new(Schema::new(vec![
+            uint64("queries"),
+            uint64("failed_queries"),
+            uint64("bytes_downloaded"),
+            uint64("cache_hits"),
+            uint64("cache_misses"),
+            Field::new("cache_hit_rate", DataType::Float64, false),
+            uint64("memory_reserved"),
+            uint64("memory_peak"),
+        ]))
+    }
+
+    fn to_record_batch(&self) -> datafusion::error::Result<RecordBatch> {
+        let uint64 = |value| Arc::new(UInt64Array::from(vec![value])) as ArrayRef;
+        Ok(RecordBatch::try_new(
+            Self::schema(),
+            vec![
+                uint64(self.queries),
+                uint64(self.failed_queries),
+                uint64(self.bytes_downloaded),
+                uint64(self.cache_hits),
+                uint64(self.cache_misses),
+                Arc::new(Float64Array::from(vec![self.cache_hit_rate])),
+                uint64(self.memory_reserved),
+                uint64(self.memory_peak),
+            ],
+        )?)
+    }
+}
+
+/// A one-row table with the metrics at the time it is scanned.
+#[derive(Debug)]
+pub struct MetricsTable {
+    metrics: Arc<SessionMetrics>,
+    registry: OpendalRegistry,
+}
+
+impl MetricsTable {
+    pub fn new(metrics: Arc<SessionMetrics>, registry: OpendalRegistry) -> Self {
+        Self { metrics, registry }
+    }
+}
+
+#[async_trait]
+impl TableProvider for MetricsTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        MetricsSnapshot::schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::View
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
+        let batch = self.metrics.snapshot(&self.registry).to_record_batch()?;
+        MemTable::try_new(batch.schema(), vec![vec![batch]])?
+            .scan(state, projection, filters, limit)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let metrics = SessionMetrics::default();
+        let labeled = ExecuteOptions {
+            label: Some("dashboard".to_string()),
+            ..Default::default()
+        };
+        metrics.record_query("SELECT 1", &labeled, Utc::now(), 10, None);
+        metrics.record_query(
+            "SELEC 1",
+            &ExecuteOptions::default(),
+            Utc::now(),
+            0,
+            Some("syntax error".to_string()),
+        );
+
+        let pool = metrics.memory_pool();
+        let mut reservation = MemoryConsumer::new("test").register(&pool);
+        reservation.try_grow(100).unwrap();
+        reservation.shrink(60);
+
+        assert_eq!(metrics.memory().query_peak(), 100);
+        metrics.memory().reset_query_peak();
+        assert_eq!(metrics.memory().query_peak(), 40);
+
+        let snapshot = metrics.snapshot(&OpendalRegistry::new());
+        assert_eq!(snapshot.queries, 2);
+        assert_eq!(snapshot.failed_queries, 1);
+        assert_eq!(snapshot.memory_reserved, 40);
+        assert_eq!(snapshot.memory_peak, 100);
+        assert_eq!(snapshot.cache_hit_rate, 0.0);
+        assert_eq!(snapshot.labels["dashboard"].queries, 1);
+        assert_eq!(snapshot.labels["dashboard"].bytes_downloaded, 10);
+        assert_eq!(snapshot.to_record_batch().unwrap().num_rows(), 1);
+
+        let history = metrics.history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].label.as_deref(), Some("dashboard"));
+        assert_eq!(history[1].error.as_deref(), Some("syntax error"));
+    }
+
+    #[test]
+    fn test_history_limit() {
+        let metrics = SessionMetrics::default();
+        for i in 0..HISTORY_LIMIT + 5 {
+            let sql = format!("SELECT {i}");
+            metrics.record_query(&sql, &ExecuteOptions::default(), Utc::now(), 0, None);
+        }
+        let history = metrics.history();
+        assert_eq!(history.len(), HISTORY_LIMIT);
+        assert_eq!(history[0].sql, "SELECT 5");
+    }
+
+    #[cfg(feature = "parquet")]
+    #[tokio::test]
+    async fn test_row_groups_pruned() {
+        use datafusion::arrow::array::{Int64Array, RecordBatch};
+        use datafusion::arrow::datatypes::{DataType, Field, Schema};
+        use datafusion::physical_plan::collect;
+        use datafusion::prelude::{ParquetReadOptions, SessionContext};
+        use parquet::arrow::ArrowWriter;
+        use parquet::file::properties::WriterProperties;
+
+        let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from(vec![1, 2, 3, 4]))],
+        )
+        .unwrap();
+        let path = std::env::temp_dir().join(format!("pruning-{}.parquet", std::process::id()));
+        let properties = WriterProperties::builder()
+            .set_max_row_group_size(2)
+            .build();
+        let mut writer = ArrowWriter::try_new(
+            std::fs::File::create(&path).unwrap(),
+            schema,
+            Some(properties),
+        )
+        .unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let ctx = SessionContext::new();
+        ctx.register_parquet("t", path.to_str().unwrap(), ParquetReadOptions::default())
+            .await
+            .unwrap();
+        let plan = ctx
+            .sql("SELECT v FROM t WHERE v = 4")
+            .await
+            .unwrap()
+            .create_physical_plan()
+            .await
+            .unwrap();
+        collect(plan.clone(), ctx.task_ctx()).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let pruning = PruningStats::from_plan(&plan);
+        assert_eq!(pruning.row_groups_pruned_statistics, 1);
+        assert_eq!(pruning.row_groups_matched_statistics, 1);
+    }
+}
@@ -0,0 +1,180 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Isolated table namespaces, so one context can serve several workspaces.
+//!
+//! A namespace is a catalog holding the tables mounted into it. Queries run in a
+//! namespace see that catalog as the default one and no other catalog, so they can't
+//! reach tables of the main session or of other namespaces. Tables they create stay
+//! in the namespace.
+//!
+//! Only the catalog is isolated. A namespace shares the session's runtime, so its
+//! queries reach every object store registered on the session, with the same S3
+//! credentials, and can read any URL those stores serve with `CREATE EXTERNAL TABLE`
+//! or write to one with `COPY TO`. Reject such statements with a statement filter
+//! where namespaces separate untrusted users.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use datafusion::catalog::CatalogProvider;
+use datafusion::catalog_common::{
+    MemoryCatalogProvider, MemoryCatalogProviderList, MemorySchemaProvider,
+};
+use datafusion::execution::context::SessionContext;
+use datafusion::execution::session_state::SessionStateBuilder;
+use datafusion::sql::TableReference;
+
+use crate::error::{Result, WasmError};
+
+#[derive(Debug, Clone, Default)]
+pub struct Namespaces {
+    catalogs: Arc<RwLock<HashMap<String, Arc<dyn CatalogProvider>>>>,
+}
+
+impl Namespaces {
+    /// Make `tables` of `ctx` visible in namespace `name`, creating it if needed.
+    /// Tables are mounted under their unqualified name and share their data with `ctx`.
+    pub async fn mount(&self, ctx: &SessionContext, name: &str, tables: &[String]) -> Result<()> {
+        if name.is_empty() {
+            return Err(WasmError::Other("namespace name is empty".to_string()));
+        }
+
+        let mut providers = Vec::with_capacity(tables.len());
+        for table in tables {
+            let reference = TableReference::from(table.as_str());
+            let provider = ctx.table_provider(reference.clone()).await?;
+            providers.push((reference.table().to_string(), provider));
+        }
+
+        let catalog = self
+            .catalogs
+            .write()
+            .unwrap()
+            .entry(name.to_string())
+            .or_insert_with(|| new_catalog(ctx))
+            .clone();
+        let state = ctx.state();
+        let schema_name = &state.config().options().catalog.default_schema;
+        let schema = catalog
+            .schema(schema_name)
+            .ok_or_else(|| WasmError::Other(format!("namespace {name} has no schema")))?;
+        for (table, provider) in providers {
+            schema.register_table(table, provider)?;
+        }
+        Ok(())
+    }
+
+    /// Drop namespace `name` and every table created in it. Mounted tables stay
+    /// registered in the main session.
+    pub fn unmount(&self, name: &str) -> bool {
+        self.catalogs.write().unwrap().remove(name).is_some()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.catalogs.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// A session over `ctx`'s settings and runtime that only sees namespace `name`.
+    pub fn context(&self, ctx: &SessionContext, name: &str) -> Result<Arc<SessionContext>> {
+        let catalog = self
+            .catalogs
+            .read()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| WasmError::Other(format!("namespace {name} is not mounted")))?;
+
+        let state = SessionStateBuilder::new_from_existing(ctx.state())
+            .with_catalog_list(Arc::new(MemoryCatalogProviderList::new()))
+            .build();
+        let default_catalog = state.config().options().catalog.default_catalog.clone();
+        state
+            .catalog_list()
+            .register_catalog(default_catalog, catalog);
+        Ok(Arc::new(SessionContext::new_with_state(state)))
+    }
+}
+
+fn new_catalog(ctx: &SessionContext) -> Arc<dyn CatalogProvider> {
+    let catalog = MemoryCatalogProvider::new();
+    let schema_name = ctx
+        .state()
+        .config()
+        .options()
+        .catalog
+        .default_schema
+        .clone();
+    // a fresh catalog has no schema to replace
+    let _ = catalog.register_schema(&schema_name, Arc::new(MemorySchemaProvider::new()));
+    Arc::new(catalog)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::{ArrayRef, Int32Array, RecordBatch};
+    use datafusion::datasource::MemTable;
+
+    fn table() -> Arc<MemTable> {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "v",
+            Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef,
+        )])
+        .unwrap();
+        Arc::new(MemTable::try_new(batch.schema(), vec![vec![batch]]).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_namespace_isolation() {
+        let ctx = SessionContext::new();
+        ctx.register_table("orders", table()).unwrap();
+        ctx.register_table("secrets", table()).unwrap();
+
+        let namespaces = Namespaces::default();
+        namespaces
+            .mount(&ctx, "tenant_a", &["orders".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(namespaces.names(), vec!["tenant_a"]);
+
+        let tenant = namespaces.context(&ctx, "tenant_a").unwrap();
+        let batches = tenant
+            .sql("SELECT * FROM orders")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(batches[0].num_rows(), 2);
+        assert!(tenant.sql("SELECT * FROM secrets").await.is_err());
+
+        // tables created in the namespace stay there
+        tenant
+            .sql("CREATE TABLE scratch AS SELECT 1 AS one")
+            .await
+            .unwrap();
+        let tenant = namespaces.context(&ctx, "tenant_a").unwrap();
+        assert!(tenant.table_exist("scratch").unwrap());
+        assert!(!ctx.table_exist("scratch").unwrap());
+
+        assert!(namespaces.unmount("tenant_a"));
+        assert!(namespaces.context(&ctx, "tenant_a").is_err());
+    }
+}
@@ -0,0 +1,329 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! A read-only object store over Node's `fs` module, serving `file://` URLs when
+//! the module runs under Node.js or Electron.
+
+use std::ops::Range;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{TimeZone, Utc};
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use js_sys::{Array, Function, Promise, Uint8Array};
+use object_store::path::Path;
+use object_store::{
+    Attributes, Error, GetOptions, GetRange, GetResult, GetResultPayload, ListResult,
+    MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult,
+    Result,
+};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::runtime::property;
+use crate::unsafe_opendal_store::ForceSend;
+
+const STORE: &str = "NodeFs";
+
+#[derive(Debug, Default)]
+pub struct NodeFsStore;
+
+impl std::fmt::Display for NodeFsStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{STORE}")
+    }
+}
+
+/// `fs.promises`, from `process.getBuiltinModule` (Node.js 20.16+) or a global `require`.
+fn fs_promises() -> Result<JsValue> {
+    let global = js_sys::global();
+    let process = property(&global, "process");
+    let fs = match property(&process, "getBuiltinModule").dyn_into::<Function>() {
+        Ok(get_builtin_module) => get_builtin_module.call1(&process, &"node:fs".into()),
+        Err(_) => match property(&global, "require").dyn_into::<Function>() {
+            Ok(require) => require.call1(&JsValue::NULL, &"fs".into()),
+            Err(_) => {
+                return Err(generic(
+                    "Node's fs module is unavailable, file:// URLs need Node.js 20.16 or later",
+                ))
+            }
+        },
+    }
+    .map_err(|err| generic(js_message(&err)))?;
+    Ok(property(&fs, "promises"))
+}
+
+/// Call `target[method](...args)` and await the promise it returns.
+async fn call(
+    target: &JsValue,
+    method: &str,
+    args: &[JsValue],
+) -> std::result::Result<JsValue, JsValue> {
+    let function: Function = property(target, method)
+        .dyn_into()
+        .map_err(|_| JsValue::from_str(&format!("{method} is not a function")))?;
+    let promise = function.apply(target, &args.iter().collect::<Array>())?;
+    JsFuture::from(Promise::from(promise)).await
+}
+
+/// The file system path of `location`, absolute unless it starts with a drive letter.
+fn fs_path(location: &Path) -> JsValue {
+    let path = location.as_ref();
+    match path.as_bytes() {
+        [drive, b':', ..] if drive.is_ascii_alphabetic() => path.into(),
+        _ => format!("/{path}").into(),
+    }
+}
+
+fn js_message(err: &JsValue) -> String {
+    property(err, "message")
+        .as_string()
+        .or_else(|| err.as_string())
+        .unwrap_or_else(|| format!("{err:?}"))
+}
+
+fn generic(message: impl Into<String>) -> Error {
+    Error::Generic {
+        store: STORE,
+        source: message.into().into(),
+    }
+}
+
+/// `NotFound` for a missing file, `Generic` otherwise.
+fn error(location: &Path, err: JsValue) -> Error {
+    let message = js_message(&err);
+    match property(&err, "code").as_string().as_deref() {
+        Some("ENOENT") => Error::NotFound {
+            path: location.to_string(),
+            source: message.into(),
+        },
+        _ => generic(message),
+    }
+}
+
+fn meta(location: Path, stats: &JsValue) -> ObjectMeta {
+    let size = property(stats, "size").as_f64().unwrap_or_default() as usize;
+    let modified = property(stats, "mtimeMs").as_f64().unwrap_or_default() as i64;
+    ObjectMeta {
+        location,
+        last_modified: Utc
+            .timestamp_millis_opt(modified)
+            .single()
+            .unwrap_or_default(),
+        size,
+        e_tag: Some(format!("{modified:x}-{size:x}")),
+        version: None,
+    }
+}
+
+fn is_dir(stats: &JsValue) -> bool {
+    property(stats, "isDirectory")
+        .dyn_into::<Function>()
+        .ok()
+        .and_then(|is_directory| is_directory.call0(stats).ok())
+        .and_then(|is_dir| is_dir.as_bool())
+        .unwrap_or(false)
+}
+
+async fn stat(location: &Path) -> Result<JsValue> {
+    call(&fs_promises()?, "stat", &[fs_path(location)])
+        .await
+        .map_err(|err| error(location, err))
+}
+
+/// The bytes of `range` of the file at `location`, fewer at its end.
+async fn read(location: &Path, range: Range<usize>) -> Result<Bytes> {
+    let handle = call(&fs_promises()?, "open", &[fs_path(location), "r".into()])
+        .await
+        .map_err(|err| error(location, err))?;
+    let buffer = Uint8Array::new_with_length(range.len() as u32);
+    let read = call(
+        &handle,
+        "read",
+        &[
+            buffer.clone().into(),
+            JsValue::from_f64(0.0),
+            JsValue::from_f64(range.len() as f64),
+            JsValue::from_f64(range.start as f64),
+        ],
+    )
+    .await;
+    let _ = call(&handle, "close", &[]).await;
+    let read = read.map_err(|err| error(location, err))?;
+    let bytes_read = property(&read, "bytesRead").as_f64().unwrap_or_default() as u32;
+    Ok(Bytes::from(buffer.subarray(0, bytes_read).to_vec()))
+}
+
+/// The entries of directory `dir` with their `fs.Stats`.
+async fn read_dir(dir: &Path) -> Result<Vec<(Path, JsValue)>> {
+    let fs = fs_promises()?;
+    let names = call(&fs, "readdir", &[fs_path(dir)])
+        .await
+        .map_err(|err| error(dir, err))?;
+    let mut entries = Vec::new();
+    for name in Array::from(&names)
+        .iter()
+        .filter_map(|name| name.as_string())
+    {
+        let location = dir.child(name.as_str());
+        let stats = stat(&location).await?;
+        entries.push((location, stats));
+    }
+    Ok(entries)
+}
+
+/// Every file below `prefix`, none when it doesn't exist.
+async fn walk(prefix: &Path) -> Result<Vec<ObjectMeta>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![prefix.clone()];
+    while let Some(dir) = dirs.pop() {
+        let entries = match read_dir(&dir).await {
+            Err(Error::NotFound { .. }) if dir == *prefix => return Ok(files),
+            entries => entries?,
+        };
+        for (location, stats) in entries {
+            match is_dir(&stats) {
+                true => dirs.push(location),
+                false => files.push(meta(location, &stats)),
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn resolve(range: &GetRange, size: usize) -> Result<Range<usize>> {
+    let range = match range {
+        GetRange::Bounded(range) => range.start..range.end.min(size),
+        GetRange::Offset(start) => *start..size,
+        GetRange::Suffix(suffix) => size.saturating_sub(*suffix)..size,
+    };
+    if range.start > range.end {
+        return Err(generic(format!(
+            "range {range:?} is outside of a {size} byte file"
+        )));
+    }
+    Ok(range)
+}
+
+#[async_trait]
+impl ObjectStore for NodeFsStore {
+    async fn put_opts(
+        &self,
+        _location: &Path,
+        _payload: PutPayload,
+        _opts: PutOptions,
+    ) -> Result<PutResult> {
+        Err(Error::NotImplemented)
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        _location: &Path,
+        _opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        Err(Error::NotImplemented)
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        ForceSend::new(async move {
+            let meta = self.head(location).await?;
+            let range = match &options.range {
+                Some(range) => resolve(range, meta.size)?,
+                None => 0..meta.size,
+            };
+            let bytes = read(location, range.clone()).await?;
+            Ok(GetResult {
+                payload: GetResultPayload::Stream(
+                    futures::stream::once(async move { Ok(bytes) }).boxed(),
+                ),
+                meta,
+                range,
+                attributes: Attributes::default(),
+            })
+        })
+        .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        ForceSend::new(async move {
+            let stats = stat(location).await?;
+            if is_dir(&stats) {
+                return Err(Error::NotFound {
+                    path: location.to_string(),
+                    source: "is a directory".into(),
+                });
+            }
+            Ok(meta(location.clone(), &stats))
+        })
+        .await
+    }
+
+    async fn delete(&self, _location: &Path) -> Result<()> {
+        Err(Error::NotImplemented)
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned().unwrap_or_default();
+        futures::stream::once(ForceSend::new(async move {
+            match walk(&prefix).await {
+                Ok(files) => futures::stream::iter(files.into_iter().map(Ok)).boxed(),
+                Err(err) => futures::stream::once(async move { Err(err) }).boxed(),
+            }
+        }))
+        .flatten()
+        .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let prefix = prefix.cloned().unwrap_or_default();
+        ForceSend::new(async move {
+            let mut result = ListResult {
+                common_prefixes: Vec::new(),
+                objects: Vec::new(),
+            };
+            for (location, stats) in read_dir(&prefix).await? {
+                match is_dir(&stats) {
+                    true => result.common_prefixes.push(location),
+                    false => result.objects.push(meta(location, &stats)),
+                }
+            }
+            Ok(result)
+        })
+        .await
+    }
+
+    async fn copy(&self, _from: &Path, _to: &Path) -> Result<()> {
+        Err(Error::NotImplemented)
+    }
+
+    async fn copy_if_not_exists(&self, _from: &Path, _to: &Path) -> Result<()> {
+        Err(Error::NotImplemented)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_range() {
+        assert_eq!(resolve(&GetRange::Bounded(2..8), 5).unwrap(), 2..5);
+        assert_eq!(resolve(&GetRange::Offset(3), 5).unwrap(), 3..5);
+        assert_eq!(resolve(&GetRange::Suffix(10), 5).unwrap(), 0..5);
+        assert!(resolve(&GetRange::Offset(6), 5).is_err());
+    }
+}
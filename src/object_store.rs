@@ -2,14 +2,32 @@ use std::sync::{Arc, Mutex};
 
 use datafusion::execution::object_store::ObjectStoreRegistry;
 use object_store::ObjectStore;
+#[cfg(feature = "http")]
 use opendal::raw::HttpClient;
-use opendal::services::{Http, S3};
+#[cfg(all(
+    feature = "fs",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+use opendal::services::Fs;
+#[cfg(feature = "http")]
+use opendal::services::Http;
+#[cfg(feature = "s3")]
+use opendal::services::S3;
 use opendal::Operator;
+#[cfg(feature = "http")]
 use reqwest::header::{HeaderMap, ACCESS_CONTROL_ALLOW_ORIGIN};
+#[cfg(feature = "http")]
 use reqwest::ClientBuilder;
 use url::Url;
 
-use crate::unsafe_opendal_store::OpendalStore;
+use crate::cache::RangeCache;
+use crate::credentials::{self, RefreshingStore};
+use crate::node_fs::NodeFsStore;
+use crate::progress::{IoProgress, JsCallback};
+use crate::runtime::Runtime;
+use crate::trace::Tracer;
+use crate::unsafe_opendal_store::{OpendalStore, ReadConfig};
+use crate::whole_file::WholeFiles;
 
 #[derive(Debug, Default)]
 pub struct S3Config {
@@ -18,16 +36,23 @@ pub struct S3Config {
     pub region: String,
     pub access_key_id: String,
     pub secret_access_key: String,
+    pub session_token: String,
 }
 
 #[derive(Debug, Default)]
 struct RegistryState {
     s3_config: S3Config,
+    read_config: ReadConfig,
+    credential_callback: Option<JsCallback>,
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct OpendalRegistry {
     state: Arc<Mutex<RegistryState>>,
+    cache: Arc<RangeCache>,
+    whole_files: Arc<WholeFiles>,
+    progress: IoProgress,
+    tracer: Tracer,
 }
 
 impl OpendalRegistry {
@@ -40,20 +65,132 @@ impl OpendalRegistry {
         state.s3_config = s3_config;
     }
 
+    /// Set the host callback asked for new S3 credentials when a request is rejected
+    /// with 401 or 403. The request is then retried once.
+    pub fn set_credential_callback(&self, callback: Option<js_sys::Function>) {
+        let mut state = self.state.lock().unwrap();
+        state.credential_callback = callback.map(JsCallback);
+    }
+
+    /// Ask the credential callback for new credentials for `url`, keep them for stores
+    /// built afterwards, and return a store using them.
+    pub async fn refresh_credentials(
+        &self,
+        url: &Url,
+    ) -> object_store::Result<Arc<dyn ObjectStore>> {
+        let callback = self.state.lock().unwrap().credential_callback.clone();
+        let callback = callback.ok_or_else(|| object_store::Error::Generic {
+            store: "credentials",
+            source: "no credential callback is set".into(),
+        })?;
+        let refreshed = credentials::request(&callback, url).await?;
+        {
+            let mut state = self.state.lock().unwrap();
+            state.s3_config.access_key_id = refreshed.access_key_id;
+            state.s3_config.secret_access_key = refreshed.secret_access_key;
+            state.s3_config.session_token = refreshed.session_token;
+        }
+
+        let store = self
+            .build_store(url)
+            .ok_or_else(|| object_store::Error::Generic {
+                store: "credentials",
+                source: format!("failed to build a store for {url}").into(),
+            })?;
+        Ok(Arc::new(store))
+    }
+
+    /// Forget the S3 configuration and credential callback. Caches are kept.
+    pub fn reset_config(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.s3_config = S3Config::default();
+        state.credential_callback = None;
+    }
+
+    /// Configure how ranged reads are coalesced and prefetched by stores built afterwards.
+    pub fn set_read_config(&self, read_config: ReadConfig) {
+        let mut state = self.state.lock().unwrap();
+        state.read_config = read_config;
+    }
+
+    /// Set the byte budget of the range cache shared by all stores. 0 disables it.
+    pub fn set_cache_capacity(&self, capacity: usize) {
+        self.cache.set_capacity(capacity);
+    }
+
+    /// Set how long cached objects are served before being revalidated with their ETag.
+    pub fn set_cache_revalidation(&self, revalidate_after: chrono::Duration) {
+        self.cache.set_revalidate_after(revalidate_after);
+    }
+
+    /// Set the largest object downloaded in full from servers that ignore `Range`
+    /// headers. Larger objects fail to read from such servers.
+    pub fn set_whole_file_limit(&self, limit: usize) {
+        self.whole_files.set_limit(limit);
+    }
+
+    pub fn progress(&self) -> &IoProgress {
+        &self.progress
+    }
+
+    pub fn tracer(&self) -> &Tracer {
+        &self.tracer
+    }
+
+    /// Range cache lookups that hit and missed.
+    pub fn cache_hit_counts(&self) -> (u64, u64) {
+        self.cache.hit_counts()
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.clear();
+        self.whole_files.clear();
+    }
+
+    fn build_store(&self, url: &Url) -> Option<OpendalStore> {
+        let operator = self.build_from_url(url)?;
+        let prefix = format!(
+            "{}://{}:{}",
+            url.scheme(),
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or(0)
+        );
+        let read_config = self.state.lock().unwrap().read_config;
+        let store = OpendalStore::new(operator)
+            .with_cache(self.cache.clone(), prefix.clone())
+            .with_read_config(read_config)
+            .with_progress(self.progress.clone())
+            .with_tracer(self.tracer.clone());
+
+        match url.scheme().to_ascii_lowercase().as_str() {
+            "http" | "https" => Some(
+                store
+                    .with_http_endpoint(prefix)
+                    .with_whole_files(self.whole_files.clone()),
+            ),
+            _ => Some(store),
+        }
+    }
+
     pub fn build_from_url(&self, url: &Url) -> Option<Operator> {
         match url.scheme().to_ascii_lowercase().as_str() {
+            #[cfg(feature = "s3")]
             "s3" => {
                 let state = self.state.lock().unwrap();
 
-                let builder = S3::default()
+                let mut builder = S3::default()
                     .root(&state.s3_config.root)
                     .bucket(&state.s3_config.bucket)
                     .region(&state.s3_config.region)
                     .endpoint("https://s3.amazonaws.com")
                     .access_key_id(&state.s3_config.access_key_id)
                     .secret_access_key(&state.s3_config.secret_access_key);
+                if !state.s3_config.session_token.is_empty() {
+                    builder = builder.session_token(&state.s3_config.session_token);
+                }
                 Some(Operator::new(builder).ok()?.finish())
             }
+            #[cfg(feature = "http")]
             "http" | "https" => {
                 let mut headers = HeaderMap::new();
                 headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, "*".parse().unwrap());
@@ -69,6 +206,13 @@ impl OpendalRegistry {
                     ));
                 Some(Operator::new(builder).unwrap().finish())
             }
+            // the browser and Node.js builds have no file system access of their own,
+            // `get_store` serves `file://` through Node's `fs` module there
+            #[cfg(all(
+                feature = "fs",
+                not(all(target_arch = "wasm32", target_os = "unknown"))
+            ))]
+            "file" => Some(Operator::new(Fs::default().root("/")).ok()?.finish()),
             _ => None,
         }
     }
@@ -80,16 +224,59 @@ impl ObjectStoreRegistry for OpendalRegistry {
         url: &Url,
         _store: Arc<dyn ObjectStore>,
     ) -> Option<Arc<dyn ObjectStore>> {
-        let operator = self.build_from_url(url)?;
-        Some(Arc::new(OpendalStore::new(operator)))
+        Some(Arc::new(self.build_store(url)?))
     }
 
     fn get_store(&self, url: &Url) -> datafusion::error::Result<Arc<dyn ObjectStore>> {
-        let operator = self.build_from_url(url).ok_or_else(|| {
+        if url.scheme().eq_ignore_ascii_case("file") {
+            match Runtime::detect() {
+                Runtime::Node => return Ok(Arc::new(NodeFsStore)),
+                // OpenDAL's fs service, in builds with the `fs` feature
+                Runtime::Native => {}
+                runtime => {
+                    return Err(datafusion::error::DataFusionError::Execution(format!(
+                        "file:// URLs are not readable in {}, only under Node.js",
+                        runtime.name()
+                    )))
+                }
+            }
+        }
+        let store = self.build_store(url).ok_or_else(|| {
             datafusion::error::DataFusionError::Execution(
                 "Failed to build operator from URL".to_string(),
             )
         })?;
-        Ok(Arc::new(OpendalStore::new(operator)))
+        let refreshable = url.scheme().eq_ignore_ascii_case("s3")
+            && self.state.lock().unwrap().credential_callback.is_some();
+        if refreshable {
+            return Ok(Arc::new(RefreshingStore::new(
+                self.clone(),
+                url.clone(),
+                Arc::new(store),
+            )));
+        }
+        Ok(Arc::new(store))
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use object_store::path::Path;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_url() {
+        let path = std::env::temp_dir().join(format!("registry-{}.csv", std::process::id()));
+        std::fs::write(&path, "a\n1\n").unwrap();
+        let url = Url::from_file_path(&path).unwrap();
+
+        let store = OpendalRegistry::new().get_store(&url).unwrap();
+        let meta = store
+            .head(&Path::from_url_path(url.path()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(meta.size, 4);
+        std::fs::remove_file(path).unwrap();
     }
 }
@@ -0,0 +1,161 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Results read a page of rows at a time from the running plan, for grids
+//! with infinite scroll.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::execution::SendableRecordBatchStream;
+use futures::StreamExt;
+use tokio::sync::Mutex;
+
+use crate::error::{Result, WasmError};
+
+struct PageCursor {
+    stream: SendableRecordBatchStream,
+    /// Rows of a batch that didn't fit into the previous page.
+    pending: Option<RecordBatch>,
+    page_size: usize,
+    done: bool,
+}
+
+impl PageCursor {
+    /// The next `page_size` rows, fewer at the end of the stream.
+    async fn next_page(&mut self) -> Result<Vec<RecordBatch>> {
+        let mut batches = Vec::new();
+        let mut rows = 0;
+        while rows < self.page_size {
+            let batch = match self.pending.take() {
+                Some(batch) => batch,
+                None => match self.stream.next().await {
+                    Some(batch) => batch?,
+                    None => {
+                        self.done = true;
+                        break;
+                    }
+                },
+            };
+
+            let wanted = self.page_size - rows;
+            if batch.num_rows() > wanted {
+                self.pending = Some(batch.slice(wanted, batch.num_rows() - wanted));
+                batches.push(batch.slice(0, wanted));
+                rows += wanted;
+            } else {
+                rows += batch.num_rows();
+                batches.push(batch);
+            }
+        }
+        Ok(batches)
+    }
+}
+
+/// Open paged results, keyed by cursor token.
+#[derive(Default)]
+pub struct Pages {
+    cursors: Mutex<HashMap<String, PageCursor>>,
+}
+
+impl Pages {
+    /// Start paging `stream`, returning the cursor token.
+    pub async fn open(
+        &self,
+        stream: SendableRecordBatchStream,
+        page_size: usize,
+    ) -> Result<String> {
+        if page_size == 0 {
+            return Err(WasmError::Other("page size must be positive".to_string()));
+        }
+        let token = next_token();
+        let cursor = PageCursor {
+            stream,
+            pending: None,
+            page_size,
+            done: false,
+        };
+        self.cursors.lock().await.insert(token.clone(), cursor);
+        Ok(token)
+    }
+
+    /// The next page of cursor `token`, or `None` once every row was returned, which
+    /// also closes the cursor.
+    pub async fn next(&self, token: &str) -> Result<Option<Vec<RecordBatch>>> {
+        // taken out while reading, so other cursors stay usable
+        let mut cursor = self
+            .cursors
+            .lock()
+            .await
+            .remove(token)
+            .ok_or_else(|| WasmError::Other(format!("unknown cursor: {token}")))?;
+        if cursor.done && cursor.pending.is_none() {
+            return Ok(None);
+        }
+
+        let batches = cursor.next_page().await?;
+        if batches.is_empty() {
+            return Ok(None);
+        }
+        self.cursors.lock().await.insert(token.to_string(), cursor);
+        Ok(Some(batches))
+    }
+
+    /// Stop the query of cursor `token`. Returns whether it was open.
+    pub async fn close(&self, token: &str) -> bool {
+        self.cursors.lock().await.remove(token).is_some()
+    }
+}
+
+fn next_token() -> String {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    format!("cursor-{}", NEXT.fetch_add(1, Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::Int64Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::physical_plan::memory::MemoryStream;
+    use std::sync::Arc;
+
+    fn rows(batches: &[RecordBatch]) -> usize {
+        batches.iter().map(|batch| batch.num_rows()).sum()
+    }
+
+    #[tokio::test]
+    async fn test_pages() {
+        let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from_iter_values(0..5))],
+        )
+        .unwrap();
+        let stream = MemoryStream::try_new(vec![batch.clone(), batch], schema, None).unwrap();
+
+        let pages = Pages::default();
+        let token = pages.open(Box::pin(stream), 4).await.unwrap();
+        assert_eq!(rows(&pages.next(&token).await.unwrap().unwrap()), 4);
+        assert_eq!(rows(&pages.next(&token).await.unwrap().unwrap()), 4);
+        assert_eq!(rows(&pages.next(&token).await.unwrap().unwrap()), 2);
+        assert!(pages.next(&token).await.unwrap().is_none());
+        assert!(pages.next(&token).await.is_err());
+        assert!(!pages.close(&token).await);
+    }
+}
@@ -0,0 +1,63 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! The panic hook: a console report plus an optional host callback, so crashes can
+//! be sent to an error tracker and the context recreated.
+
+use std::sync::Mutex;
+
+use js_sys::{Function, Object, Reflect};
+use wasm_bindgen::JsValue;
+
+use crate::progress::JsCallback;
+use crate::runtime;
+
+static CALLBACK: Mutex<Option<JsCallback>> = Mutex::new(None);
+
+pub fn install() {
+    // native builds, such as the tests, keep Rust's default hook
+    if !cfg!(target_arch = "wasm32") {
+        return;
+    }
+    // a runtime without `console.error` skips the console report rather than
+    // panicking again while reporting
+    let console = runtime::property(&js_sys::global(), "console");
+    let has_console = !runtime::property(&console, "error").is_undefined();
+    std::panic::set_hook(Box::new(move |info| {
+        if has_console {
+            console_error_panic_hook::hook(info);
+        }
+        // `try_lock`, a panic while the callback is being replaced mustn't deadlock
+        let callback = CALLBACK
+            .try_lock()
+            .ok()
+            .and_then(|callback| callback.clone());
+        if let Some(JsCallback(callback)) = callback {
+            let message = info.to_string();
+            let stack = Reflect::get(&js_sys::Error::new(&message), &"stack".into())
+                .unwrap_or(JsValue::UNDEFINED);
+            let report = Object::new();
+            let _ = Reflect::set(&report, &"message".into(), &message.into());
+            let _ = Reflect::set(&report, &"stack".into(), &stack);
+            let _ = callback.call1(&JsValue::NULL, &report);
+        }
+    }));
+}
+
+/// Call `callback({message, stack})` on panics, `None` to stop.
+pub fn set_callback(callback: Option<Function>) {
+    *CALLBACK.lock().unwrap() = callback.map(JsCallback);
+}
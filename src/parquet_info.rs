@@ -0,0 +1,305 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Parquet file layout inspection without executing a query.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use datafusion::datasource::file_format::parquet::fetch_parquet_metadata;
+use datafusion::execution::context::SessionContext;
+use datafusion::execution::object_store::ObjectStoreUrl;
+use datafusion::logical_expr::{DdlStatement, LogicalPlan};
+use object_store::path::Path;
+use object_store::ObjectMeta;
+use parquet::arrow::arrow_reader::{ArrowReaderMetadata, ArrowReaderOptions};
+use parquet::file::metadata::{ColumnChunkMetaData, ParquetMetaData, RowGroupMetaData};
+use parquet::file::statistics::Statistics;
+use serde::Serialize;
+use tsify_next::Tsify;
+use url::Url;
+
+use crate::error::{Result, WasmError};
+use crate::listing::SchemaField;
+
+const ENCRYPTED_FOOTER_MAGIC: &[u8] = b"PARE";
+
+/// Fetch the footer of the Parquet file at `url` through the session's object stores.
+pub async fn fetch_metadata(
+    ctx: &SessionContext,
+    url: &str,
+) -> Result<(ObjectStoreUrl, ObjectMeta, Arc<ParquetMetaData>)> {
+    let url = Url::parse(url).map_err(|err| WasmError::Other(err.to_string()))?;
+    let object_store_url = ObjectStoreUrl::parse(&url[..url::Position::BeforePath])?;
+    let store = ctx.runtime_env().object_store(&object_store_url)?;
+    let path = Path::from_url_path(url.path()).map_err(|err| WasmError::Other(err.to_string()))?;
+
+    let meta = store
+        .head(&path)
+        .await
+        .map_err(datafusion::error::DataFusionError::from)?;
+    let metadata = match fetch_parquet_metadata(store.as_ref(), &meta, None).await {
+        Ok(metadata) => Arc::new(metadata),
+        Err(err) => return Err(explain_error(ctx, url.as_str(), err.into()).await),
+    };
+    Ok((object_store_url, meta, metadata))
+}
+
+/// The location of a `CREATE EXTERNAL TABLE ... STORED AS PARQUET` plan.
+pub fn external_table_location(plan: &LogicalPlan) -> Option<&str> {
+    match plan {
+        LogicalPlan::Ddl(DdlStatement::CreateExternalTable(create))
+            if create.file_type.eq_ignore_ascii_case("PARQUET") =>
+        {
+            Some(&create.location)
+        }
+        _ => None,
+    }
+}
+
+/// `err` from reading the Parquet file at `location`, or a clear error if the file is
+/// encrypted. Its footer doesn't parse, which is otherwise reported as corrupt.
+pub async fn explain_error(ctx: &SessionContext, location: &str, err: WasmError) -> WasmError {
+    match is_encrypted(ctx, location).await {
+        Some(true) => WasmError::Other(format!(
+            "{location} uses Parquet modular encryption, which this build can't decrypt"
+        )),
+        _ => err,
+    }
+}
+
+/// Whether the file at `location` ends in an encrypted footer, `None` for directories
+/// and files that can't be read.
+async fn is_encrypted(ctx: &SessionContext, location: &str) -> Option<bool> {
+    let url = Url::parse(location).ok()?;
+    if url.path().ends_with('/') {
+        return None;
+    }
+    let object_store_url = ObjectStoreUrl::parse(&url[..url::Position::BeforePath]).ok()?;
+    let store = ctx.runtime_env().object_store(&object_store_url).ok()?;
+    let path = Path::from_url_path(url.path()).ok()?;
+    let meta = store.head(&path).await.ok()?;
+    let tail = meta.size.saturating_sub(4)..meta.size;
+    let magic = store.get_range(&path, tail).await.ok()?;
+    Some(is_encrypted_footer(&magic))
+}
+
+/// Whether the last bytes of a Parquet file are the magic of an encrypted footer.
+/// Files with plaintext footers end in `PAR1` and fail later, on encrypted columns.
+fn is_encrypted_footer(magic: &[u8]) -> bool {
+    magic == ENCRYPTED_FOOTER_MAGIC
+}
+
+#[derive(Debug, Serialize, Tsify)]
+#[tsify(into_wasm_abi, missing_as_null, hashmap_as_object)]
+pub struct ParquetInfo {
+    pub file_size: usize,
+    pub num_rows: i64,
+    pub format_version: i32,
+    pub created_by: Option<String>,
+    /// The Arrow schema the file is read as.
+    pub schema: Vec<SchemaField>,
+    pub key_value_metadata: BTreeMap<String, Option<String>>,
+    pub row_groups: Vec<RowGroupInfo>,
+}
+
+#[derive(Debug, Serialize, Tsify)]
+pub struct RowGroupInfo {
+    pub num_rows: i64,
+    pub uncompressed_size: i64,
+    pub compressed_size: i64,
+    pub columns: Vec<ColumnChunkInfo>,
+}
+
+#[derive(Debug, Serialize, Tsify)]
+#[tsify(missing_as_null)]
+pub struct ColumnChunkInfo {
+    pub path: String,
+    pub physical_type: String,
+    pub compression: String,
+    pub encodings: Vec<String>,
+    pub uncompressed_size: i64,
+    pub compressed_size: i64,
+    pub null_count: Option<u64>,
+    pub distinct_count: Option<u64>,
+    pub min: Option<String>,
+    pub max: Option<String>,
+}
+
+impl ParquetInfo {
+    pub async fn inspect(ctx: &SessionContext, url: &str) -> Result<Self> {
+        let (_, meta, metadata) = fetch_metadata(ctx, url).await?;
+        let reader_metadata =
+            ArrowReaderMetadata::try_new(metadata.clone(), ArrowReaderOptions::default())?;
+        let file_metadata = metadata.file_metadata();
+
+        Ok(Self {
+            file_size: meta.size,
+            num_rows: file_metadata.num_rows(),
+            format_version: file_metadata.version(),
+            created_by: file_metadata.created_by().map(str::to_string),
+            schema: reader_metadata
+                .schema()
+                .fields()
+                .iter()
+                .map(|field| SchemaField::new(field))
+                .collect(),
+            key_value_metadata: file_metadata
+                .key_value_metadata()
+                .into_iter()
+                .flatten()
+                .map(|kv| (kv.key.clone(), kv.value.clone()))
+                .collect(),
+            row_groups: metadata
+                .row_groups()
+                .iter()
+                .map(RowGroupInfo::new)
+                .collect(),
+        })
+    }
+}
+
+impl RowGroupInfo {
+    fn new(row_group: &RowGroupMetaData) -> Self {
+        Self {
+            num_rows: row_group.num_rows(),
+            uncompressed_size: row_group.total_byte_size(),
+            compressed_size: row_group.compressed_size(),
+            columns: row_group
+                .columns()
+                .iter()
+                .map(ColumnChunkInfo::new)
+                .collect(),
+        }
+    }
+}
+
+impl ColumnChunkInfo {
+    fn new(column: &ColumnChunkMetaData) -> Self {
+        let stats = column.statistics();
+        let (min, max) = stats.map(min_max).unwrap_or_default();
+        Self {
+            path: column.column_path().string(),
+            physical_type: column.column_type().to_string(),
+            compression: column.compression().to_string(),
+            encodings: column
+                .encodings()
+                .iter()
+                .map(|encoding| encoding.to_string())
+                .collect(),
+            uncompressed_size: column.uncompressed_size(),
+            compressed_size: column.compressed_size(),
+            null_count: stats.and_then(|stats| stats.null_count_opt()),
+            distinct_count: stats.and_then(|stats| stats.distinct_count_opt()),
+            min,
+            max,
+        }
+    }
+}
+
+/// Display the min / max statistics, byte arrays as UTF-8 when valid and hex otherwise.
+fn min_max(stats: &Statistics) -> (Option<String>, Option<String>) {
+    fn bytes(value: &[u8]) -> String {
+        match std::str::from_utf8(value) {
+            Ok(value) => value.to_string(),
+            Err(_) => value.iter().map(|byte| format!("{byte:02x}")).collect(),
+        }
+    }
+
+    match stats {
+        Statistics::Boolean(s) => (
+            s.min_opt().map(bool::to_string),
+            s.max_opt().map(bool::to_string),
+        ),
+        Statistics::Int32(s) => (
+            s.min_opt().map(i32::to_string),
+            s.max_opt().map(i32::to_string),
+        ),
+        Statistics::Int64(s) => (
+            s.min_opt().map(i64::to_string),
+            s.max_opt().map(i64::to_string),
+        ),
+        Statistics::Int96(s) => (
+            s.min_opt().map(|v| v.to_string()),
+            s.max_opt().map(|v| v.to_string()),
+        ),
+        Statistics::Float(s) => (
+            s.min_opt().map(f32::to_string),
+            s.max_opt().map(f32::to_string),
+        ),
+        Statistics::Double(s) => (
+            s.min_opt().map(f64::to_string),
+            s.max_opt().map(f64::to_string),
+        ),
+        Statistics::ByteArray(s) => (
+            s.min_opt().map(|v| bytes(v.data())),
+            s.max_opt().map(|v| bytes(v.data())),
+        ),
+        Statistics::FixedLenByteArray(s) => (
+            s.min_opt().map(|v| bytes(v.data())),
+            s.max_opt().map(|v| bytes(v.data())),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::data_type::ByteArray;
+
+    #[test]
+    fn test_encrypted_footer() {
+        assert!(is_encrypted_footer(b"PARE"));
+        assert!(!is_encrypted_footer(b"PAR1"));
+    }
+
+    #[tokio::test]
+    async fn test_external_table_location() {
+        let state = SessionContext::new().state();
+        let plan = |sql| state.create_logical_plan(sql);
+        let parquet =
+            plan("CREATE EXTERNAL TABLE t STORED AS PARQUET LOCATION 'file:///t.parquet'")
+                .await
+                .unwrap();
+        assert_eq!(external_table_location(&parquet), Some("file:///t.parquet"));
+        let csv = plan("CREATE EXTERNAL TABLE t STORED AS CSV LOCATION 'file:///t.csv'")
+            .await
+            .unwrap();
+        assert_eq!(external_table_location(&csv), None);
+    }
+
+    #[test]
+    fn test_min_max() {
+        let stats = Statistics::int32(Some(-1), Some(7), None, Some(0), false);
+        assert_eq!(
+            min_max(&stats),
+            (Some("-1".to_string()), Some("7".to_string()))
+        );
+
+        let stats = Statistics::byte_array(
+            Some(ByteArray::from("abc")),
+            Some(ByteArray::from(vec![0xff, 0x00])),
+            None,
+            Some(0),
+            false,
+        );
+        assert_eq!(
+            min_max(&stats),
+            (Some("abc".to_string()), Some("ff00".to_string()))
+        );
+    }
+}
@@ -0,0 +1,91 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Parquet scan options: how much of a file predicates let scans skip.
+
+use datafusion::common::config::ParquetOptions;
+use serde::Deserialize;
+use tsify_next::Tsify;
+
+use crate::error::Result;
+
+/// Reader options given as a JSON object, e.g.
+/// `{"pushdown_filters": true, "reorder_filters": true}`. Absent keys are unchanged.
+#[derive(Debug, Default, Deserialize, Tsify)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParquetReaderOptions {
+    /// Evaluate filters while decoding, so the other columns are only fetched and
+    /// decoded for matching rows (late materialization).
+    pub pushdown_filters: Option<bool>,
+    /// Evaluate the cheapest and most selective pushed down filters first.
+    pub reorder_filters: Option<bool>,
+    /// Skip pages using the page index.
+    pub page_index: Option<bool>,
+    /// Skip row groups using bloom filters.
+    pub bloom_filter: Option<bool>,
+    /// Skip row groups using their statistics.
+    pub pruning: Option<bool>,
+    /// Bytes read from the end of a file to get its metadata in one request.
+    pub metadata_size_hint: Option<usize>,
+}
+
+impl ParquetReaderOptions {
+    pub fn from_json(options: &str) -> Result<Self> {
+        Ok(serde_json::from_str(options)?)
+    }
+
+    pub fn apply(&self, options: &mut ParquetOptions) {
+        if let Some(pushdown_filters) = self.pushdown_filters {
+            options.pushdown_filters = pushdown_filters;
+        }
+        if let Some(reorder_filters) = self.reorder_filters {
+            options.reorder_filters = reorder_filters;
+        }
+        if let Some(page_index) = self.page_index {
+            options.enable_page_index = page_index;
+        }
+        if let Some(bloom_filter) = self.bloom_filter {
+            options.bloom_filter_on_read = bloom_filter;
+        }
+        if let Some(pruning) = self.pruning {
+            options.pruning = pruning;
+        }
+        if let Some(metadata_size_hint) = self.metadata_size_hint {
+            options.metadata_size_hint = Some(metadata_size_hint);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_only_given_options() {
+        let mut options = ParquetOptions {
+            reorder_filters: true,
+            ..Default::default()
+        };
+        ParquetReaderOptions::from_json(r#"{"pushdown_filters": true}"#)
+            .unwrap()
+            .apply(&mut options);
+        assert!(options.pushdown_filters);
+        assert!(options.reorder_filters);
+
+        assert!(ParquetReaderOptions::from_json(r#"{"pushdown": true}"#).is_err());
+    }
+}
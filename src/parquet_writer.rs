@@ -0,0 +1,174 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Parquet writer options, shared by `COPY TO` and `export_parquet`.
+
+use std::str::FromStr;
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::common::config::ParquetOptions;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::{EnabledStatistics, WriterProperties};
+use serde::Deserialize;
+use tsify_next::Tsify;
+
+use crate::error::{Result, WasmError};
+
+/// Writer options given as a JSON object, e.g.
+/// `{"compression": "zstd(3)", "max_row_group_size": 65536, "statistics": "page", "dictionary": true}`.
+#[derive(Debug, Default, Deserialize, Tsify)]
+#[serde(default, deny_unknown_fields)]
+pub struct ParquetWriterOptions {
+    /// `uncompressed`, `snappy`, `gzip(level)`, `brotli(level)`, `lz4`, `lz4_raw` or `zstd(level)`.
+    pub compression: Option<String>,
+    /// Maximum number of rows per row group.
+    pub max_row_group_size: Option<usize>,
+    /// `none`, `chunk` or `page`.
+    pub statistics: Option<String>,
+    pub dictionary: Option<bool>,
+}
+
+impl ParquetWriterOptions {
+    pub fn from_json(options: Option<&str>) -> Result<Self> {
+        match options {
+            Some(options) if !options.trim().is_empty() => Ok(serde_json::from_str(options)?),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn writer_properties(&self) -> Result<WriterProperties> {
+        let mut builder = WriterProperties::builder();
+        if let Some(compression) = self.compression()? {
+            builder = builder.set_compression(compression);
+        }
+        if let Some(max_row_group_size) = self.max_row_group_size {
+            builder = builder.set_max_row_group_size(max_row_group_size);
+        }
+        if let Some(statistics) = self.statistics()? {
+            builder = builder.set_statistics_enabled(statistics);
+        }
+        if let Some(dictionary) = self.dictionary {
+            builder = builder.set_dictionary_enabled(dictionary);
+        }
+        Ok(builder.build())
+    }
+
+    /// Make these options the session defaults used by `COPY ... STORED AS PARQUET`.
+    /// `OPTIONS` of the statement still take precedence.
+    pub fn apply(&self, options: &mut ParquetOptions) -> Result<()> {
+        // validate everything before changing anything
+        self.compression()?;
+        self.statistics()?;
+
+        if let Some(compression) = &self.compression {
+            options.compression = Some(compression.clone());
+        }
+        if let Some(max_row_group_size) = self.max_row_group_size {
+            options.max_row_group_size = max_row_group_size;
+        }
+        if let Some(statistics) = &self.statistics {
+            options.statistics_enabled = Some(statistics.clone());
+        }
+        if let Some(dictionary) = self.dictionary {
+            options.dictionary_enabled = Some(dictionary);
+        }
+        Ok(())
+    }
+
+    fn compression(&self) -> Result<Option<Compression>> {
+        self.compression
+            .as_deref()
+            .map(|name| {
+                Compression::from_str(name).map_err(|err| {
+                    WasmError::Other(format!("invalid Parquet compression {name}: {err}"))
+                })
+            })
+            .transpose()
+    }
+
+    fn statistics(&self) -> Result<Option<EnabledStatistics>> {
+        self.statistics
+            .as_deref()
+            .map(|name| {
+                EnabledStatistics::from_str(name).map_err(|err| {
+                    WasmError::Other(format!("invalid Parquet statistics level {name}: {err}"))
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Encode `record_batches` as a Parquet file in memory.
+pub fn write_parquet(
+    schema: SchemaRef,
+    record_batches: &[RecordBatch],
+    options: &ParquetWriterOptions,
+) -> Result<Vec<u8>> {
+    let mut writer = ArrowWriter::try_new(Vec::new(), schema, Some(options.writer_properties()?))?;
+    for batch in record_batches {
+        writer.write(batch)?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use datafusion::arrow::array::Int32Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_write_parquet() {
+        let options = ParquetWriterOptions::from_json(Some(
+            r#"{"compression": "snappy", "max_row_group_size": 2}"#,
+        ))
+        .unwrap();
+        let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+
+        let data = write_parquet(schema, &[batch], &options).unwrap();
+        let reader = SerializedFileReader::new(Bytes::from(data)).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.num_row_groups(), 2);
+        assert_eq!(
+            metadata.row_group(0).column(0).compression(),
+            Compression::SNAPPY
+        );
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        let options = ParquetWriterOptions::from_json(Some(r#"{"compression": "rar"}"#)).unwrap();
+        assert!(options.writer_properties().is_err());
+
+        let mut parquet_options = ParquetOptions::default();
+        assert!(options.apply(&mut parquet_options).is_err());
+        assert_eq!(
+            parquet_options.compression,
+            ParquetOptions::default().compression
+        );
+    }
+}
@@ -0,0 +1,114 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! `performance.measure` entries for query phases and operators, shown in the
+//! Timings track of the browser's performance profiler.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use datafusion::physical_plan::ExecutionPlan;
+use js_sys::{Function, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::runtime;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Prefix of every entry, to filter them with `performance.getEntriesByType`.
+const PREFIX: &str = "datafusion";
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Measure `name` from `start` to `end`, in nanoseconds since the Unix epoch.
+pub fn measure(name: &str, start: i64, end: i64) {
+    if enabled() {
+        emit(&format!("{PREFIX}:{name}"), start, end, None);
+    }
+}
+
+/// Measure every operator of an executed `plan` by its compute time, from the start
+/// of the execution at `start`. Operators run interleaved, so the entries overlap.
+pub fn operators(plan: &Arc<dyn ExecutionPlan>, start: i64) {
+    if enabled() {
+        operator(plan, start, 0);
+    }
+}
+
+fn operator(plan: &Arc<dyn ExecutionPlan>, start: i64, depth: usize) {
+    if let Some(elapsed) = plan.metrics().and_then(|metrics| metrics.elapsed_compute()) {
+        let detail = format!("{}{}", "  ".repeat(depth), plan.name());
+        emit(
+            &format!("{PREFIX}:operator:{}", plan.name()),
+            start,
+            start + elapsed as i64,
+            Some(&detail),
+        );
+    }
+    for child in plan.children() {
+        operator(child, start, depth + 1);
+    }
+}
+
+fn emit(name: &str, start: i64, end: i64, detail: Option<&str>) {
+    let performance = runtime::property(&js_sys::global(), "performance");
+    let Ok(measure) = runtime::property(&performance, "measure").dyn_into::<Function>() else {
+        return;
+    };
+    let origin = runtime::property(&performance, "timeOrigin")
+        .as_f64()
+        .unwrap_or_default();
+    let options = Object::new();
+    let _ = Reflect::set(
+        &options,
+        &"start".into(),
+        &to_timestamp(start, origin).into(),
+    );
+    let _ = Reflect::set(&options, &"end".into(), &to_timestamp(end, origin).into());
+    if let Some(detail) = detail {
+        let _ = Reflect::set(&options, &"detail".into(), &detail.into());
+    }
+    // profiling is best effort, a missing or failing API is ignored
+    let _ = measure.call2(&performance, &JsValue::from_str(name), &options);
+}
+
+/// Convert nanoseconds since the Unix epoch into a `DOMHighResTimeStamp`, which
+/// counts milliseconds from `origin`.
+fn to_timestamp(nanos: i64, origin: f64) -> f64 {
+    // whole milliseconds first, as nanoseconds since the epoch don't fit an f64 exactly
+    let millis = (nanos / 1_000_000) as f64 - origin;
+    (millis + (nanos % 1_000_000) as f64 / 1e6).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_timestamp() {
+        let origin = 1_700_000_000_000.0;
+        let nanos = 1_700_000_000_250_000_000;
+        assert_eq!(to_timestamp(nanos, origin), 250.0);
+        assert_eq!(to_timestamp(0, origin), 0.0);
+    }
+}
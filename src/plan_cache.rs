@@ -0,0 +1,254 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! LRU cache of optimized logical plans, so re-executed statements skip planning.
+//!
+//! Plans hold the table providers they were planned against, so they are dropped when
+//! one of their tables is replaced. Plans calling functions that aren't immutable,
+//! such as `now()` or `random()`, must not be cached since the optimizer folds them,
+//! see [`is_immutable`].
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use datafusion::common::tree_node::{TreeNode, TreeNodeRecursion};
+use datafusion::logical_expr::{Expr, LogicalPlan, Volatility};
+use datafusion::sql::TableReference;
+use serde::Serialize;
+use tsify_next::Tsify;
+
+use crate::warnings::Warning;
+
+/// Default number of cached plans.
+pub const DEFAULT_PLAN_CACHE_ENTRIES: usize = 64;
+
+#[derive(Debug)]
+struct CachedPlan {
+    plan: LogicalPlan,
+    /// Warnings found while planning, reported again on every hit.
+    warnings: Vec<Warning>,
+    /// Unqualified names of the tables the plan reads.
+    tables: Vec<String>,
+    last_used: u64,
+}
+
+#[derive(Debug)]
+struct CacheState {
+    capacity: usize,
+    tick: u64,
+    plans: HashMap<String, CachedPlan>,
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct PlanCacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+#[derive(Debug)]
+pub struct PlanCache {
+    state: Mutex<CacheState>,
+}
+
+impl Default for PlanCache {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(CacheState {
+                capacity: DEFAULT_PLAN_CACHE_ENTRIES,
+                tick: 0,
+                plans: HashMap::new(),
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+}
+
+impl PlanCache {
+    /// Change how many plans are kept. 0 disables the cache.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.capacity = capacity;
+        state.evict();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.lock().unwrap().capacity > 0
+    }
+
+    pub fn get(&self, key: &str) -> Option<(LogicalPlan, Vec<Warning>)> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        match state.plans.get_mut(key) {
+            Some(cached) => {
+                cached.last_used = tick;
+                let cached = (cached.plan.clone(), cached.warnings.clone());
+                state.hits += 1;
+                Some(cached)
+            }
+            None => {
+                state.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Cache `plan`, reading `tables`.
+    pub fn insert(
+        &self,
+        key: String,
+        plan: &LogicalPlan,
+        warnings: &[Warning],
+        tables: &[TableReference],
+    ) {
+        let mut state = self.state.lock().unwrap();
+        if state.capacity == 0 {
+            return;
+        }
+        state.tick += 1;
+        let cached = CachedPlan {
+            plan: plan.clone(),
+            warnings: warnings.to_vec(),
+            tables: tables
+                .iter()
+                .map(|table| table.table().to_string())
+                .collect(),
+            last_used: state.tick,
+        };
+        state.plans.insert(key, cached);
+        state.evict();
+    }
+
+    /// Drop plans reading table `name`.
+    pub fn invalidate(&self, name: &str) {
+        let name = TableReference::from(name).table().to_string();
+        let mut state = self.state.lock().unwrap();
+        state
+            .plans
+            .retain(|_, cached| !cached.tables.contains(&name));
+    }
+
+    pub fn clear(&self) {
+        self.state.lock().unwrap().plans.clear();
+    }
+
+    pub fn stats(&self) -> PlanCacheStats {
+        let state = self.state.lock().unwrap();
+        PlanCacheStats {
+            entries: state.plans.len(),
+            capacity: state.capacity,
+            hits: state.hits,
+            misses: state.misses,
+        }
+    }
+}
+
+impl CacheState {
+    fn evict(&mut self) {
+        while self.plans.len() > self.capacity {
+            let Some(oldest) = self
+                .plans
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.plans.remove(&oldest);
+        }
+    }
+}
+
+/// Whether every function called by the unoptimized `plan` always returns the same
+/// result, so its optimized plan can be reused.
+pub fn is_immutable(plan: &LogicalPlan) -> bool {
+    let volatile = plan.exists(|node| {
+        let mut volatile = false;
+        node.apply_expressions(|expr| {
+            volatile = expr.exists(|expr| {
+                Ok(matches!(expr, Expr::ScalarFunction(function)
+                    if function.func.signature().volatility != Volatility::Immutable))
+            })?;
+            Ok(if volatile {
+                TreeNodeRecursion::Stop
+            } else {
+                TreeNodeRecursion::Continue
+            })
+        })?;
+        Ok(volatile)
+    });
+    volatile.is_ok_and(|volatile| !volatile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::execution::context::SessionContext;
+
+    async fn plan(sql: &str) -> LogicalPlan {
+        SessionContext::new()
+            .sql(sql)
+            .await
+            .unwrap()
+            .logical_plan()
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn test_is_immutable() {
+        assert!(is_immutable(&plan("SELECT abs(-1) + 1").await));
+        assert!(!is_immutable(&plan("SELECT now()").await));
+        assert!(!is_immutable(
+            &plan("SELECT random() FROM (VALUES (1)) AS t(x)").await
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_plan_cache() {
+        let cache = PlanCache::default();
+        let tables = [TableReference::from("t")];
+        cache.insert(
+            "select 1".to_string(),
+            &plan("SELECT 1").await,
+            &[],
+            &tables,
+        );
+
+        assert!(cache.get("select 1").is_some());
+        assert!(cache.get("select 2").is_none());
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (1, 1, 1));
+
+        cache.invalidate("datafusion.public.t");
+        assert!(cache.get("select 1").is_none());
+
+        cache.set_capacity(0);
+        cache.insert(
+            "select 1".to_string(),
+            &plan("SELECT 1").await,
+            &[],
+            &tables,
+        );
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
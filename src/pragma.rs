@@ -0,0 +1,114 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Query-scoped settings given as hint comments before the SQL, e.g.
+//! `/*+ tz('Europe/Berlin') */ SELECT now()`.
+
+use std::str::FromStr;
+
+use datafusion::arrow::array::timezone::Tz;
+
+use crate::error::{Result, WasmError};
+
+/// Settings overridden for one `execute_sql` call.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Pragmas {
+    /// Replaces `datafusion.execution.time_zone`.
+    pub time_zone: Option<String>,
+}
+
+impl Pragmas {
+    pub fn is_empty(&self) -> bool {
+        self.time_zone.is_none()
+    }
+
+    /// Read the hint comments at the start of `sql`. Unknown hints are ignored, as
+    /// hints meant for other engines may be present.
+    pub fn parse(sql: &str) -> Result<Self> {
+        let mut pragmas = Self::default();
+        let mut rest = sql.trim_start();
+        while let Some(hint) = rest.strip_prefix("/*+") {
+            let (body, after) = hint
+                .split_once("*/")
+                .ok_or_else(|| WasmError::Other("unterminated hint comment".to_string()))?;
+            for (name, argument) in hints(body)? {
+                if name.eq_ignore_ascii_case("tz") {
+                    let time_zone = argument.ok_or_else(|| {
+                        WasmError::Other("tz hint needs a time zone, e.g. tz('UTC')".to_string())
+                    })?;
+                    Tz::from_str(&time_zone)?;
+                    pragmas.time_zone = Some(time_zone);
+                }
+            }
+            rest = after.trim_start();
+        }
+        Ok(pragmas)
+    }
+}
+
+/// `name('argument')` or bare `name` entries of a hint comment.
+fn hints(body: &str) -> Result<Vec<(String, Option<String>)>> {
+    let mut hints = Vec::new();
+    let mut rest = body.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    while !rest.is_empty() {
+        let name_end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if name_end == 0 {
+            return Err(WasmError::Other(format!("malformed hint: {}", rest.trim())));
+        }
+        let (name, after) = rest.split_at(name_end);
+
+        let (argument, after) = match after.trim_start().strip_prefix('(') {
+            Some(arguments) => {
+                let (arguments, after) = arguments
+                    .split_once(')')
+                    .ok_or_else(|| WasmError::Other(format!("unclosed hint {name}(")))?;
+                let argument = arguments.trim();
+                let argument = argument
+                    .strip_prefix('\'')
+                    .and_then(|argument| argument.strip_suffix('\''))
+                    .unwrap_or(argument);
+                (Some(argument.to_string()), after)
+            }
+            None => (None, after),
+        };
+        hints.push((name.to_string(), argument));
+        rest = after.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+    Ok(hints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time_zone() {
+        let pragmas = Pragmas::parse("  /*+ tz('Europe/Berlin') */ SELECT now()").unwrap();
+        assert_eq!(pragmas.time_zone.as_deref(), Some("Europe/Berlin"));
+
+        let pragmas = Pragmas::parse("/*+ parallel(4), tz('+02:00') */ SELECT 1").unwrap();
+        assert_eq!(pragmas.time_zone.as_deref(), Some("+02:00"));
+
+        assert!(Pragmas::parse("SELECT 1 /*+ tz('UTC') */")
+            .unwrap()
+            .is_empty());
+        assert!(Pragmas::parse("/*+ tz('Mars/Olympus') */ SELECT 1").is_err());
+        assert!(Pragmas::parse("/*+ tz('UTC') SELECT 1").is_err());
+    }
+}
@@ -0,0 +1,81 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Cheap previews for the `Table` format: only the first rows and columns are
+//! planned, so limits and projections reach the Parquet scan and most of the file is
+//! never fetched.
+
+use datafusion::dataframe::DataFrame;
+use datafusion::logical_expr::Expr;
+
+use crate::error::Result;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Preview {
+    pub rows: Option<usize>,
+    pub columns: Option<usize>,
+}
+
+impl Preview {
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_none() && self.columns.is_none()
+    }
+
+    /// Keep the first `columns` columns and one row more than `rows` of `data_frame`,
+    /// the extra row telling whether the preview is truncated.
+    pub fn apply(&self, mut data_frame: DataFrame) -> Result<DataFrame> {
+        if let Some(columns) = self.columns {
+            let kept = data_frame
+                .schema()
+                .columns()
+                .into_iter()
+                .take(columns)
+                .map(Expr::Column)
+                .collect::<Vec<_>>();
+            data_frame = data_frame.select(kept)?;
+        }
+        if let Some(rows) = self.rows {
+            data_frame = data_frame.limit(0, Some(rows.saturating_add(1)))?;
+        }
+        Ok(data_frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::execution::context::SessionContext;
+
+    #[tokio::test]
+    async fn test_apply() {
+        let ctx = SessionContext::new();
+        let data_frame = ctx
+            .sql("SELECT * FROM (VALUES (1, 'a', true), (2, 'b', false), (3, 'c', true)) AS t(x, y, z)")
+            .await
+            .unwrap();
+
+        let preview = Preview {
+            rows: Some(1),
+            columns: Some(2),
+        };
+        let batches = preview.apply(data_frame).unwrap().collect().await.unwrap();
+        let rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(rows, 2);
+        assert_eq!(batches[0].num_columns(), 2);
+        assert_eq!(batches[0].schema().field(1).name(), "y");
+    }
+}
@@ -0,0 +1,160 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Checking what a URL supports before registering it as a table.
+
+use reqwest::header::{
+    HeaderMap, HeaderName, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG,
+    LAST_MODIFIED, RANGE,
+};
+use reqwest::StatusCode;
+use serde::Serialize;
+use tsify_next::Tsify;
+
+use crate::unsafe_opendal_store::parse_content_range;
+
+/// What a server supports for a URL, found with a single `Range: bytes=0-0` request.
+#[derive(Debug, Default, Serialize, Tsify)]
+#[tsify(into_wasm_abi, missing_as_null)]
+pub struct ProbeReport {
+    pub url: String,
+    /// The request completed. In browsers a CORS rejection looks like a network
+    /// error, so `false` often means the server doesn't allow this origin.
+    pub reachable: bool,
+    pub status: Option<u16>,
+    /// The server answered the range request with `206 Partial Content`.
+    pub range_support: bool,
+    /// Total size of the object, if the server disclosed it.
+    pub content_length: Option<u64>,
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub error: Option<String>,
+    /// Human readable problems that will make queries slow or fail.
+    pub warnings: Vec<String>,
+}
+
+impl ProbeReport {
+    pub async fn probe(url: &str) -> Self {
+        let response = reqwest::Client::new()
+            .get(url)
+            .header(RANGE, "bytes=0-0")
+            .send()
+            .await;
+
+        match response {
+            // the body is never read, a server ignoring the range isn't downloaded
+            Ok(response) => Self::from_response(url, response.status(), response.headers()),
+            Err(err) => Self {
+                url: url.to_string(),
+                error: Some(err.to_string()),
+                warnings: vec![
+                    "the request failed, check the URL and that the server sends CORS headers \
+                     (Access-Control-Allow-Origin) for this origin"
+                        .to_string(),
+                ],
+                ..Default::default()
+            },
+        }
+    }
+
+    fn from_response(url: &str, status: StatusCode, headers: &HeaderMap) -> Self {
+        let header = |name: HeaderName| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let mut report = Self {
+            url: url.to_string(),
+            reachable: true,
+            status: Some(status.as_u16()),
+            range_support: status == StatusCode::PARTIAL_CONTENT,
+            content_type: header(CONTENT_TYPE),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            ..Default::default()
+        };
+
+        if !status.is_success() {
+            report.error = Some(format!("server answered {status}"));
+            return report;
+        }
+
+        if report.range_support {
+            report.content_length = header(CONTENT_RANGE)
+                .as_deref()
+                .and_then(parse_content_range)
+                .map(|size| size as u64);
+            if report.content_length.is_none() {
+                report.warnings.push(
+                    "the object size is hidden, the server must list Content-Range in \
+                     Access-Control-Expose-Headers"
+                        .to_string(),
+                );
+            }
+        } else {
+            report.content_length = header(CONTENT_LENGTH).and_then(|value| value.parse().ok());
+            let accepts_ranges = header(ACCEPT_RANGES).is_some_and(|value| value == "bytes");
+            report.warnings.push(if accepts_ranges {
+                "the server advertises range support but ignored the Range header, the whole \
+                 file will be downloaded"
+                    .to_string()
+            } else {
+                "this server doesn't support range requests, the whole file will be downloaded"
+                    .to_string()
+            });
+        }
+        if report.etag.is_none() {
+            report.warnings.push(
+                "no ETag is exposed, cached data can't be revalidated and is refetched instead"
+                    .to_string(),
+            );
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_range_support() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_RANGE, HeaderValue::from_static("bytes 0-0/1234"));
+        headers.insert(ETAG, HeaderValue::from_static("\"abc\""));
+        let report = ProbeReport::from_response("u", StatusCode::PARTIAL_CONTENT, &headers);
+
+        assert!(report.range_support);
+        assert_eq!(report.content_length, Some(1234));
+        assert!(report.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_ranges_ignored() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("99"));
+        let report = ProbeReport::from_response("u", StatusCode::OK, &headers);
+
+        assert!(!report.range_support);
+        assert_eq!(report.content_length, Some(99));
+        assert!(report.warnings[0].contains("whole file"));
+    }
+}
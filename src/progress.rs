@@ -0,0 +1,144 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Download and query progress reporting to JavaScript callbacks.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use js_sys::Function;
+use serde::Serialize;
+use tsify_next::Tsify;
+use wasm_bindgen::JsValue;
+
+/// A JS function that can be stored in `Send + Sync` structures.
+///
+/// Same reasoning as `ForceSend` in `unsafe_opendal_store`: the wasm target
+/// is single threaded, so the function is never touched from another thread.
+#[derive(Debug, Clone)]
+pub struct JsCallback(pub Function);
+
+unsafe impl Send for JsCallback {}
+unsafe impl Sync for JsCallback {}
+
+#[derive(Debug, Default)]
+struct ProgressState {
+    callback: Option<JsCallback>,
+    /// Bytes fetched per object since the last [`IoProgress::reset`].
+    fetched: HashMap<String, u64>,
+    /// Bytes fetched since the registry was created.
+    downloaded: u64,
+}
+
+/// Tracks bytes fetched per object and reports them to the host as
+/// `callback(location, fetched_bytes, expected_bytes)`.
+#[derive(Debug, Default, Clone)]
+pub struct IoProgress {
+    state: Arc<Mutex<ProgressState>>,
+}
+
+impl IoProgress {
+    pub fn set_callback(&self, callback: Option<Function>) {
+        let mut state = self.state.lock().unwrap();
+        state.callback = callback.map(JsCallback);
+    }
+
+    /// Forget the counters, called when a new query starts.
+    pub fn reset(&self) {
+        self.state.lock().unwrap().fetched.clear();
+    }
+
+    /// Total bytes fetched from remote objects.
+    pub fn downloaded(&self) -> u64 {
+        self.state.lock().unwrap().downloaded
+    }
+
+    pub fn record(&self, location: &str, bytes: u64, expected: u64) {
+        let (callback, fetched) = {
+            let mut state = self.state.lock().unwrap();
+            state.downloaded += bytes;
+            let Some(callback) = state.callback.clone() else {
+                return;
+            };
+            let fetched = state.fetched.entry(location.to_string()).or_default();
+            *fetched += bytes;
+            (callback, *fetched)
+        };
+
+        // called without holding the lock, the callback may re-enter the engine
+        let _ = callback.0.call3(
+            &JsValue::NULL,
+            &JsValue::from_str(location),
+            &JsValue::from_f64(fetched as f64),
+            &JsValue::from_f64(expected as f64),
+        );
+    }
+}
+
+/// Milestones of a query reported by [`QueryProgress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Tsify)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryStage {
+    /// The SQL was parsed into statements.
+    Parsed,
+    /// A statement was planned.
+    Planned,
+    /// A statement started producing rows.
+    Scanning,
+    /// A statement produced another batch.
+    Batch,
+    /// Every statement ran, or the query failed.
+    Finished,
+}
+
+#[derive(Debug, Clone, Serialize, Tsify)]
+pub struct ProgressEvent {
+    pub query_id: u64,
+    pub stage: QueryStage,
+    /// Index of the statement the event is about.
+    pub statement: usize,
+    pub statements: usize,
+    /// Batches and rows the statement produced so far.
+    pub batches: usize,
+    pub rows: usize,
+}
+
+/// Reports query milestones to the host as `callback(event)`.
+#[derive(Debug, Default, Clone)]
+pub struct QueryProgress {
+    callback: Arc<Mutex<Option<JsCallback>>>,
+}
+
+impl QueryProgress {
+    pub fn set_callback(&self, callback: Option<Function>) {
+        *self.callback.lock().unwrap() = callback.map(JsCallback);
+    }
+
+    pub fn report(&self, event: &ProgressEvent) {
+        let callback = self.callback.lock().unwrap().clone();
+        let Some(callback) = callback else {
+            return;
+        };
+        let Ok(json) = serde_json::to_string(event) else {
+            return;
+        };
+        if let Ok(event) = js_sys::JSON::parse(&json) {
+            // progress is best effort, a failing callback doesn't fail the query
+            let _ = callback.0.call1(&JsValue::NULL, &event);
+        }
+    }
+}
@@ -0,0 +1,276 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Admission of queries in call order, with a configurable number running at once,
+//! so overlapping `execute_sql` calls from JavaScript don't interleave unpredictably.
+//!
+//! `Interactive` queries are admitted before any queued `Background` one, and only
+//! count against each other, so they preempt background work: a running background
+//! query is parked by the [`Scheduler`](crate::scheduling::Scheduler) between batches
+//! while they run.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tokio::sync::watch;
+use tsify_next::Tsify;
+
+use crate::scheduling::QueryPriority;
+
+/// Default number of queries running at once.
+pub const DEFAULT_CONCURRENCY: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Tsify)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryState {
+    Queued,
+    Running,
+}
+
+/// A query waiting for or holding a slot, as listed by [`QueryQueue::queries`].
+#[derive(Debug, Clone, Serialize, Tsify)]
+#[tsify(into_wasm_abi, missing_as_null)]
+pub struct QueuedQuery {
+    pub id: u64,
+    pub sql: String,
+    pub label: Option<String>,
+    #[tsify(type = "\"interactive\" | \"background\"")]
+    pub priority: QueryPriority,
+    pub state: QueryState,
+}
+
+#[derive(Debug)]
+struct Admission {
+    concurrency: usize,
+    running: usize,
+    running_interactive: usize,
+    /// Queued queries in admission order: interactive ones first, then by id.
+    waiting: BTreeSet<(QueryPriority, u64)>,
+}
+
+impl Admission {
+    fn may_run(&self, priority: QueryPriority, id: u64) -> bool {
+        if self.waiting.first() != Some(&(priority, id)) {
+            return false;
+        }
+        match priority {
+            QueryPriority::Interactive => self.running_interactive < self.concurrency,
+            QueryPriority::Background => self.running < self.concurrency,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryQueue {
+    admission: Arc<watch::Sender<Admission>>,
+    next_id: Arc<AtomicU64>,
+    queries: Arc<Mutex<BTreeMap<u64, QueuedQuery>>>,
+}
+
+impl Default for QueryQueue {
+    fn default() -> Self {
+        let admission = Admission {
+            concurrency: DEFAULT_CONCURRENCY,
+            running: 0,
+            running_interactive: 0,
+            waiting: BTreeSet::new(),
+        };
+        Self {
+            admission: Arc::new(watch::channel(admission).0),
+            next_id: Arc::new(AtomicU64::new(0)),
+            queries: Default::default(),
+        }
+    }
+}
+
+/// A slot held by a running query, released when dropped.
+pub struct QueryPermit {
+    queue: QueryQueue,
+    id: u64,
+    priority: QueryPriority,
+}
+
+impl QueryPermit {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Drop for QueryPermit {
+    fn drop(&mut self) {
+        self.queue.queries.lock().unwrap().remove(&self.id);
+        self.queue.admission.send_modify(|admission| {
+            admission.running -= 1;
+            if self.priority == QueryPriority::Interactive {
+                admission.running_interactive -= 1;
+            }
+        });
+    }
+}
+
+/// Gives up the place in the queue of a query dropped before it was admitted.
+struct Ticket<'a> {
+    queue: &'a QueryQueue,
+    id: u64,
+    priority: QueryPriority,
+    admitted: bool,
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        if self.admitted {
+            return;
+        }
+        self.queue.queries.lock().unwrap().remove(&self.id);
+        self.queue.admission.send_modify(|admission| {
+            admission.waiting.remove(&(self.priority, self.id));
+        });
+    }
+}
+
+impl QueryQueue {
+    /// Change how many queries of each priority run at once, at least 1. Queued queries
+    /// are admitted right away if the limit grew.
+    pub fn set_concurrency(&self, concurrency: usize) {
+        self.admission
+            .send_modify(|admission| admission.concurrency = concurrency.max(1));
+    }
+
+    /// Wait until no query queued before this one, or with a higher priority, is
+    /// waiting and a slot is free.
+    pub async fn enter(
+        &self,
+        sql: &str,
+        label: Option<String>,
+        priority: QueryPriority,
+    ) -> QueryPermit {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        self.queries.lock().unwrap().insert(
+            id,
+            QueuedQuery {
+                id,
+                sql: sql.to_string(),
+                label,
+                priority,
+                state: QueryState::Queued,
+            },
+        );
+        self.admission.send_modify(|admission| {
+            admission.waiting.insert((priority, id));
+        });
+        let mut ticket = Ticket {
+            queue: self,
+            id,
+            priority,
+            admitted: false,
+        };
+
+        // the sender lives in `self`, so waiting can't fail
+        let _ = self
+            .admission
+            .subscribe()
+            .wait_for(|admission| admission.may_run(priority, id))
+            .await;
+        self.admission.send_modify(|admission| {
+            admission.waiting.remove(&(priority, id));
+            admission.running += 1;
+            if priority == QueryPriority::Interactive {
+                admission.running_interactive += 1;
+            }
+        });
+        ticket.admitted = true;
+        if let Some(query) = self.queries.lock().unwrap().get_mut(&id) {
+            query.state = QueryState::Running;
+        }
+
+        QueryPermit {
+            queue: self.clone(),
+            id,
+            priority,
+        }
+    }
+
+    /// Queued and running queries, oldest first.
+    pub fn queries(&self) -> Vec<QueuedQuery> {
+        self.queries.lock().unwrap().values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    const INTERACTIVE: QueryPriority = QueryPriority::Interactive;
+    const BACKGROUND: QueryPriority = QueryPriority::Background;
+
+    #[tokio::test]
+    async fn test_admits_in_order() {
+        let queue = QueryQueue::default();
+        let first = queue.enter("SELECT 1", None, INTERACTIVE).await;
+        assert_eq!(first.id(), 1);
+
+        let mut second = Box::pin(queue.enter("SELECT 2", None, INTERACTIVE));
+        assert!((&mut second).now_or_never().is_none());
+        let states: Vec<_> = queue.queries().iter().map(|query| query.state).collect();
+        assert_eq!(states, vec![QueryState::Running, QueryState::Queued]);
+
+        // an abandoned query doesn't block the ones after it
+        let mut third = Box::pin(queue.enter("SELECT 3", Some("x".to_string()), INTERACTIVE));
+        assert!((&mut third).now_or_never().is_none());
+        drop(second);
+        drop(first);
+        let third = third.await;
+        assert_eq!(third.id(), 3);
+        assert_eq!(queue.queries().len(), 1);
+        assert_eq!(queue.queries()[0].label.as_deref(), Some("x"));
+    }
+
+    #[tokio::test]
+    async fn test_interactive_preempts_background() {
+        let queue = QueryQueue::default();
+        let background = queue.enter("SELECT 1", None, BACKGROUND).await;
+
+        // runs next to the background query
+        let interactive = queue.enter("SELECT 2", None, INTERACTIVE).await;
+
+        let mut queued_background = Box::pin(queue.enter("SELECT 3", None, BACKGROUND));
+        assert!((&mut queued_background).now_or_never().is_none());
+        let mut queued_interactive = Box::pin(queue.enter("SELECT 4", None, INTERACTIVE));
+        assert!((&mut queued_interactive).now_or_never().is_none());
+
+        // the later interactive query goes first
+        drop(interactive);
+        assert_eq!(queued_interactive.await.id(), 4);
+        drop(background);
+        assert_eq!(queued_background.await.id(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_concurrency() {
+        let queue = QueryQueue::default();
+        queue.set_concurrency(2);
+        let _first = queue.enter("SELECT 1", None, INTERACTIVE).await;
+        let _second = queue.enter("SELECT 2", None, INTERACTIVE).await;
+        assert!(Box::pin(queue.enter("SELECT 3", None, INTERACTIVE))
+            .as_mut()
+            .now_or_never()
+            .is_none());
+    }
+}
@@ -0,0 +1,134 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Origin storage quota, as reported by `navigator.storage.estimate()`.
+
+use js_sys::{Function, Promise, Reflect};
+use serde::Serialize;
+use tsify_next::Tsify;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::error::{Result, WasmError};
+use crate::runtime::{Runtime, RuntimeApis};
+
+#[derive(Debug, Clone, Copy, Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct StorageEstimate {
+    pub usage: u64,
+    pub quota: u64,
+}
+
+impl StorageEstimate {
+    /// Ask the browser for the current usage and quota of this origin.
+    pub async fn current() -> Result<Self> {
+        let storage = property(&property(&js_sys::global(), "navigator")?, "storage")?;
+        let estimate = property(&storage, "estimate")?
+            .dyn_into::<Function>()
+            .map_err(|_| unavailable())?
+            .call0(&storage)
+            .map_err(js_error)?
+            .dyn_into::<Promise>()
+            .map_err(|_| unavailable())?;
+        let estimate = JsFuture::from(estimate).await.map_err(js_error)?;
+
+        Ok(Self {
+            usage: property(&estimate, "usage")?.as_f64().unwrap_or(0.0) as u64,
+            quota: property(&estimate, "quota")?.as_f64().unwrap_or(0.0) as u64,
+        })
+    }
+
+    pub fn available(&self) -> u64 {
+        self.quota.saturating_sub(self.usage)
+    }
+
+    /// Fail with [`WasmError::QuotaExceeded`] if `bytes` don't fit in the remaining quota.
+    pub fn ensure_available(&self, bytes: u64) -> Result<()> {
+        if bytes > self.available() {
+            return Err(WasmError::QuotaExceeded {
+                requested: bytes,
+                available: self.available(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The space writes are checked against before they store data.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Quota {
+    /// The origin's storage. Runtimes without `navigator.storage`, such as Node.js,
+    /// have no origin quota and accept every write.
+    #[default]
+    Origin,
+    /// A fixed estimate.
+    #[allow(dead_code)]
+    Fixed(StorageEstimate),
+}
+
+impl Quota {
+    /// Fail with [`WasmError::QuotaExceeded`] if `bytes` more don't fit.
+    pub async fn ensure_available(&self, bytes: u64) -> Result<()> {
+        match self {
+            Quota::Origin if !RuntimeApis::detect(Runtime::detect()).storage => Ok(()),
+            Quota::Origin => StorageEstimate::current().await?.ensure_available(bytes),
+            Quota::Fixed(estimate) => estimate.ensure_available(bytes),
+        }
+    }
+}
+
+fn property(target: &JsValue, name: &str) -> Result<JsValue> {
+    if target.is_undefined() || target.is_null() {
+        return Err(unavailable());
+    }
+    Reflect::get(target, &JsValue::from_str(name)).map_err(js_error)
+}
+
+fn unavailable() -> WasmError {
+    WasmError::Other(format!(
+        "navigator.storage.estimate() is not available in {}",
+        Runtime::detect().name()
+    ))
+}
+
+fn js_error(err: JsValue) -> WasmError {
+    WasmError::Other(
+        err.as_string()
+            .unwrap_or_else(|| format!("storage estimate failed: {err:?}")),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ensure_available() {
+        let estimate = StorageEstimate {
+            usage: 700,
+            quota: 1000,
+        };
+        assert!(estimate.ensure_available(300).is_ok());
+        assert!(matches!(
+            estimate.ensure_available(301),
+            Err(WasmError::QuotaExceeded {
+                requested: 301,
+                available: 300
+            })
+        ));
+    }
+}
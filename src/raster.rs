@@ -0,0 +1,125 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Rasterize point query results into a density image.
+
+use datafusion::arrow::array::{Array, AsArray, RecordBatch};
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Float64Type};
+
+use crate::error::{Result, WasmError};
+
+/// Bin the points of `record_batches` into a `width` x `height` grid covering
+/// `extent` (`[xmin, ymin, xmax, ymax]`) and return it as RGBA pixels, ready
+/// for `new ImageData(pixels, width, height)`.
+///
+/// The first two columns are x and y, an optional third column weighs each
+/// point. Densities are scaled linearly to the alpha channel, the densest
+/// cell being fully opaque. Row 0 of the image is the top (`ymax`) edge.
+pub fn rasterize(
+    record_batches: &[RecordBatch],
+    width: usize,
+    height: usize,
+    extent: [f64; 4],
+) -> Result<Vec<u8>> {
+    let [xmin, ymin, xmax, ymax] = extent;
+    if width == 0 || height == 0 || xmax <= xmin || ymax <= ymin {
+        return Err(WasmError::Other(
+            "rasterize needs a non-empty size and extent".to_string(),
+        ));
+    }
+
+    let mut grid = vec![0f64; width * height];
+    for batch in record_batches {
+        if batch.num_columns() < 2 {
+            return Err(WasmError::Other(
+                "rasterize needs x and y columns".to_string(),
+            ));
+        }
+        let xs = cast(batch.column(0), &DataType::Float64)?;
+        let ys = cast(batch.column(1), &DataType::Float64)?;
+        let weights = match batch.num_columns() {
+            2 => None,
+            _ => Some(cast(batch.column(2), &DataType::Float64)?),
+        };
+        let xs = xs.as_primitive::<Float64Type>();
+        let ys = ys.as_primitive::<Float64Type>();
+        let weights = weights.as_ref().map(|w| w.as_primitive::<Float64Type>());
+
+        for row in 0..batch.num_rows() {
+            if xs.is_null(row) || ys.is_null(row) {
+                continue;
+            }
+            let (x, y) = (xs.value(row), ys.value(row));
+            if x < xmin || x > xmax || y < ymin || y > ymax {
+                continue;
+            }
+            let column = (((x - xmin) / (xmax - xmin)) * width as f64) as usize;
+            let row_index = (((ymax - y) / (ymax - ymin)) * height as f64) as usize;
+            let cell = row_index.min(height - 1) * width + column.min(width - 1);
+            grid[cell] += match weights {
+                Some(weights) if weights.is_null(row) => 0.0,
+                Some(weights) => weights.value(row),
+                None => 1.0,
+            };
+        }
+    }
+
+    let max = grid.iter().cloned().fold(0f64, f64::max);
+    let mut pixels = vec![0u8; width * height * 4];
+    if max > 0.0 {
+        for (cell, density) in grid.into_iter().enumerate() {
+            pixels[cell * 4 + 3] = ((density / max) * 255.0).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    Ok(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::Float64Array;
+    use datafusion::arrow::datatypes::{Field, Schema};
+
+    use super::*;
+
+    #[test]
+    fn test_rasterize() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("x", DataType::Float64, false),
+            Field::new("y", DataType::Float64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Float64Array::from(vec![0.1, 0.2, 1.9, 5.0])),
+                Arc::new(Float64Array::from(vec![1.9, 1.8, 0.1, 5.0])),
+            ],
+        )
+        .unwrap();
+
+        let pixels = rasterize(&[batch], 2, 2, [0.0, 0.0, 2.0, 2.0]).unwrap();
+        // top-left holds two points, bottom-right one, the out-of-extent point is dropped
+        assert_eq!(pixels.len(), 16);
+        assert_eq!(pixels[3], 255);
+        assert_eq!(pixels[7], 0);
+        assert_eq!(pixels[11], 0);
+        assert_eq!(pixels[15], 128);
+    }
+}
@@ -0,0 +1,217 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Results as a WHATWG `ReadableStream` of encoded chunks, pulled from the
+//! running plan as the reader consumes them.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use arrow::csv::WriterBuilder;
+use arrow::datatypes::Schema;
+use arrow::ipc::writer::{IpcWriteOptions, StreamWriter};
+use datafusion::arrow::array::RecordBatch;
+use datafusion::execution::SendableRecordBatchStream;
+use futures::StreamExt;
+use js_sys::{Array, Function, Object, Reflect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::error::{Result, WasmError};
+use crate::IpcCompression;
+
+/// Encodes batches into the chunks of a stream.
+pub enum ChunkEncoder {
+    /// An Arrow IPC stream; the first chunk starts with the schema.
+    Ipc(StreamWriter<Vec<u8>>),
+    /// CSV lines, with a header before the first row.
+    Csv { header: bool },
+}
+
+impl ChunkEncoder {
+    pub fn new(format: &str, schema: &Schema, compression: IpcCompression) -> Result<Self> {
+        match format.to_ascii_lowercase().as_str() {
+            "ipc" | "arrow" => {
+                if !IpcCompression::supported().contains(&compression) {
+                    return Err(WasmError::Other(format!(
+                        "{} IPC compression is not enabled in this build",
+                        compression.name()
+                    )));
+                }
+                let options = IpcWriteOptions::default()
+                    .try_with_compression(compression.compression_type())?;
+                Ok(Self::Ipc(StreamWriter::try_new_with_options(
+                    Vec::new(),
+                    schema,
+                    options,
+                )?))
+            }
+            "csv" => Ok(Self::Csv { header: true }),
+            _ => Err(WasmError::Other(format!(
+                "unsupported stream format {format}, expected ipc or csv"
+            ))),
+        }
+    }
+
+    /// The bytes encoding `batch`, plus anything written before it.
+    pub fn encode(&mut self, batch: &RecordBatch) -> Result<Vec<u8>> {
+        match self {
+            Self::Ipc(writer) => {
+                writer.write(batch)?;
+                Ok(std::mem::take(writer.get_mut()))
+            }
+            Self::Csv { header } => {
+                if batch.num_rows() == 0 {
+                    return Ok(Vec::new());
+                }
+                let mut writer = WriterBuilder::new().with_header(*header).build(Vec::new());
+                writer.write(batch)?;
+                *header = false;
+                Ok(writer.into_inner())
+            }
+        }
+    }
+
+    /// The bytes ending the stream.
+    pub fn finish(&mut self) -> Result<Vec<u8>> {
+        match self {
+            Self::Ipc(writer) => {
+                writer.finish()?;
+                Ok(std::mem::take(writer.get_mut()))
+            }
+            Self::Csv { .. } => Ok(Vec::new()),
+        }
+    }
+}
+
+struct StreamState {
+    stream: SendableRecordBatchStream,
+    encoder: ChunkEncoder,
+}
+
+impl StreamState {
+    /// The next non-empty chunk, `None` at the end of the stream.
+    async fn next_chunk(&mut self) -> Result<Option<Vec<u8>>> {
+        while let Some(batch) = self.stream.next().await {
+            let chunk = self.encoder.encode(&batch?)?;
+            if !chunk.is_empty() {
+                return Ok(Some(chunk));
+            }
+        }
+        let chunk = self.encoder.finish()?;
+        Ok((!chunk.is_empty()).then_some(chunk))
+    }
+}
+
+/// A `ReadableStream` of `Uint8Array` chunks of `stream` encoded by `encoder`.
+/// Cancelling the stream drops the plan.
+pub fn readable_stream(
+    stream: SendableRecordBatchStream,
+    encoder: ChunkEncoder,
+) -> Result<JsValue> {
+    let constructor: Function = Reflect::get(&js_sys::global(), &"ReadableStream".into())
+        .ok()
+        .and_then(|constructor| constructor.dyn_into().ok())
+        .ok_or_else(|| WasmError::Other("ReadableStream is not available".to_string()))?;
+    let state = Rc::new(RefCell::new(Some(StreamState { stream, encoder })));
+
+    let pull_state = state.clone();
+    let pull = Closure::<dyn FnMut(JsValue) -> js_sys::Promise>::new(move |controller: JsValue| {
+        let state = pull_state.clone();
+        wasm_bindgen_futures::future_to_promise(async move {
+            // taken out while pulling, the stream never pulls concurrently
+            let Some(mut current) = state.borrow_mut().take() else {
+                return Ok(JsValue::UNDEFINED);
+            };
+            let chunk = current.next_chunk().await.map_err(Into::<JsValue>::into)?;
+            match chunk {
+                Some(chunk) => {
+                    call(
+                        &controller,
+                        "enqueue",
+                        &js_sys::Uint8Array::from(chunk.as_slice()).into(),
+                    )?;
+                    *state.borrow_mut() = Some(current);
+                }
+                None => call(&controller, "close", &JsValue::UNDEFINED)?,
+            }
+            Ok(JsValue::UNDEFINED)
+        })
+    });
+    let cancel = Closure::<dyn FnMut(JsValue)>::new(move |_reason: JsValue| {
+        state.borrow_mut().take();
+    });
+
+    let source = Object::new();
+    let set = |key: &str, value: JsValue| {
+        Reflect::set(&source, &key.into(), &value)
+            .map_err(|_| WasmError::Other("failed to build the stream source".to_string()))
+    };
+    set("pull", pull.into_js_value())?;
+    set("cancel", cancel.into_js_value())?;
+    Reflect::construct(&constructor, &Array::of1(&source))
+        .map_err(|err| WasmError::Other(format!("failed to create ReadableStream: {err:?}")))
+}
+
+fn call(controller: &JsValue, method: &str, arg: &JsValue) -> std::result::Result<(), JsValue> {
+    let method: Function = Reflect::get(controller, &method.into())?.dyn_into()?;
+    method.call1(controller, arg)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field};
+    use arrow::ipc::reader::StreamReader;
+    use std::sync::Arc;
+
+    fn batch(values: Vec<i32>) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, false)]));
+        RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(values))]).unwrap()
+    }
+
+    #[test]
+    fn test_ipc_chunks_concatenate_to_a_stream() {
+        let first = batch(vec![1, 2]);
+        let mut encoder = ChunkEncoder::new("ipc", &first.schema(), IpcCompression::None).unwrap();
+        let mut data = encoder.encode(&first).unwrap();
+        data.extend(encoder.encode(&batch(vec![3])).unwrap());
+        data.extend(encoder.finish().unwrap());
+
+        let reader = StreamReader::try_new(data.as_slice(), None).unwrap();
+        let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+        assert_eq!(rows, 3);
+    }
+
+    #[test]
+    fn test_csv_header_only_once() {
+        let first = batch(vec![1, 2]);
+        let mut encoder = ChunkEncoder::new("csv", &first.schema(), IpcCompression::None).unwrap();
+        assert_eq!(encoder.encode(&first).unwrap(), b"v\n1\n2\n");
+        assert!(encoder.encode(&batch(vec![])).unwrap().is_empty());
+        assert_eq!(encoder.encode(&batch(vec![3])).unwrap(), b"3\n");
+        assert!(encoder.finish().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        let schema = batch(vec![]).schema();
+        assert!(ChunkEncoder::new("xml", &schema, IpcCompression::None).is_err());
+    }
+}
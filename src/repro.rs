@@ -0,0 +1,141 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Reproduction bundles for bug reports.
+//!
+//! A bundle is a single JSON document carrying everything needed to replay a
+//! statement somewhere else: the SQL itself, DDL for every table it touches,
+//! a handful of sampled rows per table and the engine version.
+
+use std::collections::BTreeMap;
+
+use datafusion::arrow::datatypes::{DataType, Schema};
+use datafusion::execution::context::SessionContext;
+use datafusion::sql::parser::DFParser;
+use serde::Serialize;
+use tsify_next::Tsify;
+
+use crate::error::Result;
+use crate::info::EngineInfo;
+use crate::ResultFormat;
+
+/// How many rows are sampled from each referenced table.
+const SAMPLE_ROWS: usize = 20;
+
+#[derive(Debug, Serialize, Tsify)]
+pub struct ReproBundle {
+    pub engine: EngineInfo,
+    pub sql: String,
+    pub ddl: Vec<String>,
+    /// Sampled rows per table, encoded with [`ResultFormat::Json`].
+    pub samples: BTreeMap<String, String>,
+}
+
+impl ReproBundle {
+    pub async fn collect(ctx: &SessionContext, sql: &str) -> Result<Self> {
+        let state = ctx.state();
+        let mut table_refs = Vec::new();
+        for statement in DFParser::parse_sql(sql)? {
+            for table_ref in state.resolve_table_references(&statement)? {
+                if !table_refs.contains(&table_ref) {
+                    table_refs.push(table_ref);
+                }
+            }
+        }
+
+        let mut ddl = Vec::with_capacity(table_refs.len());
+        let mut samples = BTreeMap::new();
+        for table_ref in table_refs {
+            // tables created by the statement itself don't exist yet
+            let Ok(provider) = ctx.table_provider(table_ref.clone()).await else {
+                continue;
+            };
+            let name = table_ref.to_string();
+
+            ddl.push(match provider.get_table_definition() {
+                Some(definition) => definition.to_string(),
+                None => create_table_ddl(&name, &provider.schema()),
+            });
+
+            let batches = ctx
+                .table(table_ref)
+                .await?
+                .limit(0, Some(SAMPLE_ROWS))?
+                .collect()
+                .await?;
+            samples.insert(name, ResultFormat::Json.format_record_batch(&batches)?);
+        }
+
+        Ok(Self {
+            engine: EngineInfo::current(),
+            sql: sql.to_string(),
+            ddl,
+            samples,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Synthesize a `CREATE TABLE` statement for providers that don't carry their
+/// own definition (e.g. in-memory tables).
+fn create_table_ddl(name: &str, schema: &Schema) -> String {
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| {
+            let nullability = if field.is_nullable() { "" } else { " NOT NULL" };
+            format!(
+                "  \"{}\" {}{}",
+                field.name(),
+                sql_type(field.data_type()),
+                nullability
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+
+    format!("CREATE TABLE {name} (\n{columns}\n);")
+}
+
+fn sql_type(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Boolean => "BOOLEAN".to_string(),
+        DataType::Int8 => "TINYINT".to_string(),
+        DataType::Int16 => "SMALLINT".to_string(),
+        DataType::Int32 => "INT".to_string(),
+        DataType::Int64 => "BIGINT".to_string(),
+        DataType::UInt8 => "TINYINT UNSIGNED".to_string(),
+        DataType::UInt16 => "SMALLINT UNSIGNED".to_string(),
+        DataType::UInt32 => "INT UNSIGNED".to_string(),
+        DataType::UInt64 => "BIGINT UNSIGNED".to_string(),
+        DataType::Float32 => "REAL".to_string(),
+        DataType::Float64 => "DOUBLE".to_string(),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "VARCHAR".to_string(),
+        DataType::Binary | DataType::LargeBinary | DataType::BinaryView => "BYTEA".to_string(),
+        DataType::Date32 | DataType::Date64 => "DATE".to_string(),
+        DataType::Timestamp(_, None) => "TIMESTAMP".to_string(),
+        DataType::Timestamp(_, Some(_)) => "TIMESTAMP WITH TIME ZONE".to_string(),
+        DataType::Decimal128(precision, scale) | DataType::Decimal256(precision, scale) => {
+            format!("DECIMAL({precision}, {scale})")
+        }
+        // not expressible in SQL, keep the arrow name so the reader still knows the type
+        other => other.to_string(),
+    }
+}
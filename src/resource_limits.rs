@@ -0,0 +1,110 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Per-query limits that abort runaway queries instead of exhausting the tab.
+
+use datafusion::arrow::array::RecordBatch;
+use serde::Deserialize;
+use tsify_next::Tsify;
+
+use crate::error::{Result, WasmError};
+
+/// Limits given as a JSON object, each unlimited when absent.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Tsify)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResourceLimits {
+    /// Rows produced by all statements of a call, including rows past `max_rows`.
+    pub max_rows: Option<u64>,
+    /// In-memory size of those rows.
+    pub max_bytes: Option<u64>,
+    /// Bytes fetched from object stores, cache hits excluded.
+    pub max_bytes_scanned: Option<u64>,
+}
+
+/// Tracks what one query used against [`ResourceLimits`].
+#[derive(Debug)]
+pub struct ResourceGuard {
+    limits: ResourceLimits,
+    rows: u64,
+    bytes: u64,
+}
+
+impl ResourceGuard {
+    pub fn new(limits: ResourceLimits) -> Self {
+        Self {
+            limits,
+            rows: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Count `batch`, and fail if it or the `bytes_scanned` so far exceed a limit.
+    pub fn check(&mut self, batch: &RecordBatch, bytes_scanned: u64) -> Result<()> {
+        self.rows += batch.num_rows() as u64;
+        self.bytes += batch.get_array_memory_size() as u64;
+        exceeds("rows", self.limits.max_rows, self.rows)?;
+        exceeds("bytes", self.limits.max_bytes, self.bytes)?;
+        exceeds(
+            "bytes_scanned",
+            self.limits.max_bytes_scanned,
+            bytes_scanned,
+        )
+    }
+}
+
+fn exceeds(resource: &'static str, limit: Option<u64>, used: u64) -> Result<()> {
+    match limit {
+        Some(limit) if used > limit => Err(WasmError::ResourceExhausted {
+            resource,
+            limit,
+            used,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::{ArrayRef, Int32Array};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_check() {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "v",
+            Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+        )])
+        .unwrap();
+        let limits: ResourceLimits =
+            serde_json::from_str(r#"{"max_rows": 5, "max_bytes_scanned": 100}"#).unwrap();
+        let mut guard = ResourceGuard::new(limits);
+        guard.check(&batch, 0).unwrap();
+        assert!(matches!(
+            guard.check(&batch, 0),
+            Err(WasmError::ResourceExhausted {
+                resource: "rows",
+                limit: 5,
+                used: 6
+            })
+        ));
+
+        let mut guard = ResourceGuard::new(limits);
+        assert!(guard.check(&batch, 101).is_err());
+        assert!(serde_json::from_str::<ResourceLimits>(r#"{"max_row": 1}"#).is_err());
+    }
+}
@@ -0,0 +1,252 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! In-memory LRU cache of query results, so repeated queries return instantly.
+//!
+//! Results are keyed by the normalized SQL and the version of every table it reads.
+//! Appending to, updating or re-registering a table bumps its version, and any
+//! statement other than a query (DDL, `INSERT`, `SET`, ...) invalidates everything.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::sql::parser::Statement;
+use datafusion::sql::sqlparser::ast;
+use datafusion::sql::TableReference;
+
+use crate::result_format::ResultInfo;
+
+/// Batches of every statement of a query, and the size of the last one.
+#[derive(Debug, Clone)]
+pub struct CachedResult {
+    pub results: Vec<Vec<RecordBatch>>,
+    pub info: ResultInfo,
+}
+
+impl CachedResult {
+    fn size(&self) -> usize {
+        self.results
+            .iter()
+            .flatten()
+            .map(|batch| batch.get_array_memory_size())
+            .sum()
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    result: CachedResult,
+    size: usize,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    capacity: usize,
+    used: usize,
+    tick: u64,
+    /// Bumped when everything is invalidated, part of every key.
+    generation: u64,
+    versions: HashMap<String, u64>,
+    entries: HashMap<String, CacheEntry>,
+}
+
+/// Disabled until given a capacity.
+#[derive(Debug, Default)]
+pub struct ResultCache {
+    state: Mutex<CacheState>,
+}
+
+impl ResultCache {
+    /// Change the capacity in bytes. A capacity of 0 disables caching.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.capacity = capacity;
+        state.evict();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.lock().unwrap().capacity > 0
+    }
+
+    /// The key of `statements` reading `tables`, or `None` if they can't be cached.
+    /// `settings` holds anything else the results depend on, e.g. the row limit.
+    pub fn key(
+        &self,
+        statements: &[Statement],
+        tables: &[TableReference],
+        settings: &str,
+    ) -> Option<String> {
+        if statements.is_empty() || !statements.iter().all(is_query) {
+            return None;
+        }
+
+        let state = self.state.lock().unwrap();
+        let sql = statements
+            .iter()
+            .map(|statement| statement.to_string())
+            .collect::<Vec<_>>()
+            .join("; ");
+        let mut versions = tables
+            .iter()
+            .map(|table| {
+                let name = table_name(table);
+                let version = state.versions.get(&name).copied().unwrap_or_default();
+                format!("{name}@{version}")
+            })
+            .collect::<Vec<_>>();
+        versions.sort();
+        versions.dedup();
+        Some(format!(
+            "{}\n{settings}\n{}\n{sql}",
+            state.generation,
+            versions.join(",")
+        ))
+    }
+
+    pub fn get(&self, key: &str) -> Option<CachedResult> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let entry = state.entries.get_mut(key)?;
+        entry.last_used = tick;
+        Some(entry.result.clone())
+    }
+
+    /// Cache `result` unless it alone exceeds the capacity.
+    pub fn insert(&self, key: String, result: CachedResult) {
+        let mut state = self.state.lock().unwrap();
+        let size = result.size();
+        if size > state.capacity {
+            return;
+        }
+
+        state.tick += 1;
+        let entry = CacheEntry {
+            result,
+            size,
+            last_used: state.tick,
+        };
+        state.used += size;
+        if let Some(replaced) = state.entries.insert(key, entry) {
+            state.used -= replaced.size;
+        }
+        state.evict();
+    }
+
+    /// Forget results that read table `name`.
+    pub fn invalidate(&self, name: &str) {
+        let mut state = self.state.lock().unwrap();
+        let name = table_name(&TableReference::from(name));
+        *state.versions.entry(name).or_default() += 1;
+    }
+
+    /// Forget every result, after a statement that may have changed any table.
+    pub fn invalidate_all(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        state.entries.clear();
+        state.used = 0;
+    }
+}
+
+impl CacheState {
+    fn evict(&mut self) {
+        while self.used > self.capacity {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            let entry = self.entries.remove(&oldest).unwrap();
+            self.used -= entry.size;
+        }
+    }
+}
+
+/// Whether `statement` only reads data.
+pub fn is_query(statement: &Statement) -> bool {
+    matches!(statement, Statement::Statement(statement) if matches!(**statement, ast::Statement::Query(_)))
+}
+
+/// Tables are versioned by unqualified name, so a change to any table of that name
+/// invalidates results reading the others too.
+fn table_name(table: &TableReference) -> String {
+    table.table().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::{ArrayRef, Int32Array};
+    use datafusion::sql::parser::DFParser;
+    use std::sync::Arc;
+
+    fn result() -> CachedResult {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "v",
+            Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+        )])
+        .unwrap();
+        CachedResult {
+            results: vec![vec![batch]],
+            info: ResultInfo::default(),
+        }
+    }
+
+    #[test]
+    fn test_key() {
+        let cache = ResultCache::default();
+        let tables = [TableReference::from("t")];
+        let query: Vec<_> = DFParser::parse_sql("select  *\nFROM t").unwrap().into();
+        let same: Vec<_> = DFParser::parse_sql("SELECT * FROM t").unwrap().into();
+        let key = cache.key(&query, &tables, "").unwrap();
+        assert_eq!(cache.key(&same, &tables, ""), Some(key.clone()));
+        assert_ne!(cache.key(&query, &tables, "max_rows=1"), Some(key.clone()));
+
+        cache.invalidate("datafusion.public.t");
+        assert_ne!(cache.key(&query, &tables, ""), Some(key));
+
+        let insert: Vec<_> = DFParser::parse_sql("INSERT INTO t VALUES (1)")
+            .unwrap()
+            .into();
+        assert!(cache.key(&insert, &tables, "").is_none());
+    }
+
+    #[test]
+    fn test_insert_and_evict() {
+        let cache = ResultCache::default();
+        cache.insert("a".to_string(), result());
+        assert!(cache.get("a").is_none());
+
+        let size = result().size();
+        cache.set_capacity(size * 2);
+        cache.insert("a".to_string(), result());
+        cache.insert("b".to_string(), result());
+        assert!(cache.get("a").is_some());
+        cache.insert("c".to_string(), result());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+
+        cache.invalidate_all();
+        assert!(cache.get("a").is_none());
+    }
+}
@@ -15,46 +15,261 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use crate::error::Result;
+mod columns;
+mod geojson;
+mod html;
+mod ipc;
+mod json;
+mod markdown;
+mod msgpack;
+mod tsv;
+mod vega_lite;
+#[cfg(feature = "xlsx")]
+mod xlsx;
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::error::{Result, WasmError};
+use crate::extension;
+use crate::warnings::Warning;
 use arrow::array::RecordBatch;
-use arrow::util::display::FormatOptions;
+use arrow::util::display::{DurationFormat, FormatOptions};
 use arrow::util::pretty::pretty_format_batches_with_options;
+use serde::{Deserialize, Serialize};
+use tsify_next::Tsify;
 use wasm_bindgen::prelude::wasm_bindgen;
 
+#[cfg(feature = "geo")]
+pub(crate) use geojson::{parse_wkb, parse_wkt};
+pub use html::HtmlOptions;
+pub(crate) use ipc::write_stream as write_ipc_stream;
+pub use ipc::IpcCompression;
+pub use json::JsonOptions;
+pub use tsv::TsvOptions;
+#[cfg(feature = "xlsx")]
+pub(crate) use xlsx::write_batches as write_xlsx;
+
 #[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ResultFormat {
     Table,
     Json,
+    /// Binary, use `execute_sql_bytes`.
+    MessagePack,
+    /// A GeoJSON `FeatureCollection` built from a WKB/WKT geometry column.
+    GeoJson,
+    /// Binary Arrow IPC stream, use `execute_sql_bytes`.
+    ArrowIpc,
+    /// An escaped HTML `<table>` snippet.
+    Html,
+    /// A GitHub-flavored Markdown pipe table.
+    Markdown,
+    /// Column-major JSON, `{"column": [values...]}`, for charting libraries.
+    Columns,
+    /// A Vega-Lite inline dataset with the encoding type of every column.
+    VegaLite,
+    /// Tab-separated values that paste into spreadsheets.
+    Tsv,
+}
+
+/// How the `Json` and `Columns` formats write values JavaScript numbers can't hold exactly.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonNumbers {
+    /// Int64, UInt64 and decimals are JSON numbers, large values lose precision in `JSON.parse`.
+    #[default]
+    Number,
+    /// Int64, UInt64 and decimals are JSON strings.
+    String,
+}
+
+/// Size of the result of a query's last statement.
+#[derive(Debug, Clone, Default, Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct ResultInfo {
+    /// Rows rendered.
+    pub rows: usize,
+    /// Rows produced by the query.
+    pub total_rows: usize,
+    /// Whether rows were dropped to stay within the row limit.
+    pub truncated: bool,
+    /// Non-fatal notices about the query, such as implicit casts.
+    pub warnings: Vec<Warning>,
+}
+
+/// Settings of the built-in formats that aren't part of the format itself.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    pub ipc_compression: IpcCompression,
+    pub display: DisplayOptions,
+    pub json_numbers: JsonNumbers,
+    pub json: JsonOptions,
+    pub html: HtmlOptions,
+    pub tsv: TsvOptions,
+}
+
+/// How values are displayed by the `Table` and `MessagePack` formats, given as a
+/// JSON object. Date and time formats use chrono's `strftime` syntax.
+#[derive(Debug, Clone, Default, Deserialize, Tsify)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayOptions {
+    /// Session time zone (`datafusion.execution.time_zone`), e.g. `+02:00`.
+    pub time_zone: Option<String>,
+    /// Text shown for nulls, empty by default.
+    pub null: Option<String>,
+    pub date_format: Option<String>,
+    pub datetime_format: Option<String>,
+    pub timestamp_format: Option<String>,
+    pub timestamp_tz_format: Option<String>,
+    pub time_format: Option<String>,
+    pub duration_format: Option<DurationStyle>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Tsify)]
+#[serde(rename_all = "lowercase")]
+pub enum DurationStyle {
+    /// `P1DT2H`
+    Iso8601,
+    /// `1 days 2 hours 0 mins 0 secs`
+    Pretty,
+}
+
+impl DisplayOptions {
+    pub fn format_options(&self) -> FormatOptions<'_> {
+        let mut options = FormatOptions::default()
+            .with_date_format(self.date_format.as_deref())
+            .with_datetime_format(self.datetime_format.as_deref())
+            .with_timestamp_format(self.timestamp_format.as_deref())
+            .with_timestamp_tz_format(self.timestamp_tz_format.as_deref())
+            .with_time_format(self.time_format.as_deref());
+        if let Some(null) = &self.null {
+            options = options.with_null(null);
+        }
+        if let Some(duration_format) = self.duration_format {
+            options = options.with_duration_format(match duration_format {
+                DurationStyle::Iso8601 => DurationFormat::ISO8601,
+                DurationStyle::Pretty => DurationFormat::Pretty,
+            });
+        }
+        options
+    }
+}
+
+/// A custom output format, compiled in by downstream crates and selected from
+/// JavaScript by the name it was registered under.
+pub trait ResultRenderer: Send + Sync {
+    fn render(&self, record_batches: &[RecordBatch]) -> Result<Vec<u8>>;
+}
+
+fn renderers() -> &'static RwLock<HashMap<String, Arc<dyn ResultRenderer>>> {
+    static RENDERERS: OnceLock<RwLock<HashMap<String, Arc<dyn ResultRenderer>>>> = OnceLock::new();
+    RENDERERS.get_or_init(Default::default)
+}
+
+/// Register `renderer` under `name`, returning the renderer it replaced, if any.
+pub fn register_result_renderer(
+    name: impl Into<String>,
+    renderer: Arc<dyn ResultRenderer>,
+) -> Option<Arc<dyn ResultRenderer>> {
+    renderers().write().unwrap().insert(name.into(), renderer)
+}
+
+pub fn result_renderer(name: &str) -> Option<Arc<dyn ResultRenderer>> {
+    renderers().read().unwrap().get(name).cloned()
+}
+
+pub fn result_renderer_names() -> Vec<String> {
+    let mut names: Vec<String> = renderers().read().unwrap().keys().cloned().collect();
+    names.sort();
+    names
 }
 
 impl ResultFormat {
     pub fn format_record_batch(&self, record_batches: &[RecordBatch]) -> Result<String> {
+        self.format_with(record_batches, &RenderOptions::default())
+    }
+
+    fn format_with(
+        &self,
+        record_batches: &[RecordBatch],
+        options: &RenderOptions,
+    ) -> Result<String> {
         match self {
             ResultFormat::Table => {
-                let result =
-                    pretty_format_batches_with_options(&record_batches, &FormatOptions::default())?
-                        .to_string();
+                let record_batches = record_batches
+                    .iter()
+                    .map(extension::decode_for_display)
+                    .collect::<Result<Vec<_>>>()?;
+                let result = pretty_format_batches_with_options(
+                    &record_batches,
+                    &options.display.format_options(),
+                )?
+                .to_string();
                 Ok(result)
             }
-            ResultFormat::Json => {
-                let buf = Vec::new();
-                let mut writer = arrow::json::ArrayWriter::new(buf);
-                let record_batch_refs: Vec<&RecordBatch> = record_batches.iter().collect();
-                writer.write_batches(&record_batch_refs)?;
-                writer.finish()?;
-
-                Ok(String::from_utf8(writer.into_inner())?)
+            ResultFormat::Json => json::write_batches(
+                record_batches,
+                options.json_numbers,
+                &options.json,
+                &options.display.format_options(),
+            ),
+            ResultFormat::Columns => columns::write_batches(
+                record_batches,
+                options.json_numbers,
+                &options.display.format_options(),
+            ),
+            ResultFormat::GeoJson => geojson::write_batches(record_batches),
+            ResultFormat::VegaLite => vega_lite::write_batches(
+                record_batches,
+                options.json_numbers,
+                &options.display.format_options(),
+            ),
+            ResultFormat::Markdown => {
+                markdown::write_batches(record_batches, &options.display.format_options())
             }
+            ResultFormat::Html => html::write_batches(
+                record_batches,
+                &options.html,
+                &options.display.format_options(),
+            ),
+            ResultFormat::Tsv => tsv::write_batches(
+                record_batches,
+                &options.tsv,
+                &options.display.format_options(),
+            ),
+            ResultFormat::MessagePack | ResultFormat::ArrowIpc => Err(WasmError::Other(format!(
+                "{self:?} is a binary format, use execute_sql_bytes"
+            ))),
+        }
+    }
+
+    pub fn render_with(
+        &self,
+        record_batches: &[RecordBatch],
+        options: &RenderOptions,
+    ) -> Result<Vec<u8>> {
+        match self {
+            ResultFormat::MessagePack => {
+                msgpack::write_batches(record_batches, &options.display.format_options())
+            }
+            ResultFormat::ArrowIpc => ipc::write_batches(record_batches, options.ipc_compression),
+            _ => Ok(self.format_with(record_batches, options)?.into_bytes()),
         }
     }
 }
 
+impl ResultRenderer for ResultFormat {
+    fn render(&self, record_batches: &[RecordBatch]) -> Result<Vec<u8>> {
+        self.render_with(record_batches, &RenderOptions::default())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use arrow::array::{Int32Array, StringArray};
+    use arrow::array::{Int32Array, Int64Array, StringArray};
     use arrow::datatypes::{DataType, Field, Schema};
-    use std::sync::Arc;
 
     fn create_test_record_batch() -> RecordBatch {
         let schema = Schema::new(vec![
@@ -95,4 +310,71 @@ mod tests {
         assert!(result.contains("Bob"));
         assert!(result.contains("Charlie"));
     }
+
+    #[test]
+    fn test_render_message_pack() {
+        let batch = create_test_record_batch();
+        let result = ResultFormat::MessagePack.render(&[batch]).unwrap();
+
+        // fixarray of 3 rows, each a fixmap of 2 entries
+        assert_eq!(result[0], 0x93);
+        assert_eq!(result[1], 0x82);
+        assert!(result.windows(5).any(|window| window == b"Alice"));
+        assert!(ResultFormat::MessagePack.format_record_batch(&[]).is_err());
+    }
+
+    #[test]
+    fn test_display_options() {
+        let display: DisplayOptions =
+            serde_json::from_str(r#"{"null": "NULL", "duration_format": "pretty"}"#).unwrap();
+        let options = RenderOptions {
+            display,
+            ..Default::default()
+        };
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, true)])),
+            vec![Arc::new(Int32Array::from(vec![None, Some(1)]))],
+        )
+        .unwrap();
+
+        let result = ResultFormat::Table.render_with(&[batch], &options).unwrap();
+        assert!(String::from_utf8(result).unwrap().contains("NULL"));
+        assert!(serde_json::from_str::<DisplayOptions>(r#"{"duration_format": "long"}"#).is_err());
+    }
+
+    #[test]
+    fn test_json_numbers_as_strings() {
+        let batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new("v", DataType::Int64, false)])),
+            vec![Arc::new(Int64Array::from(vec![9007199254740993]))],
+        )
+        .unwrap();
+        let options = RenderOptions {
+            json_numbers: JsonNumbers::String,
+            ..Default::default()
+        };
+
+        let result = ResultFormat::Json.render_with(&[batch], &options).unwrap();
+        assert_eq!(result, br#"[{"v":"9007199254740993"}]"#);
+    }
+
+    struct RowCount;
+
+    impl ResultRenderer for RowCount {
+        fn render(&self, record_batches: &[RecordBatch]) -> Result<Vec<u8>> {
+            let rows: usize = record_batches.iter().map(|batch| batch.num_rows()).sum();
+            Ok(rows.to_string().into_bytes())
+        }
+    }
+
+    #[test]
+    fn test_register_result_renderer() {
+        assert!(register_result_renderer("row_count", Arc::new(RowCount)).is_none());
+        assert!(result_renderer_names().contains(&"row_count".to_string()));
+
+        let batch = create_test_record_batch();
+        let renderer = result_renderer("row_count").unwrap();
+        assert_eq!(renderer.render(&[batch]).unwrap(), b"3");
+        assert!(result_renderer("missing").is_none());
+    }
 }
@@ -0,0 +1,144 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Column-major JSON output, `{"column": [values...]}`, the input shape of most
+//! charting libraries.
+
+use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::datatypes::{DataType, Float16Type, Float32Type, Float64Type};
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+
+use super::json::is_big_number;
+use super::JsonNumbers;
+use crate::error::Result;
+use crate::extension;
+
+pub fn write_batches(
+    record_batches: &[RecordBatch],
+    numbers: JsonNumbers,
+    format_options: &FormatOptions,
+) -> Result<String> {
+    let Some(first) = record_batches.first() else {
+        return Ok("{}".to_string());
+    };
+    let record_batches = record_batches
+        .iter()
+        .map(extension::decode_for_display)
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut json = String::from("{");
+    for (index, field) in first.schema().fields().iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        json.push_str(&serde_json::to_string(field.name())?);
+        json.push_str(":[");
+        let mut first_value = true;
+        for batch in &record_batches {
+            let column = batch.column(index);
+            let formatter = ArrayFormatter::try_new(column.as_ref(), format_options)?;
+            for row in 0..column.len() {
+                if !first_value {
+                    json.push(',');
+                }
+                first_value = false;
+                write_value(&mut json, column.as_ref(), &formatter, row, numbers)?;
+            }
+        }
+        json.push(']');
+    }
+    json.push('}');
+    Ok(json)
+}
+
+fn write_value(
+    json: &mut String,
+    array: &dyn Array,
+    formatter: &ArrayFormatter,
+    row: usize,
+    numbers: JsonNumbers,
+) -> Result<()> {
+    if array.is_null(row) {
+        json.push_str("null");
+        return Ok(());
+    }
+
+    let data_type = array.data_type();
+    let finite = match data_type {
+        DataType::Float16 => array.as_primitive::<Float16Type>().value(row).is_finite(),
+        DataType::Float32 => array.as_primitive::<Float32Type>().value(row).is_finite(),
+        DataType::Float64 => array.as_primitive::<Float64Type>().value(row).is_finite(),
+        _ => true,
+    };
+    let text = formatter.value(row).to_string();
+    match data_type {
+        // NaN and infinities have no JSON representation
+        _ if !finite => json.push_str("null"),
+        _ if numbers == JsonNumbers::String && is_big_number(data_type) => {
+            json.push_str(&serde_json::to_string(&text)?)
+        }
+        DataType::Boolean => json.push_str(&text),
+        _ if data_type.is_numeric() => json.push_str(&text),
+        _ => json.push_str(&serde_json::to_string(&text)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, Float64Array, Int64Array, StringArray};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_columns() {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "x",
+                Arc::new(Float64Array::from(vec![Some(1.5), None, Some(f64::NAN)])) as ArrayRef,
+            ),
+            (
+                "n",
+                Arc::new(Int64Array::from(vec![1, 2, i64::MAX])) as ArrayRef,
+            ),
+            (
+                "label",
+                Arc::new(StringArray::from(vec!["a", "\"b\"", "c"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let batches = [batch.slice(0, 2), batch.slice(2, 1)];
+
+        assert_eq!(
+            write_batches(&batches, JsonNumbers::Number, &FormatOptions::default()).unwrap(),
+            r#"{"x":[1.5,null,null],"n":[1,2,9223372036854775807],"label":["a","\"b\"","c"]}"#
+        );
+        assert_eq!(
+            write_batches(
+                &batches[1..],
+                JsonNumbers::String,
+                &FormatOptions::default()
+            )
+            .unwrap(),
+            r#"{"x":[null],"n":["9223372036854775807"],"label":["c"]}"#
+        );
+        assert_eq!(
+            write_batches(&[], JsonNumbers::Number, &FormatOptions::default()).unwrap(),
+            "{}"
+        );
+    }
+}
@@ -0,0 +1,478 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! GeoJSON `FeatureCollection` output.
+//!
+//! The geometry column is the first one tagged with a `geoarrow.wkb` /
+//! `geoarrow.wkt` extension name, or else the first binary/string column
+//! named `geometry`, `geom`, `wkb` or `wkt`. Every other column becomes a
+//! feature property.
+
+use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::datatypes::{DataType, Field, Fields};
+use serde_json::{json, Map, Value};
+
+use crate::error::{Result, WasmError};
+use crate::extension::EXTENSION_NAME_KEY;
+
+const GEOMETRY_COLUMN_NAMES: [&str; 4] = ["geometry", "geom", "wkb", "wkt"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Wkb,
+    Wkt,
+}
+
+pub fn write_batches(record_batches: &[RecordBatch]) -> Result<String> {
+    let mut features = Vec::new();
+
+    for batch in record_batches {
+        let schema = batch.schema();
+        let (index, encoding) = find_geometry_column(schema.fields())
+            .ok_or_else(|| WasmError::Other("no geometry column found for GeoJSON".to_string()))?;
+
+        let mut attributes = batch.clone();
+        attributes.remove_column(index);
+        let properties = rows_as_objects(&attributes)?;
+        let geometries = batch.column(index);
+
+        for (row, properties) in properties.into_iter().enumerate() {
+            let geometry = if geometries.is_null(row) {
+                Value::Null
+            } else {
+                match encoding {
+                    Encoding::Wkb => parse_wkb(binary_value(geometries.as_ref(), row))?,
+                    Encoding::Wkt => parse_wkt(string_value(geometries.as_ref(), row))?,
+                }
+            };
+            features.push(json!({
+                "type": "Feature",
+                "geometry": geometry,
+                "properties": properties,
+            }));
+        }
+    }
+
+    Ok(serde_json::to_string(&json!({
+        "type": "FeatureCollection",
+        "features": features,
+    }))?)
+}
+
+fn find_geometry_column(fields: &Fields) -> Option<(usize, Encoding)> {
+    let encoding_of = |field: &Field| match field.data_type() {
+        DataType::Binary | DataType::LargeBinary | DataType::BinaryView => Some(Encoding::Wkb),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => Some(Encoding::Wkt),
+        _ => None,
+    };
+
+    let tagged = fields.iter().enumerate().find_map(|(index, field)| {
+        let extension = field.metadata().get(EXTENSION_NAME_KEY)?;
+        let encoding = encoding_of(field)?;
+        match (extension.as_str(), encoding) {
+            ("geoarrow.wkb", Encoding::Wkb) | ("geoarrow.wkt", Encoding::Wkt) => {
+                Some((index, encoding))
+            }
+            _ => None,
+        }
+    });
+
+    tagged.or_else(|| {
+        fields.iter().enumerate().find_map(|(index, field)| {
+            let name = field.name().to_ascii_lowercase();
+            if GEOMETRY_COLUMN_NAMES.contains(&name.as_str()) {
+                Some((index, encoding_of(field)?))
+            } else {
+                None
+            }
+        })
+    })
+}
+
+fn rows_as_objects(batch: &RecordBatch) -> Result<Vec<Map<String, Value>>> {
+    if batch.num_columns() == 0 {
+        return Ok(vec![Map::new(); batch.num_rows()]);
+    }
+
+    let mut writer = arrow::json::ArrayWriter::new(Vec::new());
+    writer.write(batch)?;
+    writer.finish()?;
+    let buf = writer.into_inner();
+    if buf.is_empty() {
+        return Ok(vec![]);
+    }
+    Ok(serde_json::from_slice(&buf)?)
+}
+
+fn binary_value(array: &dyn Array, row: usize) -> &[u8] {
+    match array.data_type() {
+        DataType::LargeBinary => array.as_binary::<i64>().value(row),
+        DataType::BinaryView => array.as_binary_view().value(row),
+        _ => array.as_binary::<i32>().value(row),
+    }
+}
+
+fn string_value(array: &dyn Array, row: usize) -> &str {
+    match array.data_type() {
+        DataType::LargeUtf8 => array.as_string::<i64>().value(row),
+        DataType::Utf8View => array.as_string_view().value(row),
+        _ => array.as_string::<i32>().value(row),
+    }
+}
+
+fn geometry_error(message: impl std::fmt::Display) -> WasmError {
+    WasmError::Other(format!("invalid geometry: {message}"))
+}
+
+/// Parse ISO or extended (PostGIS) WKB into a GeoJSON geometry.
+pub(crate) fn parse_wkb(bytes: &[u8]) -> Result<Value> {
+    let mut reader = WkbReader { bytes, offset: 0 };
+    reader.geometry()
+}
+
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl WkbReader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let chunk = self
+            .bytes
+            .get(self.offset..self.offset + N)
+            .ok_or_else(|| geometry_error("truncated WKB"))?;
+        self.offset += N;
+        Ok(chunk.try_into().unwrap())
+    }
+
+    fn geometry(&mut self) -> Result<Value> {
+        let little_endian = self.take::<1>()?[0] == 1;
+        let read_u32 = |bytes: [u8; 4]| {
+            if little_endian {
+                u32::from_le_bytes(bytes)
+            } else {
+                u32::from_be_bytes(bytes)
+            }
+        };
+
+        let raw_type = read_u32(self.take()?);
+        // EWKB flags
+        let mut has_z = raw_type & 0x8000_0000 != 0;
+        let mut has_m = raw_type & 0x4000_0000 != 0;
+        if raw_type & 0x2000_0000 != 0 {
+            self.take::<4>()?;
+        }
+        // ISO dimension offsets
+        let iso_type = raw_type & 0x0FFF_FFFF;
+        match iso_type / 1000 {
+            1 => has_z = true,
+            2 => has_m = true,
+            3 => (has_z, has_m) = (true, true),
+            _ => {}
+        }
+        let dimensions = 2 + has_z as usize + has_m as usize;
+
+        let coordinate = |reader: &mut Self| -> Result<Value> {
+            let mut values = Vec::with_capacity(dimensions);
+            for _ in 0..dimensions {
+                let bytes = reader.take::<8>()?;
+                values.push(if little_endian {
+                    f64::from_le_bytes(bytes)
+                } else {
+                    f64::from_be_bytes(bytes)
+                });
+            }
+            // GeoJSON positions carry no measure
+            values.truncate(if has_z { 3 } else { 2 });
+            Ok(json!(values))
+        };
+        let count = |reader: &mut Self| -> Result<usize> { Ok(read_u32(reader.take()?) as usize) };
+
+        let (kind, coordinates) = match iso_type % 1000 {
+            1 => {
+                let point = coordinate(self)?;
+                // empty points are encoded as NaN coordinates
+                if point.as_array().unwrap().iter().any(|v| v.is_null()) {
+                    ("Point", json!([]))
+                } else {
+                    ("Point", point)
+                }
+            }
+            2 => {
+                let n = count(self)?;
+                let points = (0..n)
+                    .map(|_| coordinate(self))
+                    .collect::<Result<Vec<_>>>()?;
+                ("LineString", json!(points))
+            }
+            3 => {
+                let rings = count(self)?;
+                let mut polygon = Vec::with_capacity(rings);
+                for _ in 0..rings {
+                    let n = count(self)?;
+                    let ring = (0..n)
+                        .map(|_| coordinate(self))
+                        .collect::<Result<Vec<_>>>()?;
+                    polygon.push(json!(ring));
+                }
+                ("Polygon", json!(polygon))
+            }
+            kind @ 4..=6 => {
+                let n = count(self)?;
+                let mut members = Vec::with_capacity(n);
+                for _ in 0..n {
+                    members.push(self.geometry()?["coordinates"].take());
+                }
+                let name = ["MultiPoint", "MultiLineString", "MultiPolygon"][kind as usize - 4];
+                (name, json!(members))
+            }
+            7 => {
+                let n = count(self)?;
+                let geometries = (0..n)
+                    .map(|_| self.geometry())
+                    .collect::<Result<Vec<_>>>()?;
+                return Ok(json!({"type": "GeometryCollection", "geometries": geometries}));
+            }
+            other => return Err(geometry_error(format!("unsupported WKB type {other}"))),
+        };
+
+        Ok(json!({"type": kind, "coordinates": coordinates}))
+    }
+}
+
+/// Parse WKT (optionally with an EWKT `SRID=...;` prefix) into a GeoJSON geometry.
+pub(crate) fn parse_wkt(text: &str) -> Result<Value> {
+    let text = match text.split_once(';') {
+        Some((srid, rest)) if srid.trim_start().to_ascii_uppercase().starts_with("SRID=") => rest,
+        _ => text,
+    };
+    let tokens = tokenize_wkt(text);
+    let mut parser = WktParser {
+        tokens: &tokens,
+        position: 0,
+    };
+    let geometry = parser.geometry()?;
+    if parser.position != tokens.len() {
+        return Err(geometry_error("trailing characters in WKT"));
+    }
+    Ok(geometry)
+}
+
+fn tokenize_wkt(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        match c {
+            '(' | ')' | ',' => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                tokens.push(c.to_string());
+            }
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+struct WktParser<'a> {
+    tokens: &'a [String],
+    position: usize,
+}
+
+impl WktParser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Result<&str> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .ok_or_else(|| geometry_error("unexpected end of WKT"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<()> {
+        let token = self.next()?;
+        if token != expected {
+            return Err(geometry_error(format!(
+                "expected '{expected}', found '{token}'"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Consume `EMPTY` if it is the next token.
+    fn empty(&mut self) -> bool {
+        if self
+            .peek()
+            .is_some_and(|token| token.eq_ignore_ascii_case("EMPTY"))
+        {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn geometry(&mut self) -> Result<Value> {
+        let kind = self.next()?.to_ascii_uppercase();
+        let mut has_m = false;
+        match self.peek().map(str::to_ascii_uppercase).as_deref() {
+            Some("Z") => self.position += 1,
+            Some("M") => (self.position, has_m) = (self.position + 1, true),
+            Some("ZM") => (self.position, has_m) = (self.position + 1, true),
+            _ => {}
+        }
+
+        if kind == "GEOMETRYCOLLECTION" {
+            let geometries = self.maybe_empty(|p| p.list(|p| p.geometry()))?;
+            return Ok(json!({"type": "GeometryCollection", "geometries": geometries}));
+        }
+
+        let (name, coordinates) = match kind.as_str() {
+            "POINT" => ("Point", self.maybe_empty(|p| p.point_body(has_m))?),
+            "LINESTRING" => ("LineString", self.maybe_empty(|p| p.points(has_m))?),
+            "POLYGON" => (
+                "Polygon",
+                self.maybe_empty(|p| p.list(|p| p.points(has_m)))?,
+            ),
+            "MULTIPOINT" => (
+                "MultiPoint",
+                self.maybe_empty(|p| {
+                    p.list(|p| {
+                        // both `MULTIPOINT ((1 2), (3 4))` and `MULTIPOINT (1 2, 3 4)` are valid
+                        if p.peek() == Some("(") {
+                            p.point_body(has_m)
+                        } else {
+                            p.coordinate(has_m)
+                        }
+                    })
+                })?,
+            ),
+            "MULTILINESTRING" => (
+                "MultiLineString",
+                self.maybe_empty(|p| p.list(|p| p.points(has_m)))?,
+            ),
+            "MULTIPOLYGON" => (
+                "MultiPolygon",
+                self.maybe_empty(|p| p.list(|p| p.list(|p| p.points(has_m))))?,
+            ),
+            other => return Err(geometry_error(format!("unsupported WKT type {other}"))),
+        };
+
+        Ok(json!({"type": name, "coordinates": coordinates}))
+    }
+
+    fn maybe_empty(&mut self, body: impl FnOnce(&mut Self) -> Result<Value>) -> Result<Value> {
+        if self.empty() {
+            Ok(json!([]))
+        } else {
+            body(self)
+        }
+    }
+
+    /// `( item, item, ... )`
+    fn list(&mut self, mut item: impl FnMut(&mut Self) -> Result<Value>) -> Result<Value> {
+        self.expect("(")?;
+        let mut items = vec![item(self)?];
+        while self.peek() == Some(",") {
+            self.position += 1;
+            items.push(item(self)?);
+        }
+        self.expect(")")?;
+        Ok(Value::Array(items))
+    }
+
+    fn points(&mut self, has_m: bool) -> Result<Value> {
+        self.list(|p| p.coordinate(has_m))
+    }
+
+    fn point_body(&mut self, has_m: bool) -> Result<Value> {
+        self.expect("(")?;
+        let point = self.coordinate(has_m)?;
+        self.expect(")")?;
+        Ok(point)
+    }
+
+    fn coordinate(&mut self, has_m: bool) -> Result<Value> {
+        let mut values = Vec::with_capacity(4);
+        while let Some(token) = self.peek() {
+            if token == "," || token == ")" {
+                break;
+            }
+            let value: f64 = token
+                .parse()
+                .map_err(|_| geometry_error(format!("invalid coordinate '{token}'")))?;
+            values.push(value);
+            self.position += 1;
+        }
+        if values.len() < 2 {
+            return Err(geometry_error("coordinate needs at least two values"));
+        }
+        // GeoJSON positions carry no measure
+        if has_m {
+            values.pop();
+        }
+        values.truncate(3);
+        Ok(json!(values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wkb_point() {
+        let mut wkb = vec![1, 1, 0, 0, 0];
+        wkb.extend_from_slice(&1.5f64.to_le_bytes());
+        wkb.extend_from_slice(&(-2.0f64).to_le_bytes());
+
+        assert_eq!(
+            parse_wkb(&wkb).unwrap(),
+            json!({"type": "Point", "coordinates": [1.5, -2.0]})
+        );
+        assert!(parse_wkb(&wkb[..10]).is_err());
+    }
+
+    #[test]
+    fn test_parse_wkt() {
+        assert_eq!(
+            parse_wkt("POLYGON ((0 0, 1 0, 1 1, 0 0))").unwrap(),
+            json!({
+                "type": "Polygon",
+                "coordinates": [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]],
+            })
+        );
+        assert_eq!(
+            parse_wkt("SRID=4326;MULTIPOINT (1 2, 3 4)").unwrap(),
+            json!({"type": "MultiPoint", "coordinates": [[1.0, 2.0], [3.0, 4.0]]})
+        );
+        assert!(parse_wkt("POINT (1)").is_err());
+    }
+}
@@ -0,0 +1,136 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! HTML `<table>` snippet output. Every name and value is escaped, so the
+//! snippet can be inserted with `innerHTML`.
+
+use std::fmt::Write;
+
+use arrow::array::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use serde::Deserialize;
+use tsify_next::Tsify;
+
+use crate::error::Result;
+use crate::extension;
+
+/// CSS classes added to the generated elements, given as a JSON object.
+#[derive(Debug, Clone, Default, Deserialize, Tsify)]
+#[serde(default, deny_unknown_fields)]
+pub struct HtmlOptions {
+    pub table_class: Option<String>,
+    pub header_class: Option<String>,
+    pub row_class: Option<String>,
+    /// Class of cells holding null.
+    pub null_class: Option<String>,
+}
+
+pub fn write_batches(
+    record_batches: &[RecordBatch],
+    options: &HtmlOptions,
+    format_options: &FormatOptions,
+) -> Result<String> {
+    let mut html = String::new();
+    write!(html, "<table{}>", class(&options.table_class)).unwrap();
+
+    if let Some(first) = record_batches.first() {
+        write!(html, "<thead><tr{}>", class(&options.header_class)).unwrap();
+        for field in first.schema().fields() {
+            write!(html, "<th>{}</th>", escape(field.name())).unwrap();
+        }
+        html.push_str("</tr></thead><tbody>");
+
+        for batch in record_batches {
+            let batch = extension::decode_for_display(batch)?;
+            let formatters = batch
+                .columns()
+                .iter()
+                .map(|column| ArrayFormatter::try_new(column.as_ref(), format_options))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            for row in 0..batch.num_rows() {
+                write!(html, "<tr{}>", class(&options.row_class)).unwrap();
+                for (column, formatter) in batch.columns().iter().zip(&formatters) {
+                    if column.is_null(row) {
+                        write!(html, "<td{}>", class(&options.null_class)).unwrap();
+                    } else {
+                        html.push_str("<td>");
+                    }
+                    html.push_str(&escape(&formatter.value(row).to_string()));
+                    html.push_str("</td>");
+                }
+                html.push_str("</tr>");
+            }
+        }
+        html.push_str("</tbody>");
+    }
+
+    html.push_str("</table>");
+    Ok(html)
+}
+
+fn class(class: &Option<String>) -> String {
+    match class {
+        Some(class) => format!(" class=\"{}\"", escape(class)),
+        None => String::new(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, StringArray};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_escaped_table() {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "<b>",
+            Arc::new(StringArray::from(vec![Some("<script>&"), None])) as ArrayRef,
+        )])
+        .unwrap();
+        let options = HtmlOptions {
+            table_class: Some("grid\"".to_string()),
+            null_class: Some("null".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            write_batches(&[batch], &options, &FormatOptions::default()).unwrap(),
+            "<table class=\"grid&quot;\"><thead><tr><th>&lt;b&gt;</th></tr></thead><tbody>\
+             <tr><td>&lt;script&gt;&amp;</td></tr><tr><td class=\"null\"></td></tr></tbody></table>"
+        );
+        assert_eq!(
+            write_batches(&[], &HtmlOptions::default(), &FormatOptions::default()).unwrap(),
+            "<table></table>"
+        );
+    }
+}
@@ -0,0 +1,164 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Arrow IPC stream encoding of record batches, with optional buffer
+//! compression.
+
+use arrow::array::RecordBatch;
+use arrow::datatypes::Schema;
+use arrow::ipc::writer::{IpcWriteOptions, StreamWriter};
+use arrow::ipc::CompressionType;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error::{Result, WasmError};
+
+/// Buffer compression of Arrow IPC output. arrow-js can only decode compressed
+/// buffers when a codec is registered for it.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IpcCompression {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+}
+
+impl IpcCompression {
+    /// Codecs compiled into this build, in order of preference.
+    pub fn supported() -> Vec<IpcCompression> {
+        let mut supported = Vec::new();
+        if cfg!(feature = "ipc-zstd") {
+            supported.push(IpcCompression::Zstd);
+        }
+        if cfg!(feature = "ipc-lz4") {
+            supported.push(IpcCompression::Lz4);
+        }
+        supported.push(IpcCompression::None);
+        supported
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IpcCompression::None => "none",
+            IpcCompression::Lz4 => "lz4",
+            IpcCompression::Zstd => "zstd",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" | "uncompressed" => Some(IpcCompression::None),
+            "lz4" | "lz4_frame" => Some(IpcCompression::Lz4),
+            "zstd" => Some(IpcCompression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// The preferred codec both this build and the reader, which can decode
+    /// `accepted`, support. Falls back to no compression.
+    pub fn negotiate(accepted: &[String]) -> Self {
+        Self::supported()
+            .into_iter()
+            .find(|codec| {
+                accepted
+                    .iter()
+                    .any(|name| Self::from_name(name) == Some(*codec))
+            })
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn compression_type(&self) -> Option<CompressionType> {
+        match self {
+            IpcCompression::None => None,
+            IpcCompression::Lz4 => Some(CompressionType::LZ4_FRAME),
+            IpcCompression::Zstd => Some(CompressionType::ZSTD),
+        }
+    }
+}
+
+pub fn write_batches(
+    record_batches: &[RecordBatch],
+    compression: IpcCompression,
+) -> Result<Vec<u8>> {
+    if !IpcCompression::supported().contains(&compression) {
+        return Err(WasmError::Other(format!(
+            "{} IPC compression is not enabled in this build",
+            compression.name()
+        )));
+    }
+    match record_batches.first() {
+        Some(first) => write_stream(&first.schema(), record_batches, compression),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// A complete IPC stream of `record_batches`: schema, batches and end-of-stream
+/// marker, decodable on its own.
+pub fn write_stream(
+    schema: &Schema,
+    record_batches: &[RecordBatch],
+    compression: IpcCompression,
+) -> Result<Vec<u8>> {
+    let options =
+        IpcWriteOptions::default().try_with_compression(compression.compression_type())?;
+    let mut writer = StreamWriter::try_new_with_options(Vec::new(), schema, options)?;
+    for batch in record_batches {
+        writer.write(batch)?;
+    }
+    writer.finish()?;
+    Ok(writer.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field};
+    use arrow::ipc::reader::StreamReader;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_round_trip() {
+        let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, false)]));
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(Int32Array::from(vec![1, 2, 3]))]).unwrap();
+
+        for compression in IpcCompression::supported() {
+            let bytes = write_batches(std::slice::from_ref(&batch), compression).unwrap();
+            let batches = StreamReader::try_new(bytes.as_slice(), None)
+                .unwrap()
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(batches, vec![batch.clone()]);
+        }
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(IpcCompression::negotiate(&[]), IpcCompression::None);
+        assert_eq!(
+            IpcCompression::negotiate(&["brotli".to_string()]),
+            IpcCompression::None
+        );
+        if cfg!(feature = "ipc-lz4") {
+            assert_eq!(
+                IpcCompression::negotiate(&["LZ4".to_string()]),
+                IpcCompression::Lz4
+            );
+        }
+    }
+}
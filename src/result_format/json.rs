@@ -0,0 +1,295 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! The JSON result format: an array of row objects.
+
+use std::sync::Arc;
+
+use arrow::array::{
+    Array, ArrayRef, AsArray, ListArray, ListBuilder, RecordBatch, StringArray, UInt8Builder,
+};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use base64::Engine;
+use serde::Deserialize;
+use tsify_next::Tsify;
+
+use super::JsonNumbers;
+use crate::error::Result;
+use crate::extension;
+
+/// How the JSON format writes binary and nested values, given as a JSON object.
+#[derive(Debug, Clone, Default, Deserialize, Tsify)]
+#[serde(default, deny_unknown_fields)]
+pub struct JsonOptions {
+    pub binary: BinaryEncoding,
+    pub nested: NestedEncoding,
+    pub map: MapEncoding,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Tsify)]
+#[serde(rename_all = "lowercase")]
+pub enum BinaryEncoding {
+    /// `"cafe"`
+    #[default]
+    Hex,
+    /// `"yv4="`
+    Base64,
+    /// `[202, 254]`
+    Array,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Tsify)]
+#[serde(rename_all = "lowercase")]
+pub enum NestedEncoding {
+    /// Structs as objects, lists as arrays.
+    #[default]
+    Json,
+    /// Struct, list and map values as their display string, e.g. `{a: 1}`.
+    String,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Tsify)]
+#[serde(rename_all = "lowercase")]
+pub enum MapEncoding {
+    /// `{"k": "v"}`, keys are converted to strings.
+    #[default]
+    Object,
+    /// `[{"key": "k", "value": "v"}]`, keeps key types and order.
+    Entries,
+}
+
+pub fn write_batches(
+    record_batches: &[RecordBatch],
+    numbers: JsonNumbers,
+    options: &JsonOptions,
+    format_options: &FormatOptions,
+) -> Result<String> {
+    let record_batches = record_batches
+        .iter()
+        .map(|batch| {
+            let batch = extension::decode_for_display(batch)?;
+            prepare(&batch, numbers, options, format_options)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut writer = arrow::json::ArrayWriter::new(Vec::new());
+    let record_batch_refs: Vec<&RecordBatch> = record_batches.iter().collect();
+    writer.write_batches(&record_batch_refs)?;
+    writer.finish()?;
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+/// Whether values of `data_type` can be outside the range JavaScript numbers hold exactly.
+pub(crate) fn is_big_number(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int64
+            | DataType::UInt64
+            | DataType::Decimal128(_, _)
+            | DataType::Decimal256(_, _)
+    )
+}
+
+/// Convert the top-level columns of `batch` to the representation selected by the options.
+fn prepare(
+    batch: &RecordBatch,
+    numbers: JsonNumbers,
+    options: &JsonOptions,
+    format_options: &FormatOptions,
+) -> Result<RecordBatch> {
+    let schema = batch.schema();
+    let mut fields = Vec::with_capacity(batch.num_columns());
+    let mut columns = Vec::with_capacity(batch.num_columns());
+
+    for (field, column) in schema.fields().iter().zip(batch.columns()) {
+        let data_type = field.data_type();
+        let converted = match data_type {
+            _ if numbers == JsonNumbers::String && is_big_number(data_type) => {
+                Some(cast(column, &DataType::Utf8)?)
+            }
+            DataType::Binary
+            | DataType::LargeBinary
+            | DataType::BinaryView
+            | DataType::FixedSizeBinary(_) => Some(encode_binary(column, options.binary)),
+            DataType::Struct(_)
+            | DataType::List(_)
+            | DataType::LargeList(_)
+            | DataType::FixedSizeList(_, _)
+            | DataType::Map(_, _)
+                if matches!(options.nested, NestedEncoding::String) =>
+            {
+                Some(display_strings(column, format_options)?)
+            }
+            DataType::Map(_, _) if matches!(options.map, MapEncoding::Entries) => {
+                Some(map_entries(column))
+            }
+            _ => None,
+        };
+
+        match converted {
+            Some(converted) => {
+                fields.push(Field::clone(field).with_data_type(converted.data_type().clone()));
+                columns.push(converted);
+            }
+            None => {
+                fields.push(Field::clone(field));
+                columns.push(column.clone());
+            }
+        }
+    }
+
+    Ok(RecordBatch::try_new(
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone())),
+        columns,
+    )?)
+}
+
+fn binary_value(array: &dyn Array, row: usize) -> &[u8] {
+    match array.data_type() {
+        DataType::Binary => array.as_binary::<i32>().value(row),
+        DataType::LargeBinary => array.as_binary::<i64>().value(row),
+        DataType::BinaryView => array.as_binary_view().value(row),
+        _ => array.as_fixed_size_binary().value(row),
+    }
+}
+
+fn encode_binary(array: &ArrayRef, encoding: BinaryEncoding) -> ArrayRef {
+    let rows = 0..array.len();
+    match encoding {
+        BinaryEncoding::Hex => Arc::new(
+            rows.map(|row| {
+                array.is_valid(row).then(|| {
+                    binary_value(array, row)
+                        .iter()
+                        .map(|byte| format!("{byte:02x}"))
+                        .collect::<String>()
+                })
+            })
+            .collect::<StringArray>(),
+        ),
+        BinaryEncoding::Base64 => Arc::new(
+            rows.map(|row| {
+                array.is_valid(row).then(|| {
+                    base64::engine::general_purpose::STANDARD.encode(binary_value(array, row))
+                })
+            })
+            .collect::<StringArray>(),
+        ),
+        BinaryEncoding::Array => {
+            let mut builder = ListBuilder::new(UInt8Builder::new());
+            for row in rows {
+                if array.is_valid(row) {
+                    builder.values().append_slice(binary_value(array, row));
+                    builder.append(true);
+                } else {
+                    builder.append_null();
+                }
+            }
+            Arc::new(builder.finish())
+        }
+    }
+}
+
+fn display_strings(array: &ArrayRef, format_options: &FormatOptions) -> Result<ArrayRef> {
+    let formatter = ArrayFormatter::try_new(array.as_ref(), format_options)?;
+    Ok(Arc::new(
+        (0..array.len())
+            .map(|row| {
+                array
+                    .is_valid(row)
+                    .then(|| formatter.value(row).to_string())
+            })
+            .collect::<StringArray>(),
+    ))
+}
+
+/// A map column as a list of its `{key, value}` entry structs.
+fn map_entries(array: &ArrayRef) -> ArrayRef {
+    let map = array.as_map();
+    let entries = map.entries();
+    Arc::new(ListArray::new(
+        Arc::new(Field::new("item", entries.data_type().clone(), false)),
+        map.offsets().clone(),
+        Arc::new(entries.clone()),
+        map.nulls().cloned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{BinaryArray, Int32Builder, MapBuilder, StringBuilder};
+
+    fn write(batch: RecordBatch, options: &JsonOptions) -> String {
+        write_batches(
+            &[batch],
+            JsonNumbers::Number,
+            options,
+            &FormatOptions::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_binary_encodings() {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "b",
+            Arc::new(BinaryArray::from(vec![Some(&[0xca, 0xfe][..]), None])) as ArrayRef,
+        )])
+        .unwrap();
+
+        let json = |binary| {
+            write(
+                batch.clone(),
+                &JsonOptions {
+                    binary,
+                    ..Default::default()
+                },
+            )
+        };
+        assert_eq!(json(BinaryEncoding::Hex), r#"[{"b":"cafe"},{}]"#);
+        assert_eq!(json(BinaryEncoding::Base64), r#"[{"b":"yv4="},{}]"#);
+        assert_eq!(json(BinaryEncoding::Array), r#"[{"b":[202,254]},{}]"#);
+    }
+
+    #[test]
+    fn test_map_entries() {
+        let mut builder = MapBuilder::new(None, StringBuilder::new(), Int32Builder::new());
+        builder.keys().append_value("a");
+        builder.values().append_value(1);
+        builder.append(true).unwrap();
+        let batch = RecordBatch::try_from_iter(vec![("m", Arc::new(builder.finish()) as ArrayRef)])
+            .unwrap();
+
+        let options = JsonOptions {
+            map: MapEncoding::Entries,
+            ..Default::default()
+        };
+        assert_eq!(
+            write(batch.clone(), &options),
+            r#"[{"m":[{"keys":"a","values":1}]}]"#
+        );
+
+        let options = JsonOptions {
+            nested: NestedEncoding::String,
+            ..Default::default()
+        };
+        assert_eq!(write(batch, &options), r#"[{"m":"{a: 1}"}]"#);
+    }
+}
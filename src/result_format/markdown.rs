@@ -0,0 +1,101 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! GitHub-flavored Markdown pipe table output. Numeric columns are right
+//! aligned.
+
+use arrow::array::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+
+use crate::error::Result;
+use crate::extension;
+
+pub fn write_batches(
+    record_batches: &[RecordBatch],
+    format_options: &FormatOptions,
+) -> Result<String> {
+    let Some(first) = record_batches.first() else {
+        return Ok(String::new());
+    };
+
+    let schema = first.schema();
+    let mut lines = Vec::new();
+    lines.push(row(schema
+        .fields()
+        .iter()
+        .map(|field| escape(field.name()))));
+    lines.push(row(schema.fields().iter().map(|field| {
+        if field.data_type().is_numeric() {
+            "---:".to_string()
+        } else {
+            "---".to_string()
+        }
+    })));
+
+    for batch in record_batches {
+        let batch = extension::decode_for_display(batch)?;
+        let formatters = batch
+            .columns()
+            .iter()
+            .map(|column| ArrayFormatter::try_new(column.as_ref(), format_options))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for index in 0..batch.num_rows() {
+            lines.push(row(formatters
+                .iter()
+                .map(|formatter| escape(&formatter.value(index).to_string()))));
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
+
+fn row(cells: impl Iterator<Item = String>) -> String {
+    let cells: Vec<String> = cells.collect();
+    format!("| {} |", cells.join(" | "))
+}
+
+/// Escape pipes, which end the cell, and line breaks, which end the table.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\n', '\r'], "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, Int32Array, StringArray};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_pipe_table() {
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "note",
+                Arc::new(StringArray::from(vec![Some("a|b"), Some("line\nbreak")])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            write_batches(&[batch], &FormatOptions::default()).unwrap(),
+            "| id | note |\n| ---: | --- |\n| 1 | a\\|b |\n| 2 | line<br>break |"
+        );
+    }
+}
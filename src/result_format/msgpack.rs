@@ -0,0 +1,114 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! MessagePack encoding of record batches: an array of row maps, the same
+//! shape as the JSON format.
+
+use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::datatypes::{
+    DataType, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type,
+    UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+
+use crate::error::{Result, WasmError};
+
+pub fn write_batches(record_batches: &[RecordBatch], options: &FormatOptions) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let num_rows: usize = record_batches.iter().map(|batch| batch.num_rows()).sum();
+    rmp::encode::write_array_len(&mut buf, num_rows as u32).map_err(encode_error)?;
+
+    for batch in record_batches {
+        let schema = batch.schema();
+        for row in 0..batch.num_rows() {
+            rmp::encode::write_map_len(&mut buf, batch.num_columns() as u32)
+                .map_err(encode_error)?;
+            for (field, column) in schema.fields().iter().zip(batch.columns()) {
+                rmp::encode::write_str(&mut buf, field.name()).map_err(encode_error)?;
+                write_value(&mut buf, column.as_ref(), row, options)?;
+            }
+        }
+    }
+
+    Ok(buf)
+}
+
+fn write_value(
+    buf: &mut Vec<u8>,
+    array: &dyn Array,
+    row: usize,
+    options: &FormatOptions,
+) -> Result<()> {
+    if array.is_null(row) {
+        return rmp::encode::write_nil(buf).map_err(encode_error);
+    }
+
+    match array.data_type() {
+        DataType::Boolean => {
+            rmp::encode::write_bool(buf, array.as_boolean().value(row)).map_err(encode_error)
+        }
+        DataType::Int8 => write_sint(buf, array.as_primitive::<Int8Type>().value(row) as i64),
+        DataType::Int16 => write_sint(buf, array.as_primitive::<Int16Type>().value(row) as i64),
+        DataType::Int32 => write_sint(buf, array.as_primitive::<Int32Type>().value(row) as i64),
+        DataType::Int64 => write_sint(buf, array.as_primitive::<Int64Type>().value(row)),
+        DataType::UInt8 => write_uint(buf, array.as_primitive::<UInt8Type>().value(row) as u64),
+        DataType::UInt16 => write_uint(buf, array.as_primitive::<UInt16Type>().value(row) as u64),
+        DataType::UInt32 => write_uint(buf, array.as_primitive::<UInt32Type>().value(row) as u64),
+        DataType::UInt64 => write_uint(buf, array.as_primitive::<UInt64Type>().value(row)),
+        DataType::Float32 => {
+            rmp::encode::write_f32(buf, array.as_primitive::<Float32Type>().value(row))
+                .map_err(encode_error)
+        }
+        DataType::Float64 => {
+            rmp::encode::write_f64(buf, array.as_primitive::<Float64Type>().value(row))
+                .map_err(encode_error)
+        }
+        DataType::Utf8 => write_str(buf, array.as_string::<i32>().value(row)),
+        DataType::LargeUtf8 => write_str(buf, array.as_string::<i64>().value(row)),
+        DataType::Utf8View => write_str(buf, array.as_string_view().value(row)),
+        DataType::Binary => write_bin(buf, array.as_binary::<i32>().value(row)),
+        DataType::LargeBinary => write_bin(buf, array.as_binary::<i64>().value(row)),
+        DataType::BinaryView => write_bin(buf, array.as_binary_view().value(row)),
+        // temporal, decimal and nested values use their display representation
+        _ => {
+            let formatter = ArrayFormatter::try_new(array, options)?;
+            write_str(buf, &formatter.value(row).to_string())
+        }
+    }
+}
+
+fn write_sint(buf: &mut Vec<u8>, value: i64) -> Result<()> {
+    rmp::encode::write_sint(buf, value).map_err(encode_error)?;
+    Ok(())
+}
+
+fn write_uint(buf: &mut Vec<u8>, value: u64) -> Result<()> {
+    rmp::encode::write_uint(buf, value).map_err(encode_error)?;
+    Ok(())
+}
+
+fn write_str(buf: &mut Vec<u8>, value: &str) -> Result<()> {
+    rmp::encode::write_str(buf, value).map_err(encode_error)
+}
+
+fn write_bin(buf: &mut Vec<u8>, value: &[u8]) -> Result<()> {
+    rmp::encode::write_bin(buf, value).map_err(encode_error)
+}
+
+fn encode_error(err: impl std::fmt::Display) -> WasmError {
+    WasmError::Other(format!("failed to encode MessagePack: {err}"))
+}
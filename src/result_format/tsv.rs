@@ -0,0 +1,126 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Tab-separated output that pastes into spreadsheets. Values holding tabs,
+//! line breaks or quotes are quoted the way Excel and Google Sheets read them.
+
+use arrow::array::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use serde::Deserialize;
+use tsify_next::Tsify;
+
+use crate::error::Result;
+use crate::extension;
+
+/// Settings of `Tsv` results, given as a JSON object.
+#[derive(Debug, Clone, Deserialize, Tsify)]
+#[serde(default, deny_unknown_fields)]
+pub struct TsvOptions {
+    /// Whether the first line holds the column names.
+    pub header: bool,
+}
+
+impl Default for TsvOptions {
+    fn default() -> Self {
+        Self { header: true }
+    }
+}
+
+pub fn write_batches(
+    record_batches: &[RecordBatch],
+    options: &TsvOptions,
+    format_options: &FormatOptions,
+) -> Result<String> {
+    let Some(first) = record_batches.first() else {
+        return Ok(String::new());
+    };
+
+    let mut lines = Vec::new();
+    if options.header {
+        lines.push(row(first
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| escape(field.name()))));
+    }
+    for batch in record_batches {
+        let batch = extension::decode_for_display(batch)?;
+        let formatters = batch
+            .columns()
+            .iter()
+            .map(|column| ArrayFormatter::try_new(column.as_ref(), format_options))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for index in 0..batch.num_rows() {
+            lines.push(row(formatters
+                .iter()
+                .map(|formatter| escape(&formatter.value(index).to_string()))));
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
+
+fn row(cells: impl Iterator<Item = String>) -> String {
+    cells.collect::<Vec<_>>().join("\t")
+}
+
+/// Quote values that would otherwise split the cell, doubling quotes inside.
+fn escape(text: &str) -> String {
+    if text.contains(['\t', '\n', '\r', '"']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, Int32Array, StringArray};
+    use std::sync::Arc;
+
+    fn batch() -> RecordBatch {
+        RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "note",
+                Arc::new(StringArray::from(vec![Some("a\tb"), Some("say \"hi\"")])) as ArrayRef,
+            ),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(
+            write_batches(
+                &[batch()],
+                &TsvOptions::default(),
+                &FormatOptions::default()
+            )
+            .unwrap(),
+            "id\tnote\n1\t\"a\tb\"\n2\t\"say \"\"hi\"\"\""
+        );
+    }
+
+    #[test]
+    fn test_without_header() {
+        let options = TsvOptions { header: false };
+        let tsv = write_batches(&[batch()], &options, &FormatOptions::default()).unwrap();
+        assert!(tsv.starts_with("1\t"));
+    }
+}
@@ -0,0 +1,92 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Vega-Lite inline dataset output: `{"data": {"values": [...]}, "fields": {...}}`
+//! where `fields` maps each column to its encoding type, ready to be merged
+//! into a chart specification.
+
+use arrow::array::RecordBatch;
+use arrow::datatypes::DataType;
+use arrow::util::display::FormatOptions;
+
+use super::json::{self, JsonOptions};
+use super::JsonNumbers;
+use crate::error::Result;
+
+pub fn write_batches(
+    record_batches: &[RecordBatch],
+    numbers: JsonNumbers,
+    format_options: &FormatOptions,
+) -> Result<String> {
+    let values = json::write_batches(
+        record_batches,
+        numbers,
+        &JsonOptions::default(),
+        format_options,
+    )?;
+    // written by hand to keep the column order
+    let mut fields = Vec::new();
+    if let Some(first) = record_batches.first() {
+        for field in first.schema().fields() {
+            fields.push(format!(
+                "{}:\"{}\"",
+                serde_json::to_string(field.name())?,
+                encoding_type(field.data_type())
+            ));
+        }
+    }
+
+    Ok(format!(
+        r#"{{"data":{{"values":{values}}},"fields":{{{}}}}}"#,
+        fields.join(",")
+    ))
+}
+
+/// The Vega-Lite measurement type of a column.
+fn encoding_type(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Dictionary(_, value_type) => encoding_type(value_type),
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, _) => "temporal",
+        data_type if data_type.is_numeric() => "quantitative",
+        _ => "nominal",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, Date32Array, Float64Array, StringArray};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_dataset() {
+        let batch = RecordBatch::try_from_iter(vec![
+            ("day", Arc::new(Date32Array::from(vec![19000])) as ArrayRef),
+            ("sales", Arc::new(Float64Array::from(vec![1.5])) as ArrayRef),
+            (
+                "region",
+                Arc::new(StringArray::from(vec!["eu"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            write_batches(&[batch], JsonNumbers::Number, &FormatOptions::default()).unwrap(),
+            r#"{"data":{"values":[{"day":"2022-01-08","sales":1.5,"region":"eu"}]},"fields":{"day":"temporal","sales":"quantitative","region":"nominal"}}"#
+        );
+    }
+}
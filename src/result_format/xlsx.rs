@@ -0,0 +1,129 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Excel workbook output: one worksheet with a bold header row. Numeric and
+//! boolean columns are written as Excel numbers and booleans, everything else
+//! as display text.
+
+use arrow::array::{Array, AsArray, RecordBatch};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Float64Type};
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+
+use crate::error::{Result, WasmError};
+use crate::extension;
+
+/// Rows of an Excel worksheet, including the header.
+const MAX_ROWS: usize = 1_048_576;
+const MAX_COLUMNS: usize = 16_384;
+
+pub fn write_batches(
+    record_batches: &[RecordBatch],
+    format_options: &FormatOptions,
+) -> Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    let Some(first) = record_batches.first() else {
+        return workbook.save_to_buffer().map_err(xlsx_error);
+    };
+
+    let schema = first.schema();
+    let rows: usize = record_batches.iter().map(|batch| batch.num_rows()).sum();
+    if rows + 1 > MAX_ROWS || schema.fields().len() > MAX_COLUMNS {
+        return Err(WasmError::Other(format!(
+            "{rows} rows and {} columns don't fit in an Excel worksheet",
+            schema.fields().len()
+        )));
+    }
+
+    let bold = Format::new().set_bold();
+    for (column, field) in schema.fields().iter().enumerate() {
+        worksheet
+            .write_string_with_format(0, column as u16, field.name(), &bold)
+            .map_err(xlsx_error)?;
+    }
+
+    let mut row = 1;
+    for batch in record_batches {
+        let batch = extension::decode_for_display(batch)?;
+        for (column, array) in batch.columns().iter().enumerate() {
+            let column = column as u16;
+            let data_type = array.data_type();
+            if data_type.is_numeric() {
+                let numbers = cast(array, &DataType::Float64)?;
+                let numbers = numbers.as_primitive::<Float64Type>();
+                for index in (0..numbers.len()).filter(|index| numbers.is_valid(*index)) {
+                    worksheet
+                        .write_number(row + index as u32, column, numbers.value(index))
+                        .map_err(xlsx_error)?;
+                }
+            } else if data_type == &DataType::Boolean {
+                let booleans = array.as_boolean();
+                for index in (0..booleans.len()).filter(|index| booleans.is_valid(*index)) {
+                    worksheet
+                        .write_boolean(row + index as u32, column, booleans.value(index))
+                        .map_err(xlsx_error)?;
+                }
+            } else {
+                let formatter = ArrayFormatter::try_new(array.as_ref(), format_options)?;
+                for index in (0..array.len()).filter(|index| array.is_valid(*index)) {
+                    worksheet
+                        .write_string(
+                            row + index as u32,
+                            column,
+                            formatter.value(index).to_string(),
+                        )
+                        .map_err(xlsx_error)?;
+                }
+            }
+        }
+        row += batch.num_rows() as u32;
+    }
+
+    workbook.save_to_buffer().map_err(xlsx_error)
+}
+
+fn xlsx_error(err: XlsxError) -> WasmError {
+    WasmError::Other(format!("failed to write the workbook: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{ArrayRef, BooleanArray, Int32Array, StringArray};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_workbook_is_a_zip() {
+        let batch = RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![Some("a"), None])) as ArrayRef,
+            ),
+            (
+                "ok",
+                Arc::new(BooleanArray::from(vec![true, false])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let data = write_batches(&[batch], &FormatOptions::default()).unwrap();
+        assert!(data.starts_with(b"PK"));
+    }
+}
@@ -0,0 +1,251 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Stable `_row_id` columns on in-memory tables, and editing rows by id.
+
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, ArrayRef, AsArray, BooleanArray, RecordBatch, UInt64Array};
+use datafusion::arrow::compute::{concat, filter_record_batch};
+use datafusion::arrow::datatypes::{DataType, Field, Schema, UInt64Type};
+use datafusion::datasource::MemTable;
+use datafusion::execution::context::SessionContext;
+use serde_json::{Map, Value};
+
+use crate::error::{Result, WasmError};
+
+pub const ROW_ID_COLUMN: &str = "_row_id";
+
+pub fn has_row_ids(schema: &Schema) -> bool {
+    schema.column_with_name(ROW_ID_COLUMN).is_some()
+}
+
+/// The id the next appended row gets: one past the largest id in `batches`.
+pub fn next_id(batches: &[RecordBatch]) -> Result<u64> {
+    let mut next = 0;
+    for batch in batches {
+        let ids = row_ids(batch)?;
+        if let Some(max) = datafusion::arrow::compute::max(ids) {
+            next = next.max(max + 1);
+        }
+    }
+    Ok(next)
+}
+
+/// Prepend a `_row_id` column numbered from `next_id` to `batches`.
+pub fn assign(batches: Vec<RecordBatch>, mut next_id: u64) -> Result<Vec<RecordBatch>> {
+    batches
+        .into_iter()
+        .map(|batch| {
+            if has_row_ids(&batch.schema()) {
+                return Err(WasmError::Other(format!(
+                    "{ROW_ID_COLUMN} is assigned by the engine and can't be appended"
+                )));
+            }
+            let ids: UInt64Array = (next_id..next_id + batch.num_rows() as u64).collect();
+            next_id += batch.num_rows() as u64;
+
+            let schema = batch.schema();
+            let mut fields = vec![Arc::new(Field::new(ROW_ID_COLUMN, DataType::UInt64, false))];
+            fields.extend(schema.fields().iter().cloned());
+            let mut columns: Vec<ArrayRef> = vec![Arc::new(ids)];
+            columns.extend(batch.columns().iter().cloned());
+            Ok(RecordBatch::try_new(
+                Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone())),
+                columns,
+            )?)
+        })
+        .collect()
+}
+
+/// Set the columns named in `values`, a JSON object, of the row with id `id`.
+pub async fn update(
+    ctx: &SessionContext,
+    name: &str,
+    id: u64,
+    values: &Map<String, Value>,
+) -> Result<()> {
+    let (schema, batches) = table_batches(ctx, name).await?;
+    if values.contains_key(ROW_ID_COLUMN) {
+        return Err(WasmError::Other(format!(
+            "{ROW_ID_COLUMN} can't be updated"
+        )));
+    }
+    let replacement = parse_row(&schema, values)?;
+
+    let mut found = false;
+    let mut updated = Vec::with_capacity(batches.len());
+    for batch in batches {
+        let position = row_ids(&batch)?
+            .iter()
+            .position(|row_id| row_id == Some(id));
+        let Some(row) = position else {
+            updated.push(batch);
+            continue;
+        };
+        found = true;
+
+        let columns = schema
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .map(
+                |(field, column)| match replacement.column_by_name(field.name()) {
+                    Some(value) => Ok(concat(&[
+                        &column.slice(0, row),
+                        value.as_ref(),
+                        &column.slice(row + 1, batch.num_rows() - row - 1),
+                    ])?),
+                    None => Ok(column.clone()),
+                },
+            )
+            .collect::<Result<Vec<_>>>()?;
+        updated.push(RecordBatch::try_new(schema.clone(), columns)?);
+    }
+
+    if !found {
+        return Err(WasmError::Other(format!(
+            "{name} has no row with {ROW_ID_COLUMN} {id}"
+        )));
+    }
+    replace(ctx, name, schema, updated)
+}
+
+/// Remove the rows with the given ids, returning how many were removed.
+pub async fn delete(ctx: &SessionContext, name: &str, ids: &[u64]) -> Result<usize> {
+    let (schema, batches) = table_batches(ctx, name).await?;
+    let ids: HashSet<u64> = ids.iter().copied().collect();
+
+    let mut removed = 0;
+    let mut kept = Vec::with_capacity(batches.len());
+    for batch in batches {
+        let keep: BooleanArray = row_ids(&batch)?
+            .iter()
+            .map(|row_id| Some(!row_id.is_some_and(|row_id| ids.contains(&row_id))))
+            .collect();
+        removed += keep.false_count();
+        kept.push(filter_record_batch(&batch, &keep)?);
+    }
+
+    replace(ctx, name, schema, kept)?;
+    Ok(removed)
+}
+
+async fn table_batches(
+    ctx: &SessionContext,
+    name: &str,
+) -> Result<(Arc<Schema>, Vec<RecordBatch>)> {
+    let table = ctx.table(name).await?;
+    let schema = Arc::new(table.schema().as_arrow().clone());
+    if !has_row_ids(&schema) {
+        return Err(WasmError::Other(format!(
+            "{name} has no {ROW_ID_COLUMN} column, enable row ids before creating it"
+        )));
+    }
+    Ok((schema, table.collect().await?))
+}
+
+fn replace(
+    ctx: &SessionContext,
+    name: &str,
+    schema: Arc<Schema>,
+    batches: Vec<RecordBatch>,
+) -> Result<()> {
+    let table = MemTable::try_new(schema, vec![batches])?;
+    ctx.deregister_table(name)?;
+    ctx.register_table(name, Arc::new(table))?;
+    Ok(())
+}
+
+fn row_ids(batch: &RecordBatch) -> Result<&UInt64Array> {
+    batch
+        .column_by_name(ROW_ID_COLUMN)
+        .and_then(|column| column.as_primitive_opt::<UInt64Type>())
+        .ok_or_else(|| WasmError::Other(format!("{ROW_ID_COLUMN} must be a UInt64 column")))
+}
+
+/// A one-row batch of the columns named in `values`, typed as in `schema`.
+fn parse_row(schema: &Schema, values: &Map<String, Value>) -> Result<RecordBatch> {
+    let fields = values
+        .keys()
+        .map(|name| {
+            schema
+                .field_with_name(name)
+                .cloned()
+                .map_err(|_| WasmError::Other(format!("unknown column {name}")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let row = serde_json::to_string(values)?;
+    let mut reader = datafusion::arrow::json::ReaderBuilder::new(Arc::new(Schema::new(fields)))
+        .build(Cursor::new(row.as_bytes()))?;
+    reader
+        .next()
+        .transpose()?
+        .ok_or_else(|| WasmError::Other("no values to update".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest::read_json;
+    use crate::warnings::Warnings;
+
+    async fn table(ctx: &SessionContext) {
+        let batches = assign(
+            read_json(
+                b"{\"a\": 1}\n{\"a\": 2}\n{\"a\": 3}\n",
+                &Warnings::default(),
+            )
+            .unwrap(),
+            0,
+        )
+        .unwrap();
+        replace(ctx, "t", batches[0].schema(), batches).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_and_delete() {
+        let ctx = SessionContext::new();
+        table(&ctx).await;
+
+        let values = serde_json::from_str(r#"{"a": 20}"#).unwrap();
+        update(&ctx, "t", 1, &values).await.unwrap();
+        assert!(update(&ctx, "t", 9, &values).await.is_err());
+        assert_eq!(delete(&ctx, "t", &[0, 9]).await.unwrap(), 1);
+
+        let (_, batches) = table_batches(&ctx, "t").await.unwrap();
+        assert_eq!(next_id(&batches).unwrap(), 3);
+        let batch = &batches[0];
+        assert_eq!(row_ids(batch).unwrap().values(), &[1, 2]);
+        assert_eq!(
+            batch
+                .column(1)
+                .as_primitive::<datafusion::arrow::datatypes::Int64Type>()
+                .values(),
+            &[20, 3]
+        );
+    }
+
+    #[test]
+    fn test_assign_rejects_row_id() {
+        let batches = assign(read_json(b"{\"a\": 1}\n", &Warnings::default()).unwrap(), 5).unwrap();
+        assert_eq!(row_ids(&batches[0]).unwrap().value(0), 5);
+        assert!(assign(batches, 0).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Detection of the JavaScript runtime hosting the module and of the APIs it
+//! offers, so features a runtime lacks fail with an error instead of a panic.
+
+use js_sys::Reflect;
+use serde::Serialize;
+use tsify_next::Tsify;
+use wasm_bindgen::JsValue;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Tsify)]
+#[serde(rename_all = "kebab-case")]
+pub enum Runtime {
+    /// A page or web worker.
+    Browser,
+    /// Node.js, including Electron's main process.
+    Node,
+    Deno,
+    CloudflareWorkers,
+    /// Not compiled to wasm, e.g. unit tests.
+    Native,
+}
+
+impl Runtime {
+    pub fn detect() -> Self {
+        if !cfg!(target_arch = "wasm32") {
+            return Runtime::Native;
+        }
+        let global = js_sys::global();
+        // Deno also defines `process` for Node.js compatibility, so it goes first
+        if !property(&global, "Deno").is_undefined() {
+            return Runtime::Deno;
+        }
+        let user_agent = property(&property(&global, "navigator"), "userAgent");
+        if user_agent.as_string().as_deref() == Some("Cloudflare-Workers") {
+            return Runtime::CloudflareWorkers;
+        }
+        match property(&property(&global, "process"), "versions") {
+            versions if !property(&versions, "node").is_undefined() => Runtime::Node,
+            _ => Runtime::Browser,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Runtime::Browser => "the browser",
+            Runtime::Node => "Node.js",
+            Runtime::Deno => "Deno",
+            Runtime::CloudflareWorkers => "Cloudflare Workers",
+            Runtime::Native => "a native build",
+        }
+    }
+}
+
+/// The APIs of the runtime that features depend on.
+#[derive(Debug, Clone, Copy, Serialize, Tsify)]
+pub struct RuntimeApis {
+    /// `fetch`, for `http(s)://` and `s3://` URLs.
+    pub fetch: bool,
+    /// `setTimeout`.
+    pub timers: bool,
+    /// `crypto.getRandomValues`, for `random()` and `uuid()`.
+    pub random: bool,
+    /// Node's `fs` module, for `file://` URLs.
+    pub file_system: bool,
+    /// `navigator.storage`, for storage quota checks and the origin private file system.
+    pub storage: bool,
+}
+
+impl RuntimeApis {
+    pub fn detect(runtime: Runtime) -> Self {
+        if runtime == Runtime::Native {
+            return Self {
+                fetch: false,
+                timers: false,
+                random: false,
+                file_system: false,
+                storage: false,
+            };
+        }
+        let global = js_sys::global();
+        let has = |target: &JsValue, name: &str| !property(target, name).is_undefined();
+        Self {
+            fetch: has(&global, "fetch"),
+            timers: has(&global, "setTimeout"),
+            random: has(&property(&global, "crypto"), "getRandomValues"),
+            file_system: runtime == Runtime::Node,
+            storage: has(&property(&global, "navigator"), "storage"),
+        }
+    }
+}
+
+/// `target[name]`, undefined when `target` isn't an object.
+pub fn property(target: &JsValue, name: &str) -> JsValue {
+    if !target.is_object() {
+        return JsValue::UNDEFINED;
+    }
+    Reflect::get(target, &JsValue::from_str(name)).unwrap_or(JsValue::UNDEFINED)
+}
@@ -0,0 +1,224 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Host-driven scheduling: let the page run between batches of a query, and
+//! park background queries while interactive ones run.
+
+use std::sync::{Arc, Mutex};
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::execution::TaskContext;
+use datafusion::physical_plan::{execute_stream, ExecutionPlan};
+use futures::StreamExt;
+use js_sys::{Function, Promise};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::error::{Result, WasmError};
+use crate::progress::JsCallback;
+
+/// Ordered by precedence, `Interactive` first.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryPriority {
+    /// User-initiated work, never parked.
+    #[default]
+    Interactive,
+    /// Prefetching and similar work, parked between batches while any
+    /// interactive query is running.
+    Background,
+}
+
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    /// A host callback awaited between units of work, e.g. one wrapping
+    /// `requestIdleCallback` or `scheduler.postTask`.
+    yield_callback: Arc<Mutex<Option<JsCallback>>>,
+    /// Number of interactive queries running.
+    interactive: Arc<watch::Sender<usize>>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            yield_callback: Default::default(),
+            interactive: Arc::new(watch::channel(0).0),
+        }
+    }
+}
+
+/// Marks an interactive query as running until dropped.
+pub struct InteractiveGuard(Arc<watch::Sender<usize>>);
+
+impl Drop for InteractiveGuard {
+    fn drop(&mut self) {
+        self.0.send_modify(|count| *count -= 1);
+    }
+}
+
+impl Scheduler {
+    pub fn set_yield_callback(&self, callback: Option<Function>) {
+        *self.yield_callback.lock().unwrap() = callback.map(JsCallback);
+    }
+
+    /// Register a query of `priority` for as long as the guard lives.
+    pub fn enter(&self, priority: QueryPriority) -> Option<InteractiveGuard> {
+        match priority {
+            QueryPriority::Interactive => {
+                self.interactive.send_modify(|count| *count += 1);
+                Some(InteractiveGuard(self.interactive.clone()))
+            }
+            QueryPriority::Background => None,
+        }
+    }
+
+    /// Let the host run, then, for background work, wait until no interactive
+    /// query is running.
+    pub async fn yield_now(&self, priority: QueryPriority) -> Result<()> {
+        let callback = self.yield_callback.lock().unwrap().clone();
+        if let Some(callback) = callback {
+            let value = callback.0.call0(&JsValue::NULL).map_err(js_error)?;
+            if let Ok(promise) = value.dyn_into::<Promise>() {
+                JsFuture::from(promise).await.map_err(js_error)?;
+            }
+        }
+
+        if priority == QueryPriority::Background {
+            // the sender lives in `self`, so waiting can't fail
+            let _ = self
+                .interactive
+                .subscribe()
+                .wait_for(|count| *count == 0)
+                .await;
+        }
+        Ok(())
+    }
+
+    /// Like [`datafusion::physical_plan::collect`], yielding after each batch.
+    pub async fn collect(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        task_ctx: Arc<TaskContext>,
+        priority: QueryPriority,
+    ) -> Result<Vec<RecordBatch>> {
+        let (batches, _) = self
+            .collect_limited(plan, task_ctx, priority, None, |_| Ok(()))
+            .await?;
+        Ok(batches)
+    }
+
+    /// Like [`Self::collect`], but only keeps the first `max_rows` rows. The rest are
+    /// counted and dropped. Every batch is passed to `on_batch` first, which can abort
+    /// the query. Returns the kept batches and the total row count.
+    pub async fn collect_limited(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        task_ctx: Arc<TaskContext>,
+        priority: QueryPriority,
+        max_rows: Option<usize>,
+        mut on_batch: impl FnMut(&RecordBatch) -> Result<()>,
+    ) -> Result<(Vec<RecordBatch>, usize)> {
+        let mut stream = execute_stream(plan, task_ctx)?;
+        let mut batches = Vec::new();
+        let mut kept = 0;
+        let mut total_rows = 0;
+        while let Some(batch) = stream.next().await {
+            let batch = batch?;
+            on_batch(&batch)?;
+            total_rows += batch.num_rows();
+            let room = max_rows.map_or(usize::MAX, |max_rows| max_rows - kept);
+            if room >= batch.num_rows() {
+                kept += batch.num_rows();
+                batches.push(batch);
+            } else if room > 0 {
+                kept += room;
+                batches.push(batch.slice(0, room));
+            }
+            self.yield_now(priority).await?;
+        }
+        Ok((batches, total_rows))
+    }
+}
+
+fn js_error(err: JsValue) -> WasmError {
+    WasmError::Other(
+        err.as_string()
+            .unwrap_or_else(|| format!("yield callback failed: {err:?}")),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    #[tokio::test]
+    async fn test_background_parks_while_interactive_runs() {
+        let scheduler = Scheduler::default();
+        let guard = scheduler.enter(QueryPriority::Interactive);
+        assert!(scheduler.enter(QueryPriority::Background).is_none());
+
+        assert!(scheduler
+            .yield_now(QueryPriority::Background)
+            .now_or_never()
+            .is_none());
+        scheduler
+            .yield_now(QueryPriority::Interactive)
+            .await
+            .unwrap();
+
+        drop(guard);
+        scheduler
+            .yield_now(QueryPriority::Background)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_collect_limited() {
+        use datafusion::arrow::array::{ArrayRef, Int32Array};
+        use datafusion::physical_plan::memory::MemoryExec;
+
+        let batch = RecordBatch::try_from_iter(vec![(
+            "v",
+            Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+        )])
+        .unwrap();
+        let plan = Arc::new(
+            MemoryExec::try_new(&[vec![batch.clone(), batch.clone()]], batch.schema(), None)
+                .unwrap(),
+        );
+
+        let (batches, total_rows) = Scheduler::default()
+            .collect_limited(
+                plan,
+                Arc::new(TaskContext::default()),
+                QueryPriority::Interactive,
+                Some(4),
+                |_| Ok(()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(total_rows, 6);
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[1].num_rows(), 1);
+    }
+}
@@ -0,0 +1,226 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Results delivered as a sequence of independent Arrow IPC streams.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::execution::SendableRecordBatchStream;
+use futures::StreamExt;
+use tokio::sync::Mutex;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error::{Result, WasmError};
+use crate::result_format::write_ipc_stream;
+use crate::IpcCompression;
+
+/// One IPC stream of a segmented result.
+#[wasm_bindgen]
+pub struct IpcSegment {
+    data: Vec<u8>,
+    continuation: Option<String>,
+}
+
+#[wasm_bindgen]
+impl IpcSegment {
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> js_sys::Uint8Array {
+        js_sys::Uint8Array::from(self.data.as_slice())
+    }
+
+    /// Token to fetch the next segment with, `undefined` after the last one.
+    #[wasm_bindgen(getter)]
+    pub fn continuation(&self) -> Option<String> {
+        self.continuation.clone()
+    }
+}
+
+/// A result stream cut into segments of about `max_bytes` of Arrow data each.
+struct SegmentCursor {
+    stream: SendableRecordBatchStream,
+    /// Rows of a batch that didn't fit into the previous segment.
+    pending: Option<RecordBatch>,
+    max_bytes: usize,
+    compression: IpcCompression,
+}
+
+impl SegmentCursor {
+    /// Encode the next segment, and whether the stream is exhausted after it.
+    async fn next_segment(&mut self) -> Result<(Vec<u8>, bool)> {
+        let mut batches = Vec::new();
+        let mut size = 0;
+        let mut done = false;
+
+        while size < self.max_bytes {
+            let batch = match self.pending.take() {
+                Some(batch) => batch,
+                None => match self.stream.next().await {
+                    Some(batch) => batch?,
+                    None => {
+                        done = true;
+                        break;
+                    }
+                },
+            };
+
+            let batch_size = slice_size(&batch);
+            let remaining = self.max_bytes - size;
+            if batch_size > remaining && batch.num_rows() > 1 {
+                let rows = (batch.num_rows() * remaining / batch_size).clamp(1, batch.num_rows());
+                if rows < batch.num_rows() {
+                    self.pending = Some(batch.slice(rows, batch.num_rows() - rows));
+                }
+                size += batch_size * rows / batch.num_rows();
+                batches.push(batch.slice(0, rows));
+            } else {
+                size += batch_size;
+                batches.push(batch);
+            }
+        }
+
+        let data = write_ipc_stream(&self.stream.schema(), &batches, self.compression)?;
+        Ok((data, done))
+    }
+}
+
+/// Open segmented results, keyed by continuation token.
+#[derive(Default)]
+pub struct Segments {
+    cursors: Mutex<HashMap<String, SegmentCursor>>,
+}
+
+impl Segments {
+    /// Start segmenting `stream` and return its first segment.
+    pub async fn start(
+        &self,
+        stream: SendableRecordBatchStream,
+        max_bytes: usize,
+        compression: IpcCompression,
+    ) -> Result<IpcSegment> {
+        if max_bytes == 0 {
+            return Err(WasmError::Other(
+                "segment size must be positive".to_string(),
+            ));
+        }
+        let cursor = SegmentCursor {
+            stream,
+            pending: None,
+            max_bytes,
+            compression,
+        };
+        self.advance(cursor, next_token()).await
+    }
+
+    pub async fn next(&self, token: &str) -> Result<IpcSegment> {
+        let cursor = self
+            .cursors
+            .lock()
+            .await
+            .remove(token)
+            .ok_or_else(|| WasmError::Other(format!("unknown continuation token: {token}")))?;
+        self.advance(cursor, token.to_string()).await
+    }
+
+    /// Drop the rest of a segmented result. Returns whether `token` was open.
+    pub async fn cancel(&self, token: &str) -> bool {
+        self.cursors.lock().await.remove(token).is_some()
+    }
+
+    async fn advance(&self, mut cursor: SegmentCursor, token: String) -> Result<IpcSegment> {
+        let (data, done) = cursor.next_segment().await?;
+        if done {
+            return Ok(IpcSegment {
+                data,
+                continuation: None,
+            });
+        }
+
+        self.cursors.lock().await.insert(token.clone(), cursor);
+        Ok(IpcSegment {
+            data,
+            continuation: Some(token),
+        })
+    }
+}
+
+/// Bytes of Arrow data in `batch`. Unlike `get_array_memory_size`, only counts the
+/// part of shared buffers a slice covers.
+fn slice_size(batch: &RecordBatch) -> usize {
+    batch
+        .columns()
+        .iter()
+        .map(|column| {
+            column
+                .to_data()
+                .get_slice_memory_size()
+                .unwrap_or_else(|_| column.get_array_memory_size())
+        })
+        .sum()
+}
+
+fn next_token() -> String {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    format!("segment-{}", NEXT.fetch_add(1, Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::arrow::array::Int64Array;
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::arrow::ipc::reader::StreamReader;
+    use datafusion::physical_plan::memory::MemoryStream;
+    use std::sync::Arc;
+
+    fn rows(data: &[u8]) -> usize {
+        StreamReader::try_new(data, None)
+            .unwrap()
+            .map(|batch| batch.unwrap().num_rows())
+            .sum()
+    }
+
+    #[tokio::test]
+    async fn test_segments() {
+        let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Array::from_iter_values(0..1000))],
+        )
+        .unwrap();
+        let stream = MemoryStream::try_new(vec![batch.clone()], schema, None).unwrap();
+
+        let segments = Segments::default();
+        let max_bytes = slice_size(&batch) / 4;
+        let mut segment = segments
+            .start(Box::pin(stream), max_bytes, IpcCompression::None)
+            .await
+            .unwrap();
+        let mut total = rows(&segment.data);
+        let mut count = 1;
+        while let Some(token) = segment.continuation.take() {
+            segment = segments.next(&token).await.unwrap();
+            total += rows(&segment.data);
+            count += 1;
+        }
+
+        assert_eq!(total, 1000);
+        assert!(count >= 4);
+        assert!(segments.next("segment-0").await.is_err());
+    }
+}
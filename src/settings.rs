@@ -0,0 +1,99 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! `SET` statements, applied to the shared session and answered with the new value.
+
+use std::sync::Arc;
+
+use datafusion::arrow::array::{RecordBatch, StringArray};
+use datafusion::arrow::datatypes::{DataType, Field, Schema};
+use datafusion::logical_expr::{LogicalPlan, SetVariable, Statement};
+use datafusion::prelude::SessionContext;
+
+use crate::error::Result;
+
+/// Name and value of a `SET` statement's plan.
+pub fn set_variable(plan: &LogicalPlan) -> Option<(&str, &str)> {
+    match plan {
+        LogicalPlan::Statement(Statement::SetVariable(SetVariable {
+            variable, value, ..
+        })) => Some((variable.as_str(), value.as_str())),
+        _ => None,
+    }
+}
+
+/// Set option `name` of `ctx`'s session.
+pub fn apply(ctx: &SessionContext, name: &str, value: &str) -> Result<()> {
+    ctx.state_ref()
+        .write()
+        .config_mut()
+        .options_mut()
+        .set(name, value)?;
+    Ok(())
+}
+
+/// The `name, value` row of option `name`, shaped like the output of `SHOW`.
+pub fn setting_batch(ctx: &SessionContext, name: &str) -> Result<RecordBatch> {
+    let value = ctx
+        .state()
+        .config_options()
+        .entries()
+        .into_iter()
+        .find(|entry| entry.key == name)
+        .and_then(|entry| entry.value);
+    let schema = Schema::new(vec![
+        Field::new("name", DataType::Utf8, false),
+        Field::new("value", DataType::Utf8, true),
+    ]);
+    Ok(RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(StringArray::from(vec![name])),
+            Arc::new(StringArray::from(vec![value])),
+        ],
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::AsArray;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_set_variable() {
+        let shared = SessionContext::new();
+        // a per-call copy of the session, as made for hints and config overrides
+        let ctx = SessionContext::new_with_state(shared.state());
+        let plan = ctx
+            .state()
+            .create_logical_plan("SET datafusion.execution.batch_size = 1024")
+            .await
+            .unwrap();
+        let (name, value) = set_variable(&plan).unwrap();
+        assert_eq!(name, "datafusion.execution.batch_size");
+
+        apply(&shared, name, value).unwrap();
+        assert_eq!(shared.state().config().batch_size(), 1024);
+        let batch = setting_batch(&shared, name).unwrap();
+        assert_eq!(batch.column(1).as_string::<i32>().value(0), "1024");
+
+        assert!(apply(&shared, "datafusion.no_such_option", "1").is_err());
+        let plan = ctx.state().create_logical_plan("SELECT 1").await.unwrap();
+        assert!(set_variable(&plan).is_none());
+    }
+}
@@ -0,0 +1,324 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! SQL formatting for editors: statements are parsed, printed back by the AST, then
+//! laid out with one clause per line and indented subqueries.
+
+use datafusion::sql::parser::DFParser;
+use datafusion::sql::sqlparser::dialect::GenericDialect;
+use datafusion::sql::sqlparser::parser::ParserError;
+use datafusion::sql::sqlparser::tokenizer::{Token, Tokenizer};
+
+use crate::error::Result;
+
+/// Spaces per indentation level.
+const INDENT: usize = 2;
+
+/// Keywords that start a clause on a new line.
+const CLAUSES: &[&str] = &[
+    "SELECT",
+    "FROM",
+    "WHERE",
+    "GROUP",
+    "HAVING",
+    "WINDOW",
+    "QUALIFY",
+    "ORDER",
+    "LIMIT",
+    "OFFSET",
+    "UNION",
+    "INTERSECT",
+    "EXCEPT",
+    "WITH",
+    "VALUES",
+];
+
+/// Keywords that can precede `JOIN`.
+const JOIN_MODIFIERS: &[&str] = &[
+    "NATURAL", "LEFT", "RIGHT", "FULL", "INNER", "OUTER", "CROSS", "SEMI", "ANTI",
+];
+
+/// Format every statement of `sql`, separated by `;` and a blank line. Comments are
+/// dropped, as the AST doesn't keep them.
+pub fn format_sql(sql: &str) -> Result<String> {
+    let formatted = DFParser::parse_sql(sql)?
+        .iter()
+        .map(|statement| layout(&statement.to_string()))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(formatted.join(";\n\n"))
+}
+
+/// A parenthesized part of a statement, or the statement itself.
+#[derive(Debug, Default)]
+struct Level {
+    /// Whether the level holds a query, whose clauses get their own lines.
+    query: bool,
+    indent: usize,
+    clause: &'static str,
+    /// A `BETWEEN` waits for its `AND`.
+    between: bool,
+}
+
+/// Lay out `sql`, a statement printed by the AST.
+fn layout(sql: &str) -> Result<String> {
+    let tokens = tokens(sql)?;
+    let mut out = String::with_capacity(sql.len() * 2);
+    let mut levels = vec![Level {
+        query: true,
+        ..Default::default()
+    }];
+    // indentation of the line the next token starts
+    let mut break_next: Option<usize> = None;
+    let mut space = false;
+
+    for (index, (token, text)) in tokens.iter().enumerate() {
+        if matches!(token, Token::Whitespace(_)) {
+            space = true;
+            continue;
+        }
+        let current = keyword(token);
+        let next = tokens[index + 1..]
+            .iter()
+            .map(|(token, _)| token)
+            .find(|token| !matches!(token, Token::Whitespace(_)));
+        let previous = previous_keyword(&tokens[..index]);
+        let level = levels.last_mut().unwrap();
+        let mut line = break_next.take();
+
+        if level.query {
+            let clause = CLAUSES
+                .iter()
+                .copied()
+                .find(|clause| Some(*clause) == current);
+            if let Some(clause) = clause {
+                // not the FROM of `IS DISTINCT FROM`, nor the ORDER of `ORDER` alone
+                let starts = match clause {
+                    "FROM" => previous != Some("DISTINCT"),
+                    "GROUP" | "ORDER" => next.and_then(keyword) == Some("BY"),
+                    _ => true,
+                };
+                if starts {
+                    line = Some(level.indent);
+                    level.clause = clause;
+                }
+            } else if starts_join(&tokens[index..], previous) {
+                line = Some(level.indent);
+                level.clause = "JOIN";
+            } else if current == Some("BETWEEN") {
+                level.between = true;
+            } else if matches!(current, Some("AND" | "OR"))
+                && matches!(level.clause, "WHERE" | "HAVING")
+            {
+                if current == Some("AND") && level.between {
+                    level.between = false;
+                } else {
+                    line = Some(level.indent + 1);
+                }
+            }
+        }
+
+        let (indent, in_query, clause) = (level.indent, level.query, level.clause);
+        match token {
+            Token::LParen => {
+                let query = matches!(next.and_then(keyword), Some("SELECT" | "WITH"));
+                let indent = indent + usize::from(query);
+                levels.push(Level {
+                    query,
+                    indent,
+                    ..Default::default()
+                });
+                if query {
+                    break_next = Some(indent);
+                }
+            }
+            Token::RParen if levels.len() > 1 => {
+                let closed = levels.pop().unwrap();
+                if closed.query {
+                    line = Some(levels.last().unwrap().indent);
+                }
+            }
+            Token::Comma if in_query && matches!(clause, "SELECT" | "WITH") => {
+                break_next = Some(indent + usize::from(clause == "SELECT"));
+            }
+            _ => {}
+        }
+        // the select list starts on its own line, after any DISTINCT
+        let level = levels.last().unwrap();
+        let select_prefix = match current {
+            Some("SELECT") => next.and_then(keyword) != Some("DISTINCT"),
+            Some("DISTINCT") => previous == Some("SELECT"),
+            _ => false,
+        };
+        if level.query && level.clause == "SELECT" && select_prefix {
+            break_next = Some(level.indent + 1);
+        }
+
+        match line {
+            Some(indent) if !out.is_empty() => {
+                out.truncate(out.trim_end().len());
+                out.push('\n');
+                out.push_str(&" ".repeat(indent * INDENT));
+            }
+            Some(_) => {}
+            None if space && !out.is_empty() => out.push(' '),
+            None => {}
+        }
+        out.push_str(text);
+        space = false;
+    }
+    Ok(out)
+}
+
+/// Whether `tokens` start a join, such as `LEFT OUTER JOIN`, that `previous`, the
+/// keyword before them, isn't already part of.
+fn starts_join(tokens: &[(Token, &str)], previous: Option<&str>) -> bool {
+    if previous.is_some_and(|previous| JOIN_MODIFIERS.contains(&previous)) {
+        return false;
+    }
+    for keyword in tokens
+        .iter()
+        .filter(|(token, _)| !matches!(token, Token::Whitespace(_)))
+        .map(|(token, _)| keyword(token))
+    {
+        match keyword {
+            Some("JOIN") => return true,
+            Some(keyword) if JOIN_MODIFIERS.contains(&keyword) => {}
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// The keyword of the last token of `tokens` other than whitespace.
+fn previous_keyword(tokens: &[(Token, &str)]) -> Option<&'static str> {
+    tokens
+        .iter()
+        .rev()
+        .map(|(token, _)| token)
+        .find(|token| !matches!(token, Token::Whitespace(_)))
+        .and_then(keyword)
+}
+
+/// The uppercase text of an unquoted keyword.
+fn keyword(token: &Token) -> Option<&'static str> {
+    let Token::Word(word) = token else {
+        return None;
+    };
+    if word.quote_style.is_some() {
+        return None;
+    }
+    CLAUSES
+        .iter()
+        .chain(JOIN_MODIFIERS)
+        .chain(&["BY", "JOIN", "AND", "OR", "BETWEEN", "DISTINCT"])
+        .find(|keyword| word.value.eq_ignore_ascii_case(keyword))
+        .copied()
+}
+
+/// The tokens of `sql` with their exact text, so literals keep their escaping.
+fn tokens(sql: &str) -> Result<Vec<(Token, &str)>> {
+    let tokens = Tokenizer::new(&GenericDialect {}, sql)
+        .tokenize_with_location()
+        .map_err(ParserError::from)?;
+
+    // byte offset of every token, from its 1-based line and column
+    let mut offsets = Vec::with_capacity(tokens.len());
+    let mut pending = tokens.iter().peekable();
+    let (mut line, mut column) = (1, 1);
+    for (offset, char) in sql.char_indices() {
+        while let Some(token) = pending.peek() {
+            if (token.location.line, token.location.column) > (line, column) {
+                break;
+            }
+            offsets.push(offset);
+            pending.next();
+        }
+        if char == '\n' {
+            (line, column) = (line + 1, 1);
+        } else {
+            column += 1;
+        }
+    }
+    offsets.resize(tokens.len(), sql.len());
+
+    Ok(tokens
+        .into_iter()
+        .zip(&offsets)
+        .enumerate()
+        .filter(|(_, (token, _))| token.token != Token::EOF)
+        .map(|(index, (token, start))| {
+            let end = offsets.get(index + 1).copied().unwrap_or(sql.len());
+            (token.token, &sql[*start..end])
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_query() {
+        let formatted = format_sql(
+            "select a,b from t where x=1 and y between 1 and 2 or z like 'it''s' order by a",
+        )
+        .unwrap();
+        assert_eq!(
+            formatted,
+            "SELECT\n  a,\n  b\nFROM t\nWHERE x = 1\n  AND y BETWEEN 1 AND 2\n  OR z LIKE 'it''s'\nORDER BY a"
+        );
+    }
+
+    #[test]
+    fn test_format_subquery_and_join() {
+        let formatted = format_sql(
+            "select distinct s.a, count(*) over (order by s.a) from (select a from t) s \
+             left outer join u on s.a = u.a where a is distinct from b; select 1",
+        )
+        .unwrap();
+        assert_eq!(
+            formatted,
+            "SELECT DISTINCT\n  s.a,\n  count(*) OVER (ORDER BY s.a)\nFROM (\n  SELECT\n    a\n  FROM t\n) AS s\nLEFT JOIN u ON s.a = u.a\nWHERE a IS DISTINCT FROM b;\n\nSELECT\n  1"
+        );
+    }
+
+    #[test]
+    fn test_format_round_trips() {
+        let parse = |sql: &str| -> Vec<String> {
+            DFParser::parse_sql(sql)
+                .unwrap()
+                .iter()
+                .map(ToString::to_string)
+                .collect()
+        };
+        for sql in [
+            "select a, b from t where x = 1 and y between 1 and 2 or z like 'it''s' order by a",
+            "with w as (select a from t), v as (select 1) select * from w natural join v",
+            "select \"from\", count(*) from t group by 1 having count(*) > 1 limit 5 offset 1",
+            "select a from t union all select b from u except select c from v",
+            "insert into t values (1, 'a'), (2, 'b'); select 1",
+        ] {
+            let formatted = format_sql(sql).unwrap();
+            assert_eq!(parse(&formatted), parse(sql), "{formatted}");
+        }
+    }
+
+    #[test]
+    fn test_format_invalid_sql() {
+        assert!(format_sql("select from where").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! SQL parser options: how identifiers and literals are read.
+
+use datafusion::common::config::SqlParserOptions;
+use serde::Deserialize;
+use tsify_next::Tsify;
+
+use crate::error::Result;
+
+/// Parser options given as a JSON object, e.g. `{"ident_normalization": false}`.
+/// Absent keys are unchanged.
+#[derive(Debug, Default, Deserialize, Tsify)]
+#[serde(default, deny_unknown_fields)]
+pub struct SqlOptions {
+    /// Lowercase unquoted identifiers, on by default. Off, `SELECT MyColumn` finds
+    /// the case-sensitive column `MyColumn` of a Parquet file without quotes.
+    pub ident_normalization: Option<bool>,
+    /// Lowercase the values of `OPTIONS (...)` in `CREATE EXTERNAL TABLE`.
+    pub options_value_normalization: Option<bool>,
+    /// Read float literals such as `1.5` as decimals instead of doubles.
+    pub parse_float_as_decimal: Option<bool>,
+}
+
+impl SqlOptions {
+    pub fn from_json(options: &str) -> Result<Self> {
+        Ok(serde_json::from_str(options)?)
+    }
+
+    pub fn apply(&self, options: &mut SqlParserOptions) {
+        if let Some(ident_normalization) = self.ident_normalization {
+            options.enable_ident_normalization = ident_normalization;
+        }
+        if let Some(options_value_normalization) = self.options_value_normalization {
+            options.enable_options_value_normalization = options_value_normalization;
+        }
+        if let Some(parse_float_as_decimal) = self.parse_float_as_decimal {
+            options.parse_float_as_decimal = parse_float_as_decimal;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::prelude::{SessionConfig, SessionContext};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_case_sensitive_identifiers() {
+        let mut config = SessionConfig::new();
+        SqlOptions::from_json(r#"{"ident_normalization": false}"#)
+            .unwrap()
+            .apply(&mut config.options_mut().sql_parser);
+        assert!(!config.options().sql_parser.enable_ident_normalization);
+
+        let ctx = SessionContext::new_with_config(config);
+        ctx.sql(r#"CREATE TABLE t AS SELECT 1 AS "MyColumn""#)
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let batches = ctx
+            .sql("SELECT MyColumn FROM t")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        assert_eq!(batches[0].num_rows(), 1);
+
+        assert!(SqlOptions::from_json(r#"{"normalize": false}"#).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A host callback that vets every statement before it runs, for per-tenant
+//! security policies.
+
+use std::sync::{Arc, Mutex};
+
+use datafusion::execution::context::SessionContext;
+use datafusion::sql::parser::Statement;
+use js_sys::{Function, Promise};
+use serde::Serialize;
+use tsify_next::Tsify;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::error::{Result, WasmError};
+use crate::progress::JsCallback;
+
+/// What the filter is called with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Tsify)]
+pub struct StatementInfo {
+    /// Variant name of the parsed statement, e.g. `Query`, `Insert`, `CreateTable`,
+    /// `CreateExternalTable` or `CopyTo`.
+    pub kind: String,
+    /// Tables the statement reads or writes, as written in the SQL.
+    pub tables: Vec<String>,
+    pub sql: String,
+}
+
+impl StatementInfo {
+    pub fn new(ctx: &SessionContext, statement: &Statement) -> Result<Self> {
+        let tables = ctx
+            .state()
+            .resolve_table_references(statement)?
+            .iter()
+            .map(|table| table.to_string())
+            .collect();
+        Ok(Self {
+            kind: kind(statement),
+            tables,
+            sql: statement.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StatementFilter {
+    callback: Arc<Mutex<Option<JsCallback>>>,
+}
+
+impl StatementFilter {
+    pub fn set_callback(&self, callback: Option<Function>) {
+        *self.callback.lock().unwrap() = callback.map(JsCallback);
+    }
+
+    /// Ask the filter whether `info` may run. It allows a statement by returning (or
+    /// resolving to) `true`; anything else rejects it, a string being the reason.
+    pub async fn check(&self, info: &StatementInfo) -> Result<()> {
+        let callback = self.callback.lock().unwrap().clone();
+        let Some(callback) = callback else {
+            return Ok(());
+        };
+
+        let argument = js_sys::JSON::parse(&serde_json::to_string(info)?).map_err(js_error)?;
+        let mut verdict = callback
+            .0
+            .call1(&JsValue::NULL, &argument)
+            .map_err(js_error)?;
+        if let Some(promise) = verdict.dyn_ref::<Promise>() {
+            verdict = JsFuture::from(promise.clone()).await.map_err(js_error)?;
+        }
+
+        if verdict.as_bool() == Some(true) {
+            return Ok(());
+        }
+        Err(WasmError::StatementRejected {
+            kind: info.kind.clone(),
+            reason: verdict
+                .as_string()
+                .unwrap_or_else(|| "denied by the statement filter".to_string()),
+        })
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.callback.lock().unwrap().is_some()
+    }
+}
+
+/// The variant name of `statement`, taken from its `Debug` output.
+pub fn kind(statement: &Statement) -> String {
+    let debug = match statement {
+        Statement::Statement(statement) => format!("{statement:?}"),
+        statement => format!("{statement:?}"),
+    };
+    debug
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn js_error(err: JsValue) -> WasmError {
+    WasmError::Other(
+        err.as_string()
+            .unwrap_or_else(|| format!("statement filter failed: {err:?}")),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::sql::parser::DFParser;
+
+    fn info(sql: &str) -> StatementInfo {
+        let statement = DFParser::parse_sql(sql).unwrap().pop_front().unwrap();
+        StatementInfo::new(&SessionContext::new(), &statement).unwrap()
+    }
+
+    #[test]
+    fn test_statement_info() {
+        let query = info("SELECT * FROM a JOIN b.c ON a.x = c.x");
+        assert_eq!(query.kind, "Query");
+        assert_eq!(query.tables, vec!["a", "b.c"]);
+
+        assert_eq!(info("INSERT INTO t VALUES (1)").kind, "Insert");
+        assert_eq!(info("DROP TABLE t").kind, "Drop");
+        assert_eq!(
+            info("CREATE EXTERNAL TABLE t STORED AS CSV LOCATION 'x.csv'").kind,
+            "CreateExternalTable"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Tables loaded from a JS `ReadableStream` of CSV or NDJSON bytes, decoded
+//! chunk by chunk so the raw text is never held in memory.
+
+use datafusion::arrow::array::RecordBatch;
+#[cfg(feature = "csv")]
+use datafusion::arrow::csv;
+use datafusion::arrow::datatypes::SchemaRef;
+#[cfg(feature = "json")]
+use datafusion::arrow::json;
+use js_sys::{Function, Promise, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::error::{Result, WasmError};
+use crate::listing::TableFormat;
+
+/// Incremental decoder of one of the text formats. There is one per stream, so the
+/// size of the CSV variant doesn't matter.
+#[allow(clippy::large_enum_variant)]
+pub enum StreamDecoder {
+    #[cfg(feature = "csv")]
+    Csv(csv::reader::Decoder),
+    #[cfg(feature = "json")]
+    Json(json::reader::Decoder),
+}
+
+impl StreamDecoder {
+    /// A decoder of `format` data with `schema`. CSV data starts with a header line.
+    pub fn new(format: TableFormat, schema: SchemaRef) -> Result<Self> {
+        match format {
+            #[cfg(feature = "csv")]
+            TableFormat::Csv => Ok(Self::Csv(
+                csv::ReaderBuilder::new(schema)
+                    .with_header(true)
+                    .build_decoder(),
+            )),
+            #[cfg(feature = "json")]
+            TableFormat::Json => Ok(Self::Json(
+                json::ReaderBuilder::new(schema).build_decoder()?,
+            )),
+            TableFormat::Parquet => Err(WasmError::Other(
+                "Parquet can't be streamed, expected CSV or NDJSON".to_string(),
+            )),
+            #[allow(unreachable_patterns)]
+            format => Err(crate::listing::format_disabled(format)),
+        }
+    }
+
+    /// Decode `data`, adding every completed batch to `batches`. Records may be
+    /// split across chunks.
+    pub fn decode(&mut self, mut data: &[u8], batches: &mut Vec<RecordBatch>) -> Result<()> {
+        while !data.is_empty() {
+            let read = match self {
+                #[cfg(feature = "csv")]
+                Self::Csv(decoder) => decoder.decode(data)?,
+                #[cfg(feature = "json")]
+                Self::Json(decoder) => decoder.decode(data)?,
+            };
+            data = &data[read..];
+            // the decoder stops reading once a batch is full
+            if !data.is_empty() {
+                self.flush(batches)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the rows decoded but not yet returned to `batches`.
+    pub fn flush(&mut self, batches: &mut Vec<RecordBatch>) -> Result<()> {
+        let batch = match self {
+            #[cfg(feature = "csv")]
+            Self::Csv(decoder) => decoder.flush()?,
+            #[cfg(feature = "json")]
+            Self::Json(decoder) => decoder.flush()?,
+        };
+        batches.extend(batch.filter(|batch| batch.num_rows() > 0));
+        Ok(())
+    }
+}
+
+/// Read every chunk of the JS `ReadableStream` `stream` through `decoder`.
+pub async fn read(stream: &JsValue, mut decoder: StreamDecoder) -> Result<Vec<RecordBatch>> {
+    let reader = call(stream, "getReader")?;
+    let mut batches = Vec::new();
+    loop {
+        let result = JsFuture::from(Promise::from(call(&reader, "read")?))
+            .await
+            .map_err(|err| WasmError::Other(format!("failed to read the stream: {err:?}")))?;
+        let done = Reflect::get(&result, &"done".into())
+            .ok()
+            .and_then(|done| done.as_bool())
+            .unwrap_or(true);
+        if done {
+            break;
+        }
+        let chunk = Reflect::get(&result, &"value".into())
+            .ok()
+            .and_then(|value| value.dyn_into::<Uint8Array>().ok())
+            .ok_or_else(|| WasmError::Other("stream chunks must be Uint8Arrays".to_string()))?;
+        decoder.decode(&chunk.to_vec(), &mut batches)?;
+    }
+    decoder.flush(&mut batches)?;
+    Ok(batches)
+}
+
+fn call(target: &JsValue, method: &str) -> Result<JsValue> {
+    Reflect::get(target, &method.into())
+        .ok()
+        .and_then(|method| method.dyn_into::<Function>().ok())
+        .ok_or_else(|| WasmError::Other(format!("expected a ReadableStream, {method} is missing")))?
+        .call0(target)
+        .map_err(|err| WasmError::Other(format!("{method} failed: {err:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+
+    use super::*;
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]))
+    }
+
+    fn decode_chunks(format: TableFormat, chunks: &[&[u8]]) -> Vec<RecordBatch> {
+        let mut decoder = StreamDecoder::new(format, schema()).unwrap();
+        let mut batches = Vec::new();
+        for chunk in chunks {
+            decoder.decode(chunk, &mut batches).unwrap();
+        }
+        decoder.flush(&mut batches).unwrap();
+        batches
+    }
+
+    fn rows(batches: &[RecordBatch]) -> usize {
+        batches.iter().map(|batch| batch.num_rows()).sum()
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_csv_records_split_across_chunks() {
+        let batches = decode_chunks(TableFormat::Csv, &[b"id,name\n1,a", b"da\n2,", b"bob\n"]);
+        assert_eq!(rows(&batches), 2);
+        let names = batches[0].column(1).as_any();
+        let names = names
+            .downcast_ref::<datafusion::arrow::array::StringArray>()
+            .unwrap();
+        assert_eq!(names.value(0), "ada");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_records_split_across_chunks() {
+        let batches = decode_chunks(
+            TableFormat::Json,
+            &[b"{\"id\": 1, \"na", b"me\": \"x\"}\n{\"id\"", b": 2}\n"],
+        );
+        assert_eq!(rows(&batches), 2);
+    }
+
+    #[test]
+    fn test_parquet_rejected() {
+        assert!(StreamDecoder::new(TableFormat::Parquet, schema()).is_err());
+    }
+}
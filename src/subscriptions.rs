@@ -0,0 +1,79 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Queries re-run whenever a table they read changes, for live dashboards.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use datafusion::sql::TableReference;
+use js_sys::Function;
+
+use crate::progress::JsCallback;
+
+#[derive(Debug)]
+struct Subscription {
+    sql: String,
+    /// Unqualified names of the tables the query reads.
+    tables: Vec<String>,
+    callback: JsCallback,
+}
+
+/// Subscribed queries, keyed by subscription id.
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    state: Mutex<(u32, BTreeMap<u32, Subscription>)>,
+}
+
+impl Subscriptions {
+    /// Subscribe `callback` to the results of `sql`, which reads `tables`.
+    pub fn add(&self, sql: String, tables: &[TableReference], callback: Function) -> u32 {
+        let mut state = self.state.lock().unwrap();
+        state.0 += 1;
+        let id = state.0;
+        let subscription = Subscription {
+            sql,
+            tables: tables
+                .iter()
+                .map(|table| table.table().to_string())
+                .collect(),
+            callback: JsCallback(callback),
+        };
+        state.1.insert(id, subscription);
+        id
+    }
+
+    pub fn remove(&self, id: u32) -> bool {
+        self.state.lock().unwrap().1.remove(&id).is_some()
+    }
+
+    pub fn clear(&self) {
+        self.state.lock().unwrap().1.clear();
+    }
+
+    /// The query and callback of every subscription reading table `name`.
+    pub fn affected(&self, name: &str) -> Vec<(String, JsCallback)> {
+        let name = TableReference::from(name).table().to_string();
+        let state = self.state.lock().unwrap();
+        state
+            .1
+            .values()
+            .filter(|subscription| subscription.tables.contains(&name))
+            .map(|subscription| (subscription.sql.clone(), subscription.callback.clone()))
+            .collect()
+    }
+}
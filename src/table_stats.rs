@@ -0,0 +1,331 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! `ANALYZE TABLE`: statistics collected once from a table and reported to the
+//! optimizer by its scans, so join ordering can use them on later queries.
+
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use datafusion::arrow::datatypes::SchemaRef;
+use datafusion::catalog::Session;
+use datafusion::common::stats::Precision;
+use datafusion::common::{ColumnStatistics, ScalarValue, Statistics};
+use datafusion::datasource::{TableProvider, TableType};
+use datafusion::execution::context::SessionContext;
+use datafusion::execution::{SendableRecordBatchStream, TaskContext};
+use datafusion::functions_aggregate::expr_fn::{count, max, min};
+use datafusion::logical_expr::{Expr, TableProviderFilterPushDown};
+use datafusion::physical_plan::{DisplayAs, DisplayFormatType, ExecutionPlan, PlanProperties};
+use datafusion::prelude::{ident, lit};
+use datafusion::sql::parser::Statement;
+use datafusion::sql::sqlparser::ast;
+use serde::Serialize;
+use tsify_next::Tsify;
+
+use crate::error::{Result, WasmError};
+
+/// The table named by `ANALYZE TABLE name [COMPUTE STATISTICS]`, if `statement` is one.
+pub fn analyze_target(statement: &Statement) -> Option<String> {
+    match statement {
+        Statement::Statement(statement) => match statement.as_ref() {
+            ast::Statement::Analyze { table_name, .. } => Some(table_name.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Scan table `name` once for its row count and the min, max and null count of
+/// every column.
+pub async fn collect(ctx: &SessionContext, name: &str) -> Result<Statistics> {
+    let df = ctx.table(name).await?;
+    let fields = df.schema().fields().clone();
+    let mut aggregates = vec![count(lit(1)).alias("rows")];
+    for (index, field) in fields.iter().enumerate() {
+        let column = ident(field.name());
+        aggregates.push(count(column.clone()).alias(format!("count_{index}")));
+        if !field.data_type().is_nested() {
+            aggregates.push(min(column.clone()).alias(format!("min_{index}")));
+            aggregates.push(max(column).alias(format!("max_{index}")));
+        }
+    }
+    let batches = df.aggregate(vec![], aggregates)?.collect().await?;
+    let batch = batches
+        .first()
+        .ok_or_else(|| WasmError::Other(format!("no statistics computed for {name}")))?;
+    let value = |column: &str| -> Result<Option<ScalarValue>> {
+        match batch.column_by_name(column) {
+            Some(array) => Ok(Some(ScalarValue::try_from_array(array, 0)?)),
+            None => Ok(None),
+        }
+    };
+    let rows = count_value(value("rows")?);
+
+    let column_statistics = (0..fields.len())
+        .map(|index| {
+            let mut statistics = ColumnStatistics::new_unknown();
+            statistics.null_count =
+                Precision::Exact(rows - count_value(value(&format!("count_{index}"))?));
+            if let Some(min) = value(&format!("min_{index}"))?.filter(|min| !min.is_null()) {
+                statistics.min_value = Precision::Exact(min);
+            }
+            if let Some(max) = value(&format!("max_{index}"))?.filter(|max| !max.is_null()) {
+                statistics.max_value = Precision::Exact(max);
+            }
+            Ok(statistics)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Statistics {
+        num_rows: Precision::Exact(rows),
+        total_byte_size: Precision::Absent,
+        column_statistics,
+    })
+}
+
+fn count_value(value: Option<ScalarValue>) -> usize {
+    match value {
+        Some(ScalarValue::Int64(Some(count))) => count as usize,
+        _ => 0,
+    }
+}
+
+/// What `analyze_table` reports about the collected statistics.
+#[derive(Debug, Serialize, Tsify)]
+#[tsify(into_wasm_abi, missing_as_null)]
+pub struct StatisticsReport {
+    pub rows: Option<usize>,
+    pub columns: Vec<ColumnReport>,
+}
+
+#[derive(Debug, Serialize, Tsify)]
+#[tsify(missing_as_null)]
+pub struct ColumnReport {
+    pub name: String,
+    pub null_count: Option<usize>,
+    pub min: Option<String>,
+    pub max: Option<String>,
+}
+
+impl StatisticsReport {
+    pub fn new(schema: &SchemaRef, statistics: &Statistics) -> Self {
+        let columns = schema
+            .fields()
+            .iter()
+            .zip(&statistics.column_statistics)
+            .map(|(field, column)| ColumnReport {
+                name: field.name().clone(),
+                null_count: column.null_count.get_value().copied(),
+                min: column.min_value.get_value().map(ToString::to_string),
+                max: column.max_value.get_value().map(ToString::to_string),
+            })
+            .collect();
+        Self {
+            rows: statistics.num_rows.get_value().copied(),
+            columns,
+        }
+    }
+}
+
+/// A table reporting collected statistics from its scans.
+#[derive(Debug)]
+pub struct StatisticsTable {
+    inner: Arc<dyn TableProvider>,
+    statistics: Statistics,
+}
+
+impl StatisticsTable {
+    /// Wrap `inner`, replacing the statistics of an already analyzed table.
+    pub fn new(inner: Arc<dyn TableProvider>, statistics: Statistics) -> Self {
+        let inner = match inner.as_any().downcast_ref::<StatisticsTable>() {
+            Some(analyzed) => analyzed.inner.clone(),
+            None => inner,
+        };
+        Self { inner, statistics }
+    }
+}
+
+#[async_trait]
+impl TableProvider for StatisticsTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.inner.table_type()
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> datafusion::error::Result<Vec<TableProviderFilterPushDown>> {
+        self.inner.supports_filters_pushdown(filters)
+    }
+
+    fn statistics(&self) -> Option<Statistics> {
+        Some(self.statistics.clone())
+    }
+
+    async fn scan(
+        &self,
+        state: &dyn Session,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
+        let input = self.inner.scan(state, projection, filters, limit).await?;
+        if limit.is_some() {
+            return Ok(input);
+        }
+        let statistics = self.statistics.clone().project(projection);
+        // filtered scans return fewer rows, the bounds still hold
+        let statistics = if filters.is_empty() {
+            statistics
+        } else {
+            statistics.to_inexact()
+        };
+        Ok(Arc::new(StatisticsExec { input, statistics }))
+    }
+}
+
+/// Passes its input through, reporting the collected statistics.
+#[derive(Debug)]
+struct StatisticsExec {
+    input: Arc<dyn ExecutionPlan>,
+    statistics: Statistics,
+}
+
+impl DisplayAs for StatisticsExec {
+    fn fmt_as(&self, _: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "StatisticsExec: rows={}", self.statistics.num_rows)
+    }
+}
+
+impl ExecutionPlan for StatisticsExec {
+    fn name(&self) -> &str {
+        "StatisticsExec"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn properties(&self) -> &PlanProperties {
+        self.input.properties()
+    }
+
+    fn children(&self) -> Vec<&Arc<dyn ExecutionPlan>> {
+        vec![&self.input]
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![true]
+    }
+
+    fn benefits_from_input_partitioning(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        mut children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(StatisticsExec {
+            input: children.remove(0),
+            statistics: self.statistics.clone(),
+        }))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> datafusion::error::Result<SendableRecordBatchStream> {
+        self.input.execute(partition, context)
+    }
+
+    fn statistics(&self) -> datafusion::error::Result<Statistics> {
+        Ok(self.statistics.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::{Int32Array, RecordBatch};
+    use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::datasource::MemTable;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_analyze() {
+        let schema = Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![Some(3), None, Some(1)]))],
+        )
+        .unwrap();
+        let ctx = SessionContext::new();
+        ctx.register_table(
+            "t",
+            Arc::new(MemTable::try_new(schema, vec![vec![batch]]).unwrap()),
+        )
+        .unwrap();
+
+        let statistics = collect(&ctx, "t").await.unwrap();
+        assert_eq!(statistics.num_rows, Precision::Exact(3));
+        let column = &statistics.column_statistics[0];
+        assert_eq!(column.null_count, Precision::Exact(1));
+        assert_eq!(
+            column.min_value,
+            Precision::Exact(ScalarValue::Int32(Some(1)))
+        );
+        assert_eq!(
+            column.max_value,
+            Precision::Exact(ScalarValue::Int32(Some(3)))
+        );
+
+        let provider = ctx.table_provider("t").await.unwrap();
+        let table = Arc::new(StatisticsTable::new(provider, statistics));
+        ctx.deregister_table("t").unwrap();
+        ctx.register_table("t", table).unwrap();
+        let plan = ctx
+            .sql("SELECT v FROM t")
+            .await
+            .unwrap()
+            .create_physical_plan()
+            .await
+            .unwrap();
+        assert_eq!(plan.statistics().unwrap().num_rows, Precision::Exact(3));
+    }
+
+    #[test]
+    fn test_analyze_target() {
+        let statement =
+            datafusion::sql::parser::DFParser::parse_sql("ANALYZE TABLE t COMPUTE STATISTICS")
+                .unwrap()
+                .pop_front()
+                .unwrap();
+        assert_eq!(analyze_target(&statement), Some("t".to_string()));
+    }
+}
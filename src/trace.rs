@@ -0,0 +1,308 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+//! Per-query traces with spans for parsing, planning, optimization, execution and
+//! object store requests, exported as OTLP JSON.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use crate::perf_marks;
+
+/// Traces of the most recent queries kept for export.
+const TRACE_LIMIT: usize = 16;
+
+/// Nanoseconds since the Unix epoch.
+pub fn now() -> i64 {
+    Utc::now().timestamp_nanos_opt().unwrap_or_default()
+}
+
+#[derive(Debug, Clone)]
+pub enum AttributeValue {
+    String(String),
+    Int(i64),
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        AttributeValue::String(value.to_string())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        AttributeValue::String(value)
+    }
+}
+
+impl From<usize> for AttributeValue {
+    fn from(value: usize) -> Self {
+        AttributeValue::Int(value as i64)
+    }
+}
+
+impl From<u64> for AttributeValue {
+    fn from(value: u64) -> Self {
+        AttributeValue::Int(value as i64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    Internal,
+    /// A request to a remote service.
+    Client,
+}
+
+#[derive(Debug, Clone)]
+struct Span {
+    span_id: u64,
+    parent_span_id: Option<u64>,
+    name: String,
+    kind: SpanKind,
+    start: i64,
+    end: i64,
+    attributes: Vec<(&'static str, AttributeValue)>,
+    error: Option<String>,
+}
+
+#[derive(Debug)]
+struct QueryTrace {
+    query_id: u64,
+    trace_id: String,
+    spans: Vec<Span>,
+}
+
+#[derive(Debug, Default)]
+struct TracerState {
+    enabled: bool,
+    /// The trace of the running query, with its root span first.
+    active: Option<QueryTrace>,
+    finished: VecDeque<QueryTrace>,
+}
+
+/// Collects the spans of the running query. Shared by the context and the object
+/// stores, whose requests become spans of the query that caused them.
+#[derive(Debug, Default, Clone)]
+pub struct Tracer {
+    state: Arc<Mutex<TracerState>>,
+}
+
+impl Tracer {
+    pub fn set_enabled(&self, enabled: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.enabled = enabled;
+        if !enabled {
+            state.active = None;
+        }
+    }
+
+    /// Start the trace of query `query_id`, whose root span starts at `start`.
+    pub fn start_query(&self, query_id: u64, sql: &str, start: i64) {
+        let mut state = self.state.lock().unwrap();
+        if !state.enabled {
+            return;
+        }
+        let root = Span {
+            span_id: 1,
+            parent_span_id: None,
+            name: "query".to_string(),
+            kind: SpanKind::Internal,
+            start,
+            end: start,
+            attributes: vec![
+                ("db.system", "datafusion".into()),
+                ("db.statement", sql.into()),
+                ("datafusion.query_id", query_id.into()),
+            ],
+            error: None,
+        };
+        state.active = Some(QueryTrace {
+            query_id,
+            trace_id: format!("{:016x}{:016x}", start as u64, query_id),
+            spans: vec![root],
+        });
+    }
+
+    /// Add a span from `start` to now to the running query's trace, if any.
+    pub fn record(
+        &self,
+        name: &str,
+        kind: SpanKind,
+        start: i64,
+        attributes: Vec<(&'static str, AttributeValue)>,
+        error: Option<String>,
+    ) {
+        self.push(name, kind, start, now(), attributes, error);
+    }
+
+    /// Add an internal span without attributes that ended at `end`.
+    pub fn record_between(&self, name: &str, start: i64, end: i64) {
+        self.push(name, SpanKind::Internal, start, end, Vec::new(), None);
+    }
+
+    fn push(
+        &self,
+        name: &str,
+        kind: SpanKind,
+        start: i64,
+        end: i64,
+        attributes: Vec<(&'static str, AttributeValue)>,
+        error: Option<String>,
+    ) {
+        perf_marks::measure(name, start, end);
+        let mut state = self.state.lock().unwrap();
+        let Some(trace) = &mut state.active else {
+            return;
+        };
+        trace.spans.push(Span {
+            span_id: trace.spans.len() as u64 + 1,
+            parent_span_id: Some(1),
+            name: name.to_string(),
+            kind,
+            start,
+            end,
+            attributes,
+            error,
+        });
+    }
+
+    /// End the running query's trace and keep it for export.
+    pub fn finish_query(&self, error: Option<String>) {
+        let end = now();
+        let mut state = self.state.lock().unwrap();
+        let Some(mut trace) = state.active.take() else {
+            return;
+        };
+        let root = &mut trace.spans[0];
+        root.end = end;
+        root.error = error;
+        if state.finished.len() == TRACE_LIMIT {
+            state.finished.pop_front();
+        }
+        state.finished.push_back(trace);
+    }
+
+    /// The trace of query `query_id`, or of the last traced query, as an OTLP
+    /// `ExportTraceServiceRequest` in JSON.
+    pub fn export(&self, query_id: Option<u64>) -> Option<String> {
+        let state = self.state.lock().unwrap();
+        let trace = match query_id {
+            Some(query_id) => state.finished.iter().find(|t| t.query_id == query_id),
+            None => state.finished.back(),
+        }?;
+        Some(to_otlp(trace).to_string())
+    }
+}
+
+fn to_otlp(trace: &QueryTrace) -> Value {
+    let spans = trace
+        .spans
+        .iter()
+        .map(|span| {
+            let mut value = json!({
+                "traceId": trace.trace_id,
+                "spanId": format!("{:016x}", span.span_id),
+                "name": span.name,
+                // OTLP SpanKind: 1 internal, 3 client
+                "kind": match span.kind {
+                    SpanKind::Internal => 1,
+                    SpanKind::Client => 3,
+                },
+                // 64-bit integers are strings in OTLP JSON
+                "startTimeUnixNano": span.start.to_string(),
+                "endTimeUnixNano": span.end.to_string(),
+                "attributes": span
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| attribute(key, value))
+                    .collect::<Vec<_>>(),
+                // OTLP StatusCode: 1 ok, 2 error
+                "status": match &span.error {
+                    Some(message) => json!({ "code": 2, "message": message }),
+                    None => json!({ "code": 1 }),
+                },
+            });
+            if let Some(parent) = span.parent_span_id {
+                value["parentSpanId"] = format!("{parent:016x}").into();
+            }
+            value
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [attribute("service.name", &"datafusion-wasm".into())],
+            },
+            "scopeSpans": [{
+                "scope": { "name": "datafusion-wasm", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn attribute(key: &str, value: &AttributeValue) -> Value {
+    let value = match value {
+        AttributeValue::String(value) => json!({ "stringValue": value }),
+        AttributeValue::Int(value) => json!({ "intValue": value.to_string() }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled() {
+        let tracer = Tracer::default();
+        tracer.start_query(1, "SELECT 1", now());
+        tracer.record("parse", SpanKind::Internal, now(), vec![], None);
+        tracer.finish_query(None);
+        assert!(tracer.export(None).is_none());
+    }
+
+    #[test]
+    fn test_export() {
+        let tracer = Tracer::default();
+        tracer.set_enabled(true);
+        tracer.start_query(7, "SELECT 1", now());
+        tracer.record("parse", SpanKind::Internal, now(), vec![], None);
+        tracer.record(
+            "GET",
+            SpanKind::Client,
+            now(),
+            vec![("http.response.body.size", 42usize.into())],
+            Some("timeout".to_string()),
+        );
+        tracer.finish_query(None);
+
+        let trace: Value = serde_json::from_str(&tracer.export(Some(7)).unwrap()).unwrap();
+        let spans = &trace["resourceSpans"][0]["scopeSpans"][0]["spans"];
+        assert_eq!(spans.as_array().unwrap().len(), 3);
+        assert_eq!(spans[0]["name"], "query");
+        assert!(spans[0].get("parentSpanId").is_none());
+        assert_eq!(spans[1]["parentSpanId"], spans[0]["spanId"]);
+        assert_eq!(spans[2]["kind"], 3);
+        assert_eq!(spans[2]["status"]["code"], 2);
+        assert_eq!(spans[2]["attributes"][0]["value"]["intValue"], "42");
+        assert!(tracer.export(Some(8)).is_none());
+    }
+}
@@ -18,7 +18,10 @@
 //! A fork of object_store_opendal::OpendalStore that uses unsafe Rust
 //! to erase \![`Send`] and \![`Sync`] for OpenDAL's future.
 
+use std::future::IntoFuture;
+use std::ops::Range;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use async_trait::async_trait;
@@ -27,21 +30,400 @@ use futures::stream::BoxStream;
 use futures::{Future, FutureExt, Stream, StreamExt, TryStreamExt};
 use object_store::path::Path;
 use object_store::{
-    Attributes, GetOptions, GetResult, GetResultPayload, ListResult, MultipartUpload, ObjectMeta,
-    ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+    Attribute, Attributes, GetOptions, GetRange, GetResult, GetResultPayload, ListResult,
+    MultipartUpload, ObjectMeta, ObjectStore, PutMode, PutMultipartOpts, PutOptions, PutPayload,
+    PutResult, Result,
 };
-use opendal::{Entry, FuturesBytesStream, Metadata, Metakey, Operator};
+use opendal::{Buffer, Entry, FuturesBytesStream, Metadata, Metakey, Operator};
 use pin_project::pin_project;
+use reqwest::header::{HeaderName, CONTENT_LENGTH, CONTENT_RANGE, ETAG, LAST_MODIFIED, RANGE};
+
+use crate::cache::RangeCache;
+use crate::progress::IoProgress;
+use crate::quota::Quota;
+use crate::trace::{self, SpanKind, Tracer};
+use crate::whole_file::WholeFiles;
+
+/// Tuning of ranged reads issued by [`OpendalStore`].
+#[derive(Debug, Clone, Copy)]
+pub struct ReadConfig {
+    /// Ranges passed to a single `get_ranges` call that are less than this
+    /// many bytes apart are merged into one request.
+    pub coalesce_window: usize,
+    /// Extra bytes fetched after each requested range when a cache is
+    /// attached, so the following sequential reads are served locally.
+    pub read_ahead: usize,
+}
+
+impl Default for ReadConfig {
+    fn default() -> Self {
+        Self {
+            coalesce_window: 1024 * 1024,
+            read_ahead: 64 * 1024,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct OpendalStore {
     inner: Operator,
+    /// Range cache and the URL prefix used to build cache keys for this store.
+    cache: Option<(Arc<RangeCache>, String)>,
+    read_config: ReadConfig,
+    progress: Option<IoProgress>,
+    tracer: Option<Tracer>,
+    /// Base URL of an HTTP service, used to stat objects with a ranged `GET`
+    /// on hosts that reject `HEAD`.
+    http_endpoint: Option<String>,
+    /// Whole-object downloads from HTTP servers that ignore `Range` headers.
+    whole_files: Option<Arc<WholeFiles>>,
+    /// Checked before every write to origin storage. Remote stores have their own
+    /// limits, so none is set for them.
+    quota: Option<Quota>,
 }
 
 impl OpendalStore {
     /// Create OpendalStore by given Operator.
     pub fn new(op: Operator) -> Self {
-        Self { inner: op }
+        Self {
+            inner: op,
+            cache: None,
+            read_config: ReadConfig::default(),
+            progress: None,
+            tracer: None,
+            http_endpoint: None,
+            whole_files: None,
+            quota: None,
+        }
+    }
+
+    /// Refuse writes that don't fit `quota`, for stores backed by the origin's
+    /// storage such as the origin private file system. No registered scheme is yet.
+    #[allow(dead_code)]
+    pub fn with_quota(mut self, quota: Quota) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    pub fn with_read_config(mut self, read_config: ReadConfig) -> Self {
+        self.read_config = read_config;
+        self
+    }
+
+    /// Serve ranged reads from `cache`. `prefix` identifies the backing
+    /// service (e.g. `https://example.com:443`) and is prepended to object
+    /// paths to build cache keys.
+    pub fn with_cache(mut self, cache: Arc<RangeCache>, prefix: String) -> Self {
+        self.cache = Some((cache, prefix));
+        self
+    }
+
+    pub fn with_progress(mut self, progress: IoProgress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn with_tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Add a request for `location` to the running query's trace, with the response
+    /// size or error in `result`.
+    fn trace_request(
+        &self,
+        method: &str,
+        location: &Path,
+        range: Option<&Range<usize>>,
+        start: i64,
+        result: std::result::Result<usize, String>,
+    ) {
+        let Some(tracer) = &self.tracer else {
+            return;
+        };
+        let mut attributes = vec![
+            ("http.request.method", method.into()),
+            ("object_store.location", location.to_string().into()),
+        ];
+        if let Some(endpoint) = &self.http_endpoint {
+            attributes.push(("url.full", format!("{endpoint}/{location}").into()));
+        }
+        if let Some(range) = range {
+            attributes.push((
+                "http.request.header.range",
+                format!("bytes={}-{}", range.start, range.end.saturating_sub(1)).into(),
+            ));
+        }
+        let error = match result {
+            Ok(size) => {
+                attributes.push(("http.response.body.size", size.into()));
+                None
+            }
+            Err(err) => Some(err),
+        };
+        tracer.record(method, SpanKind::Client, start, attributes, error);
+    }
+
+    pub fn with_http_endpoint(mut self, endpoint: String) -> Self {
+        self.http_endpoint = Some(endpoint);
+        self
+    }
+
+    /// Fall back to downloading whole objects, kept in `whole_files`, when the
+    /// HTTP endpoint ignores `Range` headers.
+    pub fn with_whole_files(mut self, whole_files: Arc<WholeFiles>) -> Self {
+        self.whole_files = Some(whole_files);
+        self
+    }
+
+    /// Stat an object with `GET` + `Range: bytes=0-0` instead of `HEAD`, reading the
+    /// size from `Content-Range` (or `Content-Length` if the range was ignored).
+    async fn head_via_ranged_get(&self, endpoint: &str, location: &Path) -> Result<ObjectMeta> {
+        let url = format!("{endpoint}/{location}");
+        let response = ForceSend::new(
+            reqwest::Client::new()
+                .get(&url)
+                .header(RANGE, "bytes=0-0")
+                .send(),
+        )
+        .await
+        .map_err(|err| object_store::Error::Generic {
+            store: "HTTP",
+            source: Box::new(err),
+        })?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(object_store::Error::NotFound {
+                path: location.to_string(),
+                source: format!("{url} not found").into(),
+            });
+        }
+        let response = response
+            .error_for_status()
+            .map_err(|err| object_store::Error::Generic {
+                store: "HTTP",
+                source: Box::new(err),
+            })?;
+
+        let header = |name: HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        let size = match response.status() {
+            reqwest::StatusCode::PARTIAL_CONTENT => {
+                header(CONTENT_RANGE).and_then(parse_content_range)
+            }
+            _ => header(CONTENT_LENGTH).and_then(|length| length.parse().ok()),
+        }
+        .ok_or_else(|| object_store::Error::Generic {
+            store: "HTTP",
+            source: format!("cannot determine the size of {url}").into(),
+        })?;
+
+        Ok(ObjectMeta {
+            location: location.clone(),
+            last_modified: header(LAST_MODIFIED)
+                .and_then(|value| chrono::DateTime::parse_from_rfc2822(value).ok())
+                .map(|value| value.with_timezone(&chrono::Utc))
+                .unwrap_or_default(),
+            size,
+            e_tag: header(ETAG).map(str::to_string),
+            version: None,
+        })
+    }
+
+    /// Fail with `AlreadyExists` if `location` exists.
+    async fn ensure_absent(&self, location: &Path) -> Result<()> {
+        match ForceSend::new(self.inner.stat(location.as_ref())).await {
+            Ok(_) => Err(object_store::Error::AlreadyExists {
+                path: location.to_string(),
+                source: format!("{location} already exists").into(),
+            }),
+            Err(err) if err.kind() == opendal::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(format_object_store_error(err, location.as_ref())),
+        }
+    }
+
+    /// Drop cached ranges of an object that was just overwritten or removed.
+    fn invalidate_cached(&self, location: &Path) {
+        if let Some((cache, prefix)) = &self.cache {
+            cache.invalidate(&format!("{prefix}/{location}"));
+        }
+        if let (Some(whole_files), Some(endpoint)) = (&self.whole_files, &self.http_endpoint) {
+            whole_files.invalidate(&format!("{endpoint}/{location}"));
+        }
+    }
+
+    /// Read `range` of an object of `size` bytes, if known.
+    async fn read_range(
+        &self,
+        location: &Path,
+        range: Range<usize>,
+        size: Option<usize>,
+    ) -> Result<Bytes> {
+        let whole_files = match (&self.whole_files, &self.http_endpoint) {
+            (Some(whole_files), Some(endpoint)) => {
+                let key = format!("{endpoint}/{location}");
+                if let Some(data) = whole_files.get(&key).filter(|data| match size {
+                    Some(size) => data.len() == size,
+                    None => data.len() >= range.end,
+                }) {
+                    return Ok(data.slice(range));
+                }
+                if whole_files.ignores_ranges(endpoint) {
+                    let size = match size {
+                        Some(size) => size,
+                        None => self.head(location).await?.size,
+                    };
+                    let data = self.read_whole(location, size, whole_files).await?;
+                    whole_files.insert(endpoint, &key, data.clone());
+                    return Ok(data.slice(range));
+                }
+                Some((whole_files, endpoint, key))
+            }
+            _ => None,
+        };
+
+        let start = trace::now();
+        let buffer = ForceSend::new(
+            self.inner
+                .read_with(location.as_ref())
+                .range(range.start as u64..range.end as u64)
+                .into_future(),
+        )
+        .await
+        .map_err(|err| format_object_store_error(err, location.as_ref()));
+        let result = buffer
+            .as_ref()
+            .map(Buffer::len)
+            .map_err(|err| err.to_string());
+        self.trace_request("GET", location, Some(&range), start, result);
+        let buffer = buffer?;
+        if let Some(progress) = &self.progress {
+            let expected = size.unwrap_or_default() as u64;
+            progress.record(location.as_ref(), buffer.len() as u64, expected);
+        }
+        let data = buffer.to_bytes();
+
+        // the server ignored the Range header and sent the whole object
+        let whole = match size {
+            Some(size) => data.len() == size,
+            None => data.len() > range.len(),
+        };
+        if data.len() != range.len() && whole && data.len() >= range.end {
+            if let Some((whole_files, endpoint, key)) = whole_files {
+                whole_files.insert(endpoint, &key, data.clone());
+            }
+            return Ok(data.slice(range));
+        }
+        Ok(data)
+    }
+
+    /// Fail if a write of `bytes` doesn't fit the quota, when there is one.
+    async fn ensure_quota(&self, bytes: usize) -> Result<()> {
+        let Some(quota) = &self.quota else {
+            return Ok(());
+        };
+        ForceSend::new(quota.ensure_available(bytes as u64))
+            .await
+            .map_err(|err| object_store::Error::Generic {
+                store: "OpenDAL",
+                source: Box::new(err),
+            })
+    }
+
+    /// Read `range` without fetching the metadata first. The result's metadata only
+    /// has the location and the size the range implies.
+    async fn get_uncached(&self, location: &Path, range: Range<usize>) -> Result<GetResult> {
+        let bytes = self.read_range(location, range.clone(), None).await?;
+        let meta = ObjectMeta {
+            location: location.clone(),
+            last_modified: Default::default(),
+            size: range.end,
+            e_tag: None,
+            version: None,
+        };
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(
+                futures::stream::once(async move { Ok(bytes) }).boxed(),
+            ),
+            range,
+            meta,
+            attributes: Attributes::default(),
+        })
+    }
+
+    /// Download a whole object from a server known to ignore ranges.
+    async fn read_whole(
+        &self,
+        location: &Path,
+        size: usize,
+        whole_files: &WholeFiles,
+    ) -> Result<Bytes> {
+        if size > whole_files.limit() {
+            return Err(object_store::Error::Generic {
+                store: "HTTP",
+                source: format!(
+                    "{location} is {size} bytes and the server doesn't support range \
+                     requests, which is over the {} byte whole-file download limit",
+                    whole_files.limit()
+                )
+                .into(),
+            });
+        }
+
+        let start = trace::now();
+        let buffer = ForceSend::new(self.inner.read(location.as_ref()))
+            .await
+            .map_err(|err| format_object_store_error(err, location.as_ref()));
+        let result = buffer
+            .as_ref()
+            .map(Buffer::len)
+            .map_err(|err| err.to_string());
+        self.trace_request("GET", location, None, start, result);
+        let buffer = buffer?;
+        if let Some(progress) = &self.progress {
+            progress.record(location.as_ref(), buffer.len() as u64, size as u64);
+        }
+        Ok(buffer.to_bytes())
+    }
+
+    /// Check whether the cached copy of `location` is still current with a
+    /// conditional `If-None-Match` request, so an unchanged object costs a
+    /// `304 Not Modified` instead of a download.
+    async fn revalidate(
+        &self,
+        location: &Path,
+        cache: &RangeCache,
+        key: &str,
+        cached: ObjectMeta,
+    ) -> Result<ObjectMeta> {
+        let Some(etag) = cached.e_tag.clone() else {
+            cache.invalidate(key);
+            return self.head(location).await;
+        };
+
+        let stat = self
+            .inner
+            .stat_with(location.as_ref())
+            .if_none_match(&etag)
+            .into_future();
+        match ForceSend::new(stat).await {
+            Ok(meta) => {
+                cache.invalidate(key);
+                let mut meta = format_object_meta(location.as_ref(), &meta);
+                // some services omit the ETag on conditional responses
+                meta.e_tag.get_or_insert(etag);
+                Ok(meta)
+            }
+            Err(err) if err.kind() == opendal::ErrorKind::ConditionNotMatch => {
+                cache.mark_validated(key);
+                Ok(cached)
+            }
+            Err(err) => Err(format_object_store_error(err, location.as_ref())),
+        }
     }
 }
 
@@ -54,29 +436,52 @@ impl std::fmt::Display for OpendalStore {
 #[async_trait]
 impl ObjectStore for OpendalStore {
     async fn put(&self, location: &Path, payload: PutPayload) -> Result<PutResult> {
-        ForceSend::new(
-            self.inner
-                .write(location.as_ref(), payload.as_ref()[0].clone()),
-        )
-        .await
-        .map_err(|err| format_object_store_error(err, location.as_ref()))?;
-        Ok(PutResult {
-            e_tag: None,
-            version: None,
-        })
+        self.put_opts(location, payload, PutOptions::default())
+            .await
     }
 
+    /// Supports `PutMode::Overwrite` and `PutMode::Create`, the latter checked
+    /// with a separate existence request so it is not atomic.
     async fn put_opts(
         &self,
-        _location: &Path,
-        _bytes: PutPayload,
-        _opts: PutOptions,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
     ) -> Result<PutResult> {
-        Err(object_store::Error::NotSupported {
-            source: Box::new(opendal::Error::new(
-                opendal::ErrorKind::Unsupported,
-                "put_opts is not implemented so far",
-            )),
+        match opts.mode {
+            PutMode::Overwrite => {}
+            PutMode::Create => self.ensure_absent(location).await?,
+            PutMode::Update(_) => {
+                return Err(object_store::Error::NotImplemented);
+            }
+        }
+        self.ensure_quota(payload.content_length()).await?;
+
+        let buffer = Buffer::from(payload.as_ref().to_vec());
+        let mut write = self.inner.write_with(location.as_ref(), buffer);
+        for (attribute, value) in opts.attributes.iter() {
+            write = match attribute {
+                Attribute::ContentType => write.content_type(value.as_ref()),
+                Attribute::ContentDisposition => write.content_disposition(value.as_ref()),
+                Attribute::CacheControl => write.cache_control(value.as_ref()),
+                _ => {
+                    return Err(object_store::Error::NotSupported {
+                        source: Box::new(opendal::Error::new(
+                            opendal::ErrorKind::Unsupported,
+                            format!("attribute {attribute:?} is not supported so far"),
+                        )),
+                    })
+                }
+            };
+        }
+        ForceSend::new(write.into_future())
+            .await
+            .map_err(|err| format_object_store_error(err, location.as_ref()))?;
+        self.invalidate_cached(location);
+
+        Ok(PutResult {
+            e_tag: None,
+            version: None,
         })
     }
 
@@ -97,27 +502,98 @@ impl ObjectStore for OpendalStore {
         })
     }
 
-    async fn get_opts(&self, _location: &Path, _options: GetOptions) -> Result<GetResult> {
-        Err(object_store::Error::NotSupported {
-            source: Box::new(opendal::Error::new(
-                opendal::ErrorKind::Unsupported,
-                "get_opts is not implemented so far",
-            )),
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let cache_key = self
+            .cache
+            .as_ref()
+            .filter(|(cache, _)| cache.is_enabled())
+            .map(|(cache, prefix)| (cache, format!("{prefix}/{location}")));
+
+        // without a cache or conditions, a bounded read needs no metadata
+        let conditional = options.if_match.is_some()
+            || options.if_none_match.is_some()
+            || options.if_modified_since.is_some()
+            || options.if_unmodified_since.is_some()
+            || options.version.is_some();
+        if let (None, false, false, Some(GetRange::Bounded(range))) =
+            (&cache_key, conditional, options.head, &options.range)
+        {
+            return self.get_uncached(location, range.clone()).await;
+        }
+
+        let meta = match &cache_key {
+            Some((cache, key)) => match cache.meta(key) {
+                Some((meta, false)) => meta,
+                Some((meta, true)) => self.revalidate(location, cache, key, meta).await?,
+                None => self.head(location).await?,
+            },
+            None => self.head(location).await?,
+        };
+        // `if_match` and the like are checked against the cached metadata, which is as
+        // fresh as the revalidation interval
+        check_preconditions(&options, &meta)?;
+        if options.version.is_some() && options.version != meta.version {
+            return Err(object_store::Error::NotSupported {
+                source: Box::new(opendal::Error::new(
+                    opendal::ErrorKind::Unsupported,
+                    "reading a version other than the current one is not supported so far",
+                )),
+            });
+        }
+
+        let range = match &options.range {
+            Some(range) => resolve_range(range, meta.size, location)?,
+            None => 0..meta.size,
+        };
+        if options.head {
+            return Ok(GetResult {
+                payload: GetResultPayload::Stream(futures::stream::empty().boxed()),
+                range,
+                meta,
+                attributes: Attributes::default(),
+            });
+        }
+
+        let cached = cache_key
+            .as_ref()
+            .and_then(|(cache, key)| cache.get(key, &range));
+        let bytes = match (cached, &cache_key) {
+            (Some(bytes), _) => bytes,
+            (None, Some((cache, key))) => {
+                let fetch_end = (range.end + self.read_config.read_ahead).min(meta.size);
+                let fetched = self
+                    .read_range(location, range.start..fetch_end, Some(meta.size))
+                    .await?;
+                cache.insert(key, meta.clone(), range.start..fetch_end, fetched.clone());
+                fetched.slice(0..range.len().min(fetched.len()))
+            }
+            (None, None) => {
+                self.read_range(location, range.clone(), Some(meta.size))
+                    .await?
+            }
+        };
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(
+                futures::stream::once(async move { Ok(bytes) }).boxed(),
+            ),
+            range,
+            meta,
+            attributes: Attributes::default(),
         })
     }
 
-    async fn get(&self, location: &Path) -> Result<GetResult> {
-        let meta = ForceSend::new(self.inner.stat(location.as_ref()))
-            .await
-            .map_err(|err| format_object_store_error(err, location.as_ref()))?;
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        object_store::coalesce_ranges(
+            ranges,
+            |range| self.get_range(location, range),
+            self.read_config.coalesce_window,
+        )
+        .await
+    }
 
-        let meta = ObjectMeta {
-            location: location.clone(),
-            last_modified: meta.last_modified().unwrap_or_default(),
-            size: meta.content_length() as usize,
-            e_tag: meta.etag().map(|x| x.to_string()),
-            version: meta.version().map(|x| x.to_string()),
-        };
+    async fn get(&self, location: &Path) -> Result<GetResult> {
+        let meta = self.head(location).await?;
         let r = ForceSend::new(self.inner.reader(location.as_ref()))
             .await
             .map_err(|err| format_object_store_error(err, location.as_ref()))?;
@@ -127,6 +603,10 @@ impl ObjectStore for OpendalStore {
                 inner: ForceSend::new(r.into_bytes_stream(0..meta.size as u64))
                     .await
                     .unwrap(),
+                progress: self
+                    .progress
+                    .clone()
+                    .map(|progress| (progress, location.to_string(), meta.size as u64)),
             }))),
             range: (0..meta.size),
             meta,
@@ -135,9 +615,24 @@ impl ObjectStore for OpendalStore {
     }
 
     async fn head(&self, location: &Path) -> Result<ObjectMeta> {
-        let meta = ForceSend::new(self.inner.stat(location.as_ref()))
-            .await
-            .map_err(|err| format_object_store_error(err, location.as_ref()))?;
+        let start = trace::now();
+        let stat = ForceSend::new(self.inner.stat(location.as_ref())).await;
+        let result = stat.as_ref().map(|_| 0).map_err(|err| err.to_string());
+        self.trace_request("HEAD", location, None, start, result);
+        let meta = match stat {
+            Ok(meta) => meta,
+            Err(err) if err.kind() != opendal::ErrorKind::NotFound => {
+                // some static hosts reject HEAD, retry with a one-byte ranged GET
+                let Some(endpoint) = &self.http_endpoint else {
+                    return Err(format_object_store_error(err, location.as_ref()));
+                };
+                return self
+                    .head_via_ranged_get(endpoint, location)
+                    .await
+                    .map_err(|_| format_object_store_error(err, location.as_ref()));
+            }
+            Err(err) => return Err(format_object_store_error(err, location.as_ref())),
+        };
 
         Ok(ObjectMeta {
             location: location.clone(),
@@ -152,6 +647,7 @@ impl ObjectStore for OpendalStore {
         ForceSend::new(self.inner.delete(location.as_ref()))
             .await
             .map_err(|err| format_object_store_error(err, location.as_ref()))?;
+        self.invalidate_cached(location);
 
         Ok(())
     }
@@ -257,31 +753,48 @@ impl ObjectStore for OpendalStore {
         })
     }
 
-    async fn copy(&self, _from: &Path, _to: &Path) -> Result<()> {
-        Err(object_store::Error::NotSupported {
-            source: Box::new(opendal::Error::new(
-                opendal::ErrorKind::Unsupported,
-                "copy is not implemented so far",
-            )),
-        })
+    /// Copy natively when the service supports it, otherwise read the whole
+    /// object and write it back under the new name.
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        if self.inner.info().full_capability().copy {
+            ForceSend::new(self.inner.copy(from.as_ref(), to.as_ref()))
+                .await
+                .map_err(|err| format_object_store_error(err, from.as_ref()))?;
+        } else {
+            let buffer = ForceSend::new(self.inner.read(from.as_ref()))
+                .await
+                .map_err(|err| format_object_store_error(err, from.as_ref()))?;
+            self.ensure_quota(buffer.len()).await?;
+            ForceSend::new(self.inner.write(to.as_ref(), buffer))
+                .await
+                .map_err(|err| format_object_store_error(err, to.as_ref()))?;
+        }
+        self.invalidate_cached(to);
+
+        Ok(())
     }
 
-    async fn rename(&self, _from: &Path, _to: &Path) -> Result<()> {
-        Err(object_store::Error::NotSupported {
-            source: Box::new(opendal::Error::new(
-                opendal::ErrorKind::Unsupported,
-                "rename is not implemented so far",
-            )),
-        })
+    /// Rename natively when the service supports it, otherwise copy and delete
+    /// the source.
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        if self.inner.info().full_capability().rename {
+            ForceSend::new(self.inner.rename(from.as_ref(), to.as_ref()))
+                .await
+                .map_err(|err| format_object_store_error(err, from.as_ref()))?;
+            self.invalidate_cached(to);
+        } else {
+            self.copy(from, to).await?;
+            self.delete(from).await?;
+        }
+        self.invalidate_cached(from);
+
+        Ok(())
     }
 
-    async fn copy_if_not_exists(&self, _from: &Path, _to: &Path) -> Result<()> {
-        Err(object_store::Error::NotSupported {
-            source: Box::new(opendal::Error::new(
-                opendal::ErrorKind::Unsupported,
-                "copy_if_not_exists is not implemented so far",
-            )),
-        })
+    /// Not atomic: the existence check and the copy are separate requests.
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.ensure_absent(to).await?;
+        self.copy(from, to).await
     }
 }
 
@@ -299,6 +812,15 @@ fn format_object_store_error(err: opendal::Error, path: &str) -> object_store::E
             path: path.to_string(),
             source: Box::new(err),
         },
+        ErrorKind::PermissionDenied => object_store::Error::PermissionDenied {
+            path: path.to_string(),
+            source: Box::new(err),
+        },
+        // services report 401 as unexpected, with the response status in the context
+        _ if err.to_string().contains("status: 401") => object_store::Error::Unauthenticated {
+            path: path.to_string(),
+            source: Box::new(err),
+        },
         kind => object_store::Error::Generic {
             store: kind.into_static(),
             source: Box::new(err),
@@ -306,6 +828,27 @@ fn format_object_store_error(err: opendal::Error, path: &str) -> object_store::E
     }
 }
 
+fn resolve_range(range: &GetRange, size: usize, location: &Path) -> Result<Range<usize>> {
+    let resolved = match range {
+        GetRange::Bounded(range) => range.start..range.end.min(size),
+        GetRange::Offset(offset) => *offset..size,
+        GetRange::Suffix(suffix) => size.saturating_sub(*suffix)..size,
+    };
+    if resolved.start > resolved.end {
+        return Err(object_store::Error::Generic {
+            store: "OpenDAL",
+            source: format!("invalid range {range:?} for {location} of size {size}").into(),
+        });
+    }
+    Ok(resolved)
+}
+
+/// Total size from a `Content-Range` header such as `bytes 0-0/1234`.
+pub(crate) fn parse_content_range(value: &str) -> Option<usize> {
+    let (_, total) = value.strip_prefix("bytes ")?.rsplit_once('/')?;
+    total.trim().parse().ok()
+}
+
 fn format_object_meta(path: &str, meta: &Metadata) -> ObjectMeta {
     ObjectMeta {
         location: path.into(),
@@ -325,24 +868,175 @@ async fn try_format_object_meta(res: Result<Entry, opendal::Error>) -> Result<Ob
 
 struct OpendalReader {
     inner: FuturesBytesStream,
+    /// Progress tracker, object location and expected size.
+    progress: Option<(IoProgress, String, u64)>,
 }
 
 impl Stream for OpendalReader {
     type Item = Result<Bytes>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let inner = Pin::new(&mut self.get_mut().inner);
-        inner
-            .poll_next(cx)
-            .map_err(|err| object_store::Error::Generic {
-                store: "IoError",
-                source: Box::new(err),
-            })
+        let this = self.get_mut();
+        let poll =
+            Pin::new(&mut this.inner)
+                .poll_next(cx)
+                .map_err(|err| object_store::Error::Generic {
+                    store: "IoError",
+                    source: Box::new(err),
+                });
+        if let (Poll::Ready(Some(Ok(bytes))), Some((progress, location, expected))) =
+            (&poll, &this.progress)
+        {
+            progress.record(location, bytes.len() as u64, *expected);
+        }
+        poll
+    }
+}
+
+/// Fail like a server would if the conditions of `options` don't hold for `meta`,
+/// the checks `object_store`'s in-memory store does.
+fn check_preconditions(options: &GetOptions, meta: &ObjectMeta) -> Result<()> {
+    // no ETag never matches
+    let etag = meta.e_tag.as_deref().unwrap_or("*");
+    let matches = |tags: &str| tags.split(',').map(str::trim).any(|tag| tag == etag);
+
+    if let Some(tags) = &options.if_match {
+        if tags != "*" && !matches(tags) {
+            return Err(object_store::Error::Precondition {
+                path: meta.location.to_string(),
+                source: format!("{etag} does not match {tags}").into(),
+            });
+        }
+    } else if let Some(date) = options.if_unmodified_since {
+        if meta.last_modified > date {
+            return Err(object_store::Error::Precondition {
+                path: meta.location.to_string(),
+                source: format!("modified at {}, after {date}", meta.last_modified).into(),
+            });
+        }
+    }
+
+    if let Some(tags) = &options.if_none_match {
+        if tags == "*" || matches(tags) {
+            return Err(object_store::Error::NotModified {
+                path: meta.location.to_string(),
+                source: format!("{etag} matches {tags}").into(),
+            });
+        }
+    } else if let Some(date) = options.if_modified_since {
+        if meta.last_modified <= date {
+            return Err(object_store::Error::NotModified {
+                path: meta.location.to_string(),
+                source: format!("modified at {}, not after {date}", meta.last_modified).into(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(parse_content_range("bytes 0-0/1234"), Some(1234));
+        assert_eq!(parse_content_range("bytes */1234"), Some(1234));
+        assert_eq!(parse_content_range("bytes 0-0/*"), None);
+        assert_eq!(parse_content_range("items 0-0/10"), None);
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn test_get_preconditions() {
+        use chrono::{Duration, Utc};
+
+        let root = std::env::temp_dir().join(format!("preconditions-{}", std::process::id()));
+        let operator = Operator::new(opendal::services::Fs::default().root(root.to_str().unwrap()))
+            .unwrap()
+            .finish();
+        let store =
+            OpendalStore::new(operator).with_cache(Arc::new(RangeCache::new(0)), "fs".into());
+        let location = Path::from("t.csv");
+        store
+            .put(&location, PutPayload::from_static(b"a\n1\n"))
+            .await
+            .unwrap();
+
+        let get = |options| store.get_opts(&location, options);
+        let modified = get(GetOptions {
+            if_modified_since: Some(Utc::now() + Duration::hours(1)),
+            ..Default::default()
+        });
+        assert!(matches!(
+            modified.await,
+            Err(object_store::Error::NotModified { .. })
+        ));
+        let unmodified = get(GetOptions {
+            if_unmodified_since: Some(Utc::now() - Duration::hours(1)),
+            ..Default::default()
+        });
+        assert!(matches!(
+            unmodified.await,
+            Err(object_store::Error::Precondition { .. })
+        ));
+        let version = get(GetOptions {
+            version: Some("1".to_string()),
+            ..Default::default()
+        });
+        assert!(matches!(
+            version.await,
+            Err(object_store::Error::NotSupported { .. })
+        ));
+        let bytes = get(GetOptions::default())
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        assert_eq!(bytes.as_ref(), b"a\n1\n");
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(feature = "fs")]
+    #[tokio::test]
+    async fn test_put_refused_over_quota() {
+        use crate::error::WasmError;
+        use crate::quota::StorageEstimate;
+
+        let root = std::env::temp_dir().join(format!("quota-{}", std::process::id()));
+        let operator = Operator::new(opendal::services::Fs::default().root(root.to_str().unwrap()))
+            .unwrap()
+            .finish();
+        let store = OpendalStore::new(operator).with_quota(Quota::Fixed(StorageEstimate {
+            usage: 90,
+            quota: 100,
+        }));
+        let location = Path::from("t.csv");
+
+        let err = store
+            .put(&location, PutPayload::from_static(b"a\n1\n2\n3\n4\n5\n"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            WasmError::from(datafusion::error::DataFusionError::from(err)),
+            WasmError::QuotaExceeded {
+                requested: 12,
+                available: 10
+            }
+        ));
+        assert!(store.head(&location).await.is_err());
+
+        store
+            .put(&location, PutPayload::from_static(b"a\n1\n"))
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(root).unwrap();
     }
 }
 
 #[pin_project]
-struct ForceSend<T> {
+pub(crate) struct ForceSend<T> {
     #[pin]
     item: T,
 }
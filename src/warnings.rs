@@ -0,0 +1,228 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Non-fatal notices about a query or ingest, returned next to its result.
+
+use std::sync::{Arc, Mutex};
+
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::config::ConfigOptions;
+use datafusion::common::tree_node::{TreeNode, TreeNodeRecursion};
+use datafusion::common::DFSchema;
+use datafusion::logical_expr::expr::{Cast, TryCast};
+use datafusion::logical_expr::{Expr, ExprSchemable, LogicalPlan};
+use datafusion::optimizer::analyzer::type_coercion::TypeCoercion;
+use datafusion::optimizer::AnalyzerRule;
+use serde::Serialize;
+use tsify_next::Tsify;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Tsify)]
+#[serde(rename_all = "snake_case")]
+pub enum WarningKind {
+    /// Type coercion cast a column to make an expression type check.
+    ImplicitCast,
+    /// A schema was inferred from a sample of the data only.
+    SchemaInference,
+    /// Malformed input records were skipped.
+    MalformedRecords,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Tsify)]
+#[tsify(into_wasm_abi)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(kind: WarningKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+/// Warnings of the running call, cleared when the next one starts.
+#[derive(Debug, Default, Clone)]
+pub struct Warnings {
+    warnings: Arc<Mutex<Vec<Warning>>>,
+}
+
+impl Warnings {
+    pub fn clear(&self) {
+        self.warnings.lock().unwrap().clear();
+    }
+
+    /// Add `warnings`, skipping those already reported.
+    pub fn extend(&self, warnings: impl IntoIterator<Item = Warning>) {
+        let mut current = self.warnings.lock().unwrap();
+        for warning in warnings {
+            if !current.contains(&warning) {
+                current.push(warning);
+            }
+        }
+    }
+
+    pub fn push(&self, warning: Warning) {
+        self.extend([warning]);
+    }
+
+    pub fn snapshot(&self) -> Vec<Warning> {
+        self.warnings.lock().unwrap().clone()
+    }
+}
+
+/// Casts of non-literal expressions between type families, such as string to
+/// number, that type coercion adds to `plan`, a plan before analysis. Widening
+/// casts and casts written in the SQL aren't reported.
+pub fn implicit_casts(plan: &LogicalPlan, config: &ConfigOptions) -> Vec<Warning> {
+    let Ok(coerced) = TypeCoercion::new().analyze(plan.clone(), config) else {
+        // the query fails later with the actual error
+        return Vec::new();
+    };
+    let explicit = casts(plan);
+    casts(&coerced)
+        .into_iter()
+        .filter(|cast| !explicit.contains(cast))
+        .map(|cast| {
+            Warning::new(
+                WarningKind::ImplicitCast,
+                format!("implicit cast of {cast}"),
+            )
+        })
+        .collect()
+}
+
+/// `<expr> (<type>) to <type>` of every cast between type families in `plan`.
+fn casts(plan: &LogicalPlan) -> Vec<String> {
+    let mut casts = Vec::new();
+    let _ = plan.apply_with_subqueries(|node| {
+        let schema = input_schema(node);
+        node.apply_expressions(|expr| {
+            expr.apply(|expr| {
+                let (Expr::Cast(Cast { expr, data_type })
+                | Expr::TryCast(TryCast { expr, data_type })) = expr
+                else {
+                    return Ok(TreeNodeRecursion::Continue);
+                };
+                if matches!(**expr, Expr::Literal(_)) {
+                    return Ok(TreeNodeRecursion::Continue);
+                }
+                let Ok(from) = expr.get_type(&schema) else {
+                    return Ok(TreeNodeRecursion::Continue);
+                };
+                let cast = format!("{expr} ({from}) to {data_type}");
+                if !widens(&from, data_type) && !casts.contains(&cast) {
+                    casts.push(cast);
+                }
+                Ok(TreeNodeRecursion::Continue)
+            })
+        })
+    });
+    casts
+}
+
+/// The schema the expressions of `node` are evaluated against.
+fn input_schema(node: &LogicalPlan) -> DFSchema {
+    let inputs = node.inputs();
+    if inputs.is_empty() {
+        return node.schema().as_ref().clone();
+    }
+    let mut schema = DFSchema::empty();
+    for input in inputs {
+        schema.merge(input.schema());
+    }
+    schema
+}
+
+#[derive(PartialEq, Eq)]
+enum TypeFamily {
+    Integer,
+    Float,
+    String,
+    Temporal,
+    Other,
+}
+
+/// Whether a cast `from` -> `to` stays within a type family or widens an integer,
+/// which integers do to floats and decimals without surprises.
+fn widens(from: &DataType, to: &DataType) -> bool {
+    match (family(from), family(to)) {
+        (TypeFamily::Integer, TypeFamily::Float) => true,
+        (from, to) => from == to,
+    }
+}
+
+fn family(data_type: &DataType) -> TypeFamily {
+    match data_type {
+        DataType::Dictionary(_, value) => family(value),
+        data_type if data_type.is_integer() => TypeFamily::Integer,
+        data_type if data_type.is_numeric() => TypeFamily::Float,
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => TypeFamily::String,
+        data_type if data_type.is_temporal() => TypeFamily::Temporal,
+        _ => TypeFamily::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::prelude::SessionContext;
+
+    use super::*;
+
+    async fn warnings(sql: &str) -> Vec<Warning> {
+        let ctx = SessionContext::new();
+        ctx.sql("CREATE TABLE t (a INT, b VARCHAR)")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+        let state = ctx.state();
+        let plan = state.create_logical_plan(sql).await.unwrap();
+        implicit_casts(&plan, state.config_options())
+    }
+
+    #[tokio::test]
+    async fn test_implicit_casts() {
+        let found = warnings("SELECT * FROM t WHERE a = b").await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].kind, WarningKind::ImplicitCast);
+        assert!(
+            found[0].message.contains("t.a (Int32) to Utf8"),
+            "{found:?}"
+        );
+
+        assert!(warnings("SELECT * FROM t WHERE CAST(a AS VARCHAR) = b")
+            .await
+            .is_empty());
+        let found = warnings("SELECT a + 1, a + 1.5 FROM t WHERE b = 'x'").await;
+        assert!(found.is_empty(), "{found:?}");
+    }
+
+    #[test]
+    fn test_extend_skips_duplicates() {
+        let warnings = Warnings::default();
+        let warning = Warning::new(WarningKind::SchemaInference, "sampled");
+        warnings.push(warning.clone());
+        warnings.push(warning);
+        assert_eq!(warnings.snapshot().len(), 1);
+        warnings.clear();
+        assert!(warnings.snapshot().is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Objects downloaded in full from servers that ignore `Range` headers.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use bytes::Bytes;
+
+/// Default largest object downloaded in full when ranges are unsupported.
+pub const DEFAULT_WHOLE_FILE_LIMIT: usize = 256 * 1024 * 1024;
+
+#[derive(Debug)]
+struct WholeFilesState {
+    limit: usize,
+    used: usize,
+    /// Downloaded objects by `{endpoint}/{location}`.
+    files: HashMap<String, Bytes>,
+    /// Endpoints seen answering a range request with the whole object.
+    endpoints: HashSet<String>,
+}
+
+/// Keeps whole-object downloads so later ranged reads of the same object are
+/// served from memory instead of downloading it again.
+#[derive(Debug)]
+pub struct WholeFiles {
+    state: Mutex<WholeFilesState>,
+}
+
+impl Default for WholeFiles {
+    fn default() -> Self {
+        Self::new(DEFAULT_WHOLE_FILE_LIMIT)
+    }
+}
+
+impl WholeFiles {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            state: Mutex::new(WholeFilesState {
+                limit,
+                used: 0,
+                files: HashMap::new(),
+                endpoints: HashSet::new(),
+            }),
+        }
+    }
+
+    /// Set the largest object that may be downloaded in full, which is also the
+    /// memory kept for downloaded objects.
+    pub fn set_limit(&self, limit: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.limit = limit;
+        if state.used > limit {
+            state.files.clear();
+            state.used = 0;
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit
+    }
+
+    pub fn ignores_ranges(&self, endpoint: &str) -> bool {
+        self.state.lock().unwrap().endpoints.contains(endpoint)
+    }
+
+    pub fn get(&self, key: &str) -> Option<Bytes> {
+        self.state.lock().unwrap().files.get(key).cloned()
+    }
+
+    /// Remember that `endpoint` ignores ranges and keep `data` if it fits.
+    pub fn insert(&self, endpoint: &str, key: &str, data: Bytes) {
+        let mut state = self.state.lock().unwrap();
+        state.endpoints.insert(endpoint.to_string());
+        if data.len() > state.limit {
+            return;
+        }
+        if state.used + data.len() > state.limit {
+            state.files.clear();
+            state.used = 0;
+        }
+        state.used += data.len();
+        if let Some(previous) = state.files.insert(key.to_string(), data) {
+            state.used -= previous.len();
+        }
+    }
+
+    pub fn invalidate(&self, key: &str) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(previous) = state.files.remove(key) {
+            state.used -= previous.len();
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.files.clear();
+        state.used = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whole_files() {
+        let files = WholeFiles::new(10);
+        assert!(!files.ignores_ranges("http://a:80"));
+
+        files.insert(
+            "http://a:80",
+            "http://a:80/x",
+            Bytes::from_static(b"123456"),
+        );
+        assert!(files.ignores_ranges("http://a:80"));
+        assert_eq!(files.get("http://a:80/x").unwrap().len(), 6);
+
+        // over the budget, older files make room
+        files.insert("http://a:80", "http://a:80/y", Bytes::from_static(b"12345"));
+        assert!(files.get("http://a:80/x").is_none());
+        assert!(files.get("http://a:80/y").is_some());
+
+        // too large to keep, but the endpoint is still remembered
+        files.insert("http://b:80", "http://b:80/z", Bytes::from(vec![0; 11]));
+        assert!(files.get("http://b:80/z").is_none());
+        assert!(files.ignores_ranges("http://b:80"));
+    }
+}
@@ -0,0 +1,306 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Worker mode: run a [`DataFusionContext`] inside a dedicated Web Worker and serve
+//! its API over `postMessage`, so heavy queries never block the page.
+//!
+//! Requests are `{id, method, args}`. Each gets one reply, `{id, result}` or
+//! `{id, error: {name, message}}`. Byte results are transferred, not copied. The
+//! page side is `js/worker-client.js`.
+//!
+//! The methods served are `execute_sql`, `execute_sql_bytes`, `export_parquet`,
+//! `register_csv`, `append_csv`, `append_json`, `append_ipc`, `last_query_stats`,
+//! `set_result_format`, `set_s3_config` and `reset`, those of disabled features
+//! excepted. Their arguments are the same as on the page, `set_result_format` takes
+//! the numeric `ResultFormat` value.
+
+use std::cell::{Ref, RefCell, RefMut};
+use std::ops::Deref;
+use std::rc::Rc;
+
+use js_sys::{Array, Function, Object, Reflect, Uint8Array};
+use wasm_bindgen::convert::TryFromJsValue;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::core::DataFusionContext;
+use crate::error::{Result, WasmError};
+use crate::result_format::ResultFormat;
+
+/// Serve a new context to the page that created this worker. Call once, from the
+/// worker's script, after the module is initialized.
+#[wasm_bindgen]
+pub fn serve_worker() -> Result<()> {
+    let global = js_sys::global();
+    let post_message: Function = Reflect::get(&global, &"postMessage".into())
+        .ok()
+        .and_then(|post_message| post_message.dyn_into().ok())
+        .ok_or_else(|| WasmError::Other("serve_worker must run in a Web Worker".to_string()))?;
+
+    let context = Rc::new(RefCell::new(DataFusionContext::new()));
+    let on_message = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+        let context = context.clone();
+        let post_message = post_message.clone();
+        let global = js_sys::global();
+        wasm_bindgen_futures::spawn_local(async move {
+            let data = Reflect::get(&event, &"data".into()).unwrap_or(JsValue::UNDEFINED);
+            let id = Reflect::get(&data, &"id".into()).unwrap_or(JsValue::UNDEFINED);
+            let reply = Object::new();
+            let _ = Reflect::set(&reply, &"id".into(), &id);
+            let transfer = Array::new();
+            match dispatch(&context, &data).await {
+                Ok(result) => {
+                    if let Some(bytes) = result.dyn_ref::<Uint8Array>() {
+                        transfer.push(&bytes.buffer());
+                    }
+                    let _ = Reflect::set(&reply, &"result".into(), &result);
+                }
+                Err(err) => {
+                    let error = Object::new();
+                    let _ = Reflect::set(&error, &"name".into(), &error_name(&err).into());
+                    let _ = Reflect::set(&error, &"message".into(), &err.to_string().into());
+                    let _ = Reflect::set(&reply, &"error".into(), &error);
+                }
+            }
+            let _ = post_message.call2(&global, &reply, &transfer);
+        });
+    });
+    Reflect::set(&global, &"onmessage".into(), on_message.as_ref())
+        .map_err(|_| WasmError::Other("failed to install the message handler".to_string()))?;
+    // the handler lives as long as the worker
+    on_message.forget();
+    Ok(())
+}
+
+/// A method of [`DataFusionContext`] served to the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+    ExecuteSql,
+    ExecuteSqlBytes,
+    #[cfg(feature = "parquet")]
+    ExportParquet,
+    #[cfg(feature = "csv")]
+    RegisterCsv,
+    #[cfg(feature = "csv")]
+    AppendCsv,
+    #[cfg(feature = "json")]
+    AppendJson,
+    AppendIpc,
+    LastQueryStats,
+    SetResultFormat,
+    SetS3Config,
+    Reset,
+}
+
+impl Method {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "execute_sql" => Method::ExecuteSql,
+            "execute_sql_bytes" => Method::ExecuteSqlBytes,
+            #[cfg(feature = "parquet")]
+            "export_parquet" => Method::ExportParquet,
+            #[cfg(feature = "csv")]
+            "register_csv" => Method::RegisterCsv,
+            #[cfg(feature = "csv")]
+            "append_csv" => Method::AppendCsv,
+            #[cfg(feature = "json")]
+            "append_json" => Method::AppendJson,
+            "append_ipc" => Method::AppendIpc,
+            "last_query_stats" => Method::LastQueryStats,
+            "set_result_format" => Method::SetResultFormat,
+            "set_s3_config" => Method::SetS3Config,
+            "reset" => Method::Reset,
+            _ => return Err(WasmError::Other(format!("unknown method {name}"))),
+        })
+    }
+
+    /// Whether the method takes `&mut self`.
+    fn is_mut(self) -> bool {
+        matches!(
+            self,
+            Method::SetResultFormat | Method::SetS3Config | Method::Reset
+        )
+    }
+}
+
+/// The context borrowed for one request.
+enum Borrowed<'a> {
+    Shared(Ref<'a, DataFusionContext>),
+    Exclusive(RefMut<'a, DataFusionContext>),
+}
+
+impl Borrowed<'_> {
+    fn exclusive(&mut self) -> &mut DataFusionContext {
+        match self {
+            Borrowed::Exclusive(context) => context,
+            Borrowed::Shared(_) => unreachable!("`&mut` methods borrow the context exclusively"),
+        }
+    }
+}
+
+impl Deref for Borrowed<'_> {
+    type Target = DataFusionContext;
+
+    fn deref(&self) -> &DataFusionContext {
+        match self {
+            Borrowed::Shared(context) => context,
+            Borrowed::Exclusive(context) => context,
+        }
+    }
+}
+
+/// Borrow `context` for `method`. The borrow is held until the call finishes, so
+/// methods taking `&mut self` fail while another request is running, as calling them
+/// on the page would.
+fn borrow(context: &RefCell<DataFusionContext>, method: Method) -> Result<Borrowed<'_>> {
+    if method.is_mut() {
+        context
+            .try_borrow_mut()
+            .map(Borrowed::Exclusive)
+            .map_err(busy)
+    } else {
+        context.try_borrow().map(Borrowed::Shared).map_err(busy)
+    }
+}
+
+/// Run the method named in request `data`.
+// holding the shared borrow across awaits is what makes `&mut` calls fail while busy
+#[allow(clippy::await_holding_refcell_ref)]
+async fn dispatch(context: &RefCell<DataFusionContext>, data: &JsValue) -> Result<JsValue> {
+    let name = Reflect::get(data, &"method".into())
+        .ok()
+        .and_then(|method| method.as_string())
+        .ok_or_else(|| WasmError::Other("request has no method".to_string()))?;
+    let method = Method::parse(&name)?;
+    let args = Reflect::get(data, &"args".into())
+        .ok()
+        .and_then(|args| args.dyn_into::<Array>().ok())
+        .unwrap_or_default();
+    let arg = |index: u32| args.get(index);
+    let string = |index: u32| {
+        arg(index)
+            .as_string()
+            .ok_or_else(|| WasmError::Other(format!("{name}: argument {index} must be a string")))
+    };
+    let optional_string = |index: u32| arg(index).as_string();
+
+    let mut context = borrow(context, method)?;
+    match method {
+        Method::ExecuteSql => Ok(context.execute_sql(string(0)?, arg(1)).await?.into()),
+        Method::ExecuteSqlBytes => Ok(context.execute_sql_bytes(string(0)?).await?.into()),
+        #[cfg(feature = "parquet")]
+        Method::ExportParquet => Ok(context
+            .export_parquet(string(0)?, optional_string(1))
+            .await?
+            .into()),
+        #[cfg(feature = "csv")]
+        Method::RegisterCsv => {
+            context
+                .register_csv(string(0)?, string(1)?, optional_string(2))
+                .await?;
+            Ok(JsValue::UNDEFINED)
+        }
+        #[cfg(feature = "csv")]
+        Method::AppendCsv => {
+            let has_header = arg(2).as_bool().unwrap_or(true);
+            context
+                .append_csv(string(0)?, string(1)?, has_header)
+                .await?;
+            Ok(JsValue::UNDEFINED)
+        }
+        #[cfg(feature = "json")]
+        Method::AppendJson => {
+            context.append_json(string(0)?, string(1)?).await?;
+            Ok(JsValue::UNDEFINED)
+        }
+        Method::AppendIpc => {
+            let data = arg(1)
+                .dyn_into::<Uint8Array>()
+                .map_err(|_| WasmError::Other("append_ipc: data must be a Uint8Array".into()))?;
+            context.append_ipc(string(0)?, data.to_vec()).await?;
+            Ok(JsValue::UNDEFINED)
+        }
+        Method::LastQueryStats => Ok(context.last_query_stats().into()),
+        Method::SetResultFormat => {
+            let format = result_format(&arg(0))?;
+            context.exclusive().set_result_format(format);
+            Ok(JsValue::UNDEFINED)
+        }
+        Method::SetS3Config => {
+            context.exclusive().set_s3_config(
+                string(0)?,
+                string(1)?,
+                string(2)?,
+                string(3)?,
+                string(4)?,
+            );
+            Ok(JsValue::UNDEFINED)
+        }
+        Method::Reset => {
+            context.exclusive().reset();
+            Ok(JsValue::UNDEFINED)
+        }
+    }
+}
+
+/// A `ResultFormat` as sent by the page, its numeric value in the generated bindings.
+fn result_format(value: &JsValue) -> Result<ResultFormat> {
+    ResultFormat::try_from_js_value(value.clone())
+        .map_err(|_| WasmError::Other(format!("invalid result format {value:?}")))
+}
+
+fn busy<E>(_: E) -> WasmError {
+    WasmError::Other("the context is busy with another request".to_string())
+}
+
+/// The `name` the page's `Error` gets, matching the errors thrown outside worker mode.
+fn error_name(err: &WasmError) -> &'static str {
+    match err {
+        WasmError::QuotaExceeded { .. } => "QuotaExceededError",
+        WasmError::ResourceExhausted { .. } => "ResourceLimitError",
+        WasmError::StatementRejected { .. } => "StatementRejectedError",
+        _ => "Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_method() {
+        assert_eq!(Method::parse("execute_sql").unwrap(), Method::ExecuteSql);
+        assert_eq!(Method::parse("reset").unwrap(), Method::Reset);
+        assert!(Method::parse("free").is_err());
+    }
+
+    #[test]
+    fn test_mut_methods_rejected_while_busy() {
+        let context = RefCell::new(DataFusionContext::new());
+        let running = borrow(&context, Method::ExecuteSql).unwrap();
+        assert!(borrow(&context, Method::LastQueryStats).is_ok());
+        assert!(matches!(
+            borrow(&context, Method::Reset),
+            Err(WasmError::Other(message)) if message.contains("busy")
+        ));
+
+        drop(running);
+        let mut resetting = borrow(&context, Method::Reset).unwrap();
+        resetting.exclusive().reset();
+        assert!(borrow(&context, Method::ExecuteSql).is_err());
+    }
+}